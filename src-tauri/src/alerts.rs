@@ -0,0 +1,202 @@
+// src-tauri/src/alerts.rs
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How far a metric must fall back below its threshold, in percent of the threshold, before
+/// its alert clears. Keeps a value hovering at the threshold from flapping.
+pub const DEFAULT_HYSTERESIS_PERCENT: f64 = 10.0;
+
+/// How long an abort condition must hold before it stops the test, when unset.
+pub const DEFAULT_SUSTAINED_SECONDS: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AlertMetric {
+	Latency,
+	PacketLoss,
+	Jitter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AlertKind {
+	/// The metric went above its threshold
+	Alert,
+	/// The metric recovered below the threshold minus the hysteresis
+	Clear,
+}
+
+/// Limits that raise an alert while a test runs. Unset metrics aren't watched.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct AlertThresholds {
+	pub latency_ms: Option<f64>,
+	pub packet_loss_percentage: Option<f64>,
+	pub jitter_ms: Option<f64>,
+	/// Defaults to `DEFAULT_HYSTERESIS_PERCENT`
+	pub hysteresis_percent: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AlertEvent {
+	pub metric: AlertMetric,
+	pub kind: AlertKind,
+	pub value: f64,
+	pub threshold: f64,
+}
+
+pub type AlertSink = Arc<dyn Fn(AlertEvent) + Send + Sync>;
+
+/// Stop the test once loss or latency stays above its limit for a whole window. Unlike the
+/// circuit breaker a single bad sample doesn't trip it. Unset metrics aren't watched.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SustainedAbort {
+	/// Checked against the loss of the most recent packets, not the whole run's
+	pub packet_loss_percentage: Option<f64>,
+	pub latency_ms: Option<f64>,
+	/// Defaults to `DEFAULT_SUSTAINED_SECONDS`
+	pub sustained_seconds: Option<u32>,
+}
+
+/// Recent values of the watched metrics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlertSample {
+	pub latency_ms: f64,
+	pub packet_loss_percentage: f64,
+	pub jitter_ms: f64,
+}
+
+/// Turns a stream of samples into alert/clear transitions.
+pub struct AlertTracker {
+	thresholds: AlertThresholds,
+	active: Vec<AlertMetric>,
+}
+
+impl AlertTracker {
+	pub fn new(thresholds: AlertThresholds) -> Self {
+		Self { thresholds, active: Vec::new() }
+	}
+
+	/// Events for every metric whose alert state changed with `sample`.
+	pub fn check(&mut self, sample: AlertSample) -> Vec<AlertEvent> {
+		let hysteresis = self.thresholds.hysteresis_percent.unwrap_or(DEFAULT_HYSTERESIS_PERCENT).clamp(0.0, 100.0);
+		let watched = [
+			(AlertMetric::Latency, self.thresholds.latency_ms, sample.latency_ms),
+			(AlertMetric::PacketLoss, self.thresholds.packet_loss_percentage, sample.packet_loss_percentage),
+			(AlertMetric::Jitter, self.thresholds.jitter_ms, sample.jitter_ms),
+		];
+
+		let mut events = Vec::new();
+		for (metric, threshold, value) in watched {
+			let Some(threshold) = threshold else {
+				continue;
+			};
+			let active = self.active.contains(&metric);
+			let kind = if !active && value > threshold {
+				self.active.push(metric);
+				AlertKind::Alert
+			} else if active && value < threshold * (1.0 - hysteresis / 100.0) {
+				self.active.retain(|m| *m != metric);
+				AlertKind::Clear
+			} else {
+				continue;
+			};
+			events.push(AlertEvent { metric, kind, value, threshold });
+		}
+		events
+	}
+}
+
+/// Follows how long a `SustainedAbort` condition has held without a break.
+pub struct SustainedTracker {
+	abort: SustainedAbort,
+	unhealthy_since: Option<Duration>,
+}
+
+impl SustainedTracker {
+	pub fn new(abort: SustainedAbort) -> Self {
+		Self { abort, unhealthy_since: None }
+	}
+
+	/// Why the test should stop, once the samples up to `elapsed` into the test have been
+	/// unhealthy for the whole window. A healthy sample starts the window over.
+	pub fn check(&mut self, sample: AlertSample, elapsed: Duration) -> Option<String> {
+		let mut conditions = Vec::new();
+		if let Some(max) = self.abort.packet_loss_percentage.filter(|max| sample.packet_loss_percentage > *max) {
+			conditions.push(format!("packet loss {:.1}% above {}%", sample.packet_loss_percentage, max));
+		}
+		if let Some(max) = self.abort.latency_ms.filter(|max| sample.latency_ms > *max) {
+			conditions.push(format!("latency {:.1} ms above {} ms", sample.latency_ms, max));
+		}
+		if conditions.is_empty() {
+			self.unhealthy_since = None;
+			return None;
+		}
+
+		let since = *self.unhealthy_since.get_or_insert(elapsed);
+		let window = self.abort.sustained_seconds.unwrap_or(DEFAULT_SUSTAINED_SECONDS);
+		(elapsed - since >= Duration::from_secs(window.into()))
+			.then(|| format!("{} for more than {} s", conditions.join(" and "), window))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn latency(latency_ms: f64) -> AlertSample {
+		AlertSample { latency_ms, ..AlertSample::default() }
+	}
+
+	#[test]
+	fn test_latency_alert_and_clear() {
+		let mut tracker = AlertTracker::new(AlertThresholds { latency_ms: Some(100.0), ..AlertThresholds::default() });
+
+		assert!(tracker.check(latency(20.0)).is_empty());
+		let events = tracker.check(latency(150.0));
+		assert_eq!(events, vec![AlertEvent { metric: AlertMetric::Latency, kind: AlertKind::Alert, value: 150.0, threshold: 100.0 }]);
+		// Still over, nothing new
+		assert!(tracker.check(latency(180.0)).is_empty());
+
+		// Dipping just under the threshold is within the hysteresis band
+		assert!(tracker.check(latency(95.0)).is_empty());
+		assert!(tracker.check(latency(105.0)).is_empty());
+
+		let events = tracker.check(latency(40.0));
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].kind, AlertKind::Clear);
+		assert_eq!(events[0].metric, AlertMetric::Latency);
+	}
+
+	#[test]
+	fn test_unset_thresholds_are_ignored() {
+		let mut tracker = AlertTracker::new(AlertThresholds { packet_loss_percentage: Some(5.0), ..AlertThresholds::default() });
+		let sample = AlertSample { latency_ms: 900.0, packet_loss_percentage: 10.0, jitter_ms: 300.0 };
+		let events = tracker.check(sample);
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].metric, AlertMetric::PacketLoss);
+	}
+
+	#[test]
+	fn test_sustained_condition_aborts_but_spike_does_not() {
+		let abort = SustainedAbort { latency_ms: Some(100.0), sustained_seconds: Some(5), ..SustainedAbort::default() };
+		let at = |seconds: f64| Duration::from_secs_f64(seconds);
+
+		// A two-second spike, then recovery: the window starts over
+		let mut tracker = SustainedTracker::new(abort.clone());
+		assert_eq!(tracker.check(latency(300.0), at(1.0)), None);
+		assert_eq!(tracker.check(latency(300.0), at(3.0)), None);
+		assert_eq!(tracker.check(latency(20.0), at(3.5)), None);
+		assert_eq!(tracker.check(latency(300.0), at(7.0)), None);
+		assert_eq!(tracker.check(latency(20.0), at(8.0)), None);
+
+		// Staying high for the full window trips it
+		let mut tracker = SustainedTracker::new(abort);
+		for second in 1..6 {
+			assert_eq!(tracker.check(latency(300.0), at(second as f64)), None);
+		}
+		let reason = tracker.check(latency(300.0), at(6.0)).expect("five seconds over the limit should abort");
+		assert_eq!(reason, "latency 300.0 ms above 100 ms for more than 5 s");
+	}
+}
@@ -0,0 +1,117 @@
+// src-tauri/src/aliases.rs
+
+use pnet::datalink::MacAddr;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use thiserror::Error;
+
+use crate::scanner::Device;
+
+pub const ALIASES_FILE: &str = "device_aliases.json";
+const MAX_ALIAS_LENGTH: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum AliasError {
+	#[error("Invalid MAC address: {0}")]
+	InvalidMac(String),
+	#[error("Name is longer than {MAX_ALIAS_LENGTH} characters")]
+	NameTooLong,
+	#[error("Could not save aliases: {0}")]
+	IoError(#[from] std::io::Error),
+}
+
+/// Friendly names for devices ("Dad's laptop"), keyed by MAC so they follow a device across
+/// DHCP leases and restarts. Persisted to disk on every change.
+#[derive(Debug)]
+pub struct DeviceAliases {
+	path: PathBuf,
+	aliases: Mutex<BTreeMap<String, String>>,
+}
+
+impl DeviceAliases {
+	/// Loads the aliases saved at `path`; a missing or unreadable file starts empty.
+	pub fn new(path: PathBuf) -> Self {
+		let aliases = std::fs::read_to_string(&path)
+			.ok()
+			.and_then(|json| serde_json::from_str(&json).ok())
+			.unwrap_or_default();
+		Self { path, aliases: Mutex::new(aliases) }
+	}
+
+	/// Names the device with `mac`. A blank name removes its alias.
+	pub fn set(&self, mac: &str, name: &str) -> Result<(), AliasError> {
+		let mac = normalize_mac(mac)?;
+		let name = name.trim();
+		if name.chars().count() > MAX_ALIAS_LENGTH {
+			return Err(AliasError::NameTooLong);
+		}
+
+		let mut aliases = self.aliases.lock().unwrap();
+		if name.is_empty() {
+			aliases.remove(&mac);
+		} else {
+			aliases.insert(mac, name.to_string());
+		}
+		self.path.parent().map_or(Ok(()), std::fs::create_dir_all)?;
+		std::fs::write(&self.path, serde_json::to_string_pretty(&*aliases).map_err(std::io::Error::other)?)?;
+		Ok(())
+	}
+
+	pub fn get(&self, mac: &str) -> Option<String> {
+		let mac = normalize_mac(mac).ok()?;
+		self.aliases.lock().unwrap().get(&mac).cloned()
+	}
+
+	/// Every alias, by lowercase MAC.
+	pub fn all(&self) -> BTreeMap<String, String> {
+		self.aliases.lock().unwrap().clone()
+	}
+
+	/// Sets each device's friendly name from the store, clearing names since removed.
+	pub fn apply(&self, devices: &mut [Device]) {
+		for device in devices {
+			let name = self.get(device.mac_address());
+			device.set_friendly_name(name);
+		}
+	}
+}
+
+fn normalize_mac(mac: &str) -> Result<String, AliasError> {
+	mac.trim()
+		.replace('-', ":")
+		.parse::<MacAddr>()
+		.map(|mac| mac.to_string())
+		.map_err(|_| AliasError::InvalidMac(mac.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_alias_survives_restart_and_reaches_scan_results() {
+		let dir = std::env::temp_dir().join(format!("cutecatnet-aliases-{}", std::process::id()));
+		let path = dir.join(ALIASES_FILE);
+		let aliases = DeviceAliases::new(path.clone());
+		aliases.set("AA-BB-CC-DD-EE-01", "Dad's laptop").unwrap();
+		assert!(matches!(aliases.set("not a mac", "x"), Err(AliasError::InvalidMac(_))));
+		assert!(matches!(aliases.set("aa:bb:cc:dd:ee:02", &"x".repeat(65)), Err(AliasError::NameTooLong)));
+
+		// A later scan, after a restart, picks the name up by MAC
+		let reloaded = DeviceAliases::new(path);
+		let mut devices = vec![
+			Device::sample("192.168.1.20", "aa:bb:cc:dd:ee:01", "eth0"),
+			Device::sample("192.168.1.21", "aa:bb:cc:dd:ee:02", "eth0"),
+		];
+		reloaded.apply(&mut devices);
+		assert_eq!(devices[0].friendly_name(), Some("Dad's laptop"));
+		assert_eq!(devices[1].friendly_name(), None);
+
+		reloaded.set("aa:bb:cc:dd:ee:01", " ").unwrap();
+		reloaded.apply(&mut devices);
+		assert_eq!(devices[0].friendly_name(), None);
+		assert!(reloaded.all().is_empty());
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -0,0 +1,129 @@
+// src-tauri/src/arp_cache.rs
+
+use log::debug;
+use pnet::datalink::MacAddr;
+use std::net::Ipv4Addr;
+use std::process::Command;
+
+/// (IP, MAC) pairs from the operating system's ARP table. Empty when it can't be read.
+pub fn read_arp_cache() -> Vec<(Ipv4Addr, MacAddr)> {
+	match arp_table_text() {
+		Some(text) => parse_arp_table(&text),
+		None => {
+			debug!("ARP cache unavailable on this system");
+			Vec::new()
+		}
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn arp_table_text() -> Option<String> {
+	std::fs::read_to_string("/proc/net/arp")
+		.ok()
+		.or_else(|| command_output("ip", &["neigh"]))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn arp_table_text() -> Option<String> {
+	command_output("arp", &["-a"])
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+	let output = Command::new(program).args(args).output().ok()?;
+	output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses any of the formats we read: `/proc/net/arp`, `ip neigh`, and the `arp -a` output
+/// of Windows and macOS/BSD. Each line is searched for an IPv4 address and a MAC, so column
+/// order doesn't matter; incomplete, broadcast and multicast entries are dropped.
+pub fn parse_arp_table(text: &str) -> Vec<(Ipv4Addr, MacAddr)> {
+	text.lines()
+		.filter_map(|line| {
+			let mut tokens = line.split_whitespace().map(|token| token.trim_matches(|c| c == '(' || c == ')'));
+			let ip = tokens.clone().find_map(|token| token.parse::<Ipv4Addr>().ok())?;
+			let mac = tokens.find_map(parse_mac)?;
+			is_unicast_host(mac).then_some((ip, mac))
+		})
+		.collect()
+}
+
+/// Accepts `aa:bb:cc:dd:ee:ff`, `aa-bb-cc-dd-ee-ff` and macOS's zero-stripped `a:b:c:d:e:f`.
+fn parse_mac(token: &str) -> Option<MacAddr> {
+	let parts: Vec<&str> = token.split([':', '-']).collect();
+	if parts.len() != 6 || parts.iter().any(|part| part.is_empty() || part.len() > 2) {
+		return None;
+	}
+
+	let mut octets = [0u8; 6];
+	for (octet, part) in octets.iter_mut().zip(&parts) {
+		*octet = u8::from_str_radix(part, 16).ok()?;
+	}
+	Some(MacAddr::new(octets[0], octets[1], octets[2], octets[3], octets[4], octets[5]))
+}
+
+fn is_unicast_host(mac: MacAddr) -> bool {
+	// The least significant bit of the first octet marks group (multicast/broadcast) addresses
+	mac != MacAddr::zero() && mac.0 & 0x01 == 0
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pair(ip: &str, mac: &str) -> (Ipv4Addr, MacAddr) {
+		(ip.parse().unwrap(), mac.parse().unwrap())
+	}
+
+	#[test]
+	fn test_parse_proc_net_arp() {
+		let text = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:01     *        eth0
+192.168.1.77     0x1         0x0         00:00:00:00:00:00     *        eth0
+192.168.1.20     0x1         0x2         aa:bb:cc:dd:ee:20     *        eth0
+";
+		assert_eq!(
+			parse_arp_table(text),
+			vec![pair("192.168.1.1", "aa:bb:cc:dd:ee:01"), pair("192.168.1.20", "aa:bb:cc:dd:ee:20")]
+		);
+	}
+
+	#[test]
+	fn test_parse_ip_neigh() {
+		let text = "\
+192.168.1.1 dev eth0 lladdr aa:bb:cc:dd:ee:01 REACHABLE
+192.168.1.33 dev eth0 FAILED
+fe80::1 dev eth0 lladdr aa:bb:cc:dd:ee:01 router STALE
+192.168.1.20 dev eth0 lladdr aa:bb:cc:dd:ee:20 STALE
+";
+		assert_eq!(
+			parse_arp_table(text),
+			vec![pair("192.168.1.1", "aa:bb:cc:dd:ee:01"), pair("192.168.1.20", "aa:bb:cc:dd:ee:20")]
+		);
+	}
+
+	#[test]
+	fn test_parse_windows_arp() {
+		let text = "\
+Interface: 192.168.1.10 --- 0x7
+  Internet Address      Physical Address      Type
+  192.168.1.1           aa-bb-cc-dd-ee-01     dynamic
+  192.168.1.255         ff-ff-ff-ff-ff-ff     static
+  224.0.0.22            01-00-5e-00-00-16     static
+";
+		assert_eq!(parse_arp_table(text), vec![pair("192.168.1.1", "aa:bb:cc:dd:ee:01")]);
+	}
+
+	#[test]
+	fn test_parse_macos_arp() {
+		let text = "\
+? (192.168.1.1) at aa:bb:cc:dd:ee:1 on en0 ifscope [ethernet]
+? (192.168.1.44) at (incomplete) on en0 ifscope [ethernet]
+router.lan (192.168.1.2) at 0:11:22:33:44:55 on en0 ifscope permanent [ethernet]
+";
+		assert_eq!(
+			parse_arp_table(text),
+			vec![pair("192.168.1.1", "aa:bb:cc:dd:ee:01"), pair("192.168.1.2", "00:11:22:33:44:55")]
+		);
+	}
+}
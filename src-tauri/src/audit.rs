@@ -0,0 +1,87 @@
+// src-tauri/src/audit.rs
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::stresser::StressTestConfig;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+	Start,
+	Stop,
+	Complete,
+	Fail,
+	DeadMansSwitch,
+	/// A start request refused by validation, e.g. during a target's cooldown.
+	Rejected,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+	pub timestamp: u64,
+	pub event: AuditEvent,
+	pub test_id: Option<String>,
+	pub target: String,
+	pub outcome: String,
+	pub config: Option<StressTestConfig>,
+}
+
+impl AuditEntry {
+	pub fn new(event: AuditEvent, test_id: Option<&str>, target: &str, outcome: impl Into<String>) -> Self {
+		Self {
+			timestamp: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map(|now| now.as_millis() as u64)
+				.unwrap_or_default(),
+			event,
+			test_id: test_id.map(str::to_string),
+			target: target.to_string(),
+			outcome: outcome.into(),
+			config: None,
+		}
+	}
+
+	pub fn with_config(mut self, config: &StressTestConfig) -> Self {
+		self.config = Some(config.clone());
+		self
+	}
+}
+
+/// Append-only JSON-lines record of stress test lifecycle events. Entries are never rewritten.
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+	path: PathBuf,
+}
+
+impl AuditLog {
+	pub fn new(path: PathBuf) -> Self {
+		Self { path }
+	}
+
+	pub fn record(&self, entry: &AuditEntry) {
+		if let Err(e) = self.append(entry) {
+			warn!("Failed to write audit entry to {}: {}", self.path.display(), e);
+		}
+	}
+
+	fn append(&self, entry: &AuditEntry) -> io::Result<()> {
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let line = serde_json::to_string(entry).map_err(io::Error::other)?;
+		let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+		writeln!(file, "{}", line)
+	}
+
+	pub fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+		Ok(fs::read_to_string(&self.path)?
+			.lines()
+			.filter_map(|line| serde_json::from_str(line).ok())
+			.collect())
+	}
+}
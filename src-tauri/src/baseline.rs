@@ -0,0 +1,77 @@
+// src-tauri/src/baseline.rs
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::stresser::TestType;
+
+pub const BASELINES_FILE: &str = "baselines.json";
+
+/// The test each target's new results are compared against, by target IP and test type, since
+/// a flood and a latency test of the same host aren't comparable. Read from disk on every
+/// call, like the history, so the engine and a finishing test never disagree.
+pub struct BaselineStore {
+	path: PathBuf,
+}
+
+impl BaselineStore {
+	pub fn new(path: PathBuf) -> Self {
+		Self { path }
+	}
+
+	pub fn in_dir(dir: &Path) -> Self {
+		Self::new(dir.join(BASELINES_FILE))
+	}
+
+	/// The baseline `test_type` test of `target_ip`, if one has been captured or pinned.
+	pub fn get(&self, target_ip: &str, test_type: &TestType) -> Option<String> {
+		self.load().remove(target_ip)?.remove(&type_key(test_type))
+	}
+
+	/// Makes `test_id` the baseline `test_type` test of `target_ip`, replacing any earlier one.
+	pub fn set(&self, target_ip: &str, test_type: &TestType, test_id: &str) -> io::Result<()> {
+		let mut baselines = self.load();
+		baselines.entry(target_ip.to_string()).or_default().insert(type_key(test_type), test_id.to_string());
+		self.path.parent().map_or(Ok(()), std::fs::create_dir_all)?;
+		std::fs::write(&self.path, serde_json::to_string_pretty(&baselines).map_err(io::Error::other)?)
+	}
+
+	/// Every baseline, by target then test type; a missing or unreadable file has none.
+	fn load(&self) -> BTreeMap<String, BTreeMap<String, String>> {
+		std::fs::read_to_string(&self.path)
+			.ok()
+			.and_then(|json| serde_json::from_str(&json).ok())
+			.unwrap_or_default()
+	}
+}
+
+/// `test_type` as it's written everywhere else on disk.
+fn type_key(test_type: &TestType) -> String {
+	serde_json::to_value(test_type).ok().and_then(|key| key.as_str().map(str::to_string)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_baselines_persist_per_target() {
+		let dir = std::env::temp_dir().join(format!("cutecatnet-baselines-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		let store = BaselineStore::in_dir(&dir);
+		assert_eq!(store.get("192.168.1.50", &TestType::LatencyTest), None);
+
+		store.set("192.168.1.50", &TestType::LatencyTest, "test_1").unwrap();
+		store.set("192.168.1.60", &TestType::LatencyTest, "test_2").unwrap();
+		store.set("192.168.1.50", &TestType::LatencyTest, "test_3").unwrap();
+		store.set("192.168.1.50", &TestType::PingFlood, "test_4").unwrap();
+
+		let reopened = BaselineStore::in_dir(&dir);
+		assert_eq!(reopened.get("192.168.1.50", &TestType::LatencyTest).as_deref(), Some("test_3"));
+		assert_eq!(reopened.get("192.168.1.50", &TestType::PingFlood).as_deref(), Some("test_4"));
+		assert_eq!(reopened.get("192.168.1.60", &TestType::LatencyTest).as_deref(), Some("test_2"));
+		assert_eq!(reopened.get("192.168.1.60", &TestType::PingFlood), None);
+		let _ = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -0,0 +1,165 @@
+// src-tauri/src/batch.rs
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::scanner::{ScanError, ScanOptions, ScanSummary};
+use crate::stresser::{ErrorResponse, StressTestConfig, StressTestEngine};
+
+/// Longest a single `wait` step may pause the batch.
+pub const MAX_BATCH_WAIT: Duration = Duration::from_secs(300);
+
+/// One operation of a scripted workflow.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+	Scan {
+		#[serde(default)]
+		options: ScanOptions,
+	},
+	Validate {
+		ip: String,
+	},
+	StartTest {
+		config: StressTestConfig,
+	},
+	Wait {
+		ms: u64,
+	},
+	Stop,
+}
+
+impl BatchOperation {
+	fn name(&self) -> &'static str {
+		match self {
+			BatchOperation::Scan { .. } => "scan",
+			BatchOperation::Validate { .. } => "validate",
+			BatchOperation::StartTest { .. } => "start_test",
+			BatchOperation::Wait { .. } => "wait",
+			BatchOperation::Stop => "stop",
+		}
+	}
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct BatchStep {
+	#[serde(flatten)]
+	pub operation: BatchOperation,
+	/// Abort the rest of the batch if this step fails
+	#[serde(default)]
+	pub required: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchStepResult {
+	pub op: String,
+	pub ok: bool,
+	pub result: Option<serde_json::Value>,
+	pub error: Option<serde_json::Value>,
+}
+
+/// Runs `steps` in order, collecting each one's result or error. A failed step doesn't stop
+/// the batch unless it's `required`, in which case the steps after it are not run and have
+/// no result.
+///
+/// The engine is only locked for the duration of each step, so a long `wait` doesn't block
+/// `confirm_stress_alive` or a manual stop.
+pub async fn execute_batch<S, Fut>(steps: Vec<BatchStep>, engine: &Mutex<StressTestEngine>, scan: S) -> Vec<BatchStepResult>
+where
+	S: Fn(ScanOptions) -> Fut,
+	Fut: Future<Output = Result<ScanSummary, ScanError>>,
+{
+	let mut results = Vec::with_capacity(steps.len());
+	for step in steps {
+		let outcome = match step.operation.clone() {
+			BatchOperation::Scan { options } => scan(options).await.map(json).map_err(|e| json(e.to_string())),
+			BatchOperation::Validate { ip } => engine
+				.lock()
+				.await
+				.validate_target_ip(&ip)
+				.await
+				.map(|()| json(true))
+				.map_err(|e| json(ErrorResponse::from(e))),
+			BatchOperation::StartTest { config } => engine
+				.lock()
+				.await
+				.start_stress_test(config)
+				.await
+				.map(json)
+				.map_err(|e| json(ErrorResponse::from(e))),
+			BatchOperation::Wait { ms } => {
+				tokio::time::sleep(Duration::from_millis(ms).min(MAX_BATCH_WAIT)).await;
+				Ok(serde_json::Value::Null)
+			}
+			BatchOperation::Stop => engine
+				.lock()
+				.await
+				.stop_current_test()
+				.await
+				.map(|()| serde_json::Value::Null)
+				.map_err(|e| json(ErrorResponse::from(e))),
+		};
+
+		let failed = outcome.is_err();
+		let (result, error) = match outcome {
+			Ok(result) => (Some(result), None),
+			Err(error) => (None, Some(error)),
+		};
+		results.push(BatchStepResult { op: step.operation.name().to_string(), ok: !failed, result, error });
+		if failed && step.required {
+			break;
+		}
+	}
+	results
+}
+
+fn json<T: Serialize>(value: T) -> serde_json::Value {
+	serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Device;
+
+	fn steps(json: &str) -> Vec<BatchStep> {
+		serde_json::from_str(json).unwrap()
+	}
+
+	async fn fake_scan(_: ScanOptions) -> Result<ScanSummary, ScanError> {
+		Ok(ScanSummary {
+			devices: vec![Device::sample("192.168.1.20", "aa:aa:aa:aa:aa:01", "eth0")],
+			..ScanSummary::default()
+		})
+	}
+
+	#[tokio::test]
+	async fn test_scan_then_validate() {
+		let engine = Mutex::new(StressTestEngine::new());
+		let batch = steps(r#"[{"op": "scan"}, {"op": "validate", "ip": "192.168.1.20"}, {"op": "validate", "ip": "8.8.8.8"}]"#);
+
+		let results = execute_batch(batch, &engine, fake_scan).await;
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[0].op, "scan");
+		assert!(results[0].ok);
+		assert_eq!(results[0].result.as_ref().unwrap()["devices"][0]["ip_address"], "192.168.1.20");
+		assert_eq!(results[1].op, "validate");
+		assert_eq!(results[1].result, Some(serde_json::Value::Bool(true)));
+
+		// A failing optional step is reported without aborting
+		assert!(!results[2].ok);
+		assert_eq!(results[2].error.as_ref().unwrap()["code"], "INVALID_TARGET_IP");
+	}
+
+	#[tokio::test]
+	async fn test_required_failure_aborts() {
+		let engine = Mutex::new(StressTestEngine::new());
+		let batch = steps(r#"[{"op": "validate", "ip": "8.8.8.8", "required": true}, {"op": "scan"}]"#);
+
+		let results = execute_batch(batch, &engine, fake_scan).await;
+		assert_eq!(results.len(), 1);
+		assert!(!results[0].ok);
+	}
+}
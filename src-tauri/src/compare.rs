@@ -0,0 +1,148 @@
+// src-tauri/src/compare.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::stresser::{TestMetrics, TestResult};
+
+/// Changes smaller than this (in percent of the baseline) are reported as unchanged.
+const UNCHANGED_THRESHOLD_PERCENT: f64 = 1.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+	Better,
+	Worse,
+	Unchanged,
+}
+
+/// One metric of test B measured against test A.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MetricDelta {
+	pub metric: String,
+	pub a: f64,
+	pub b: f64,
+	pub absolute_delta: f64,
+	/// `None` when A is zero and a percentage is meaningless
+	pub percent_delta: Option<f64>,
+	pub verdict: Verdict,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TestComparison {
+	pub test_a: String,
+	pub test_b: String,
+	pub label_a: Option<String>,
+	pub label_b: Option<String>,
+	pub metrics: Vec<MetricDelta>,
+}
+
+/// Diffs the key metrics of `b` against `a`. Results without final metrics compare as zeros.
+pub fn compare_results(a: &TestResult, b: &TestResult) -> TestComparison {
+	let metrics_a = a.final_metrics.clone().unwrap_or_default();
+	let metrics_b = b.final_metrics.clone().unwrap_or_default();
+
+	// (name, value, lower is better)
+	let rows: [(&str, fn(&TestMetrics) -> f64, bool); 5] = [
+		("latency_ms", |m| m.latency_ms, true),
+		("latency_p95_ms", |m| m.latency_p95_ms, true),
+		("packet_loss_percentage", |m| m.packet_loss_percentage, true),
+		("jitter_ms", |m| m.jitter_ms, true),
+		("throughput_mbps", |m| m.throughput_mbps, false),
+	];
+
+	TestComparison {
+		test_a: a.test_id.clone(),
+		test_b: b.test_id.clone(),
+		label_a: a.label.clone(),
+		label_b: b.label.clone(),
+		metrics: rows
+			.iter()
+			.map(|(name, value, lower_is_better)| metric_delta(name, value(&metrics_a), value(&metrics_b), *lower_is_better))
+			.collect(),
+	}
+}
+
+fn metric_delta(metric: &str, a: f64, b: f64, lower_is_better: bool) -> MetricDelta {
+	let absolute_delta = b - a;
+	let percent_delta = (a != 0.0).then(|| absolute_delta / a.abs() * 100.0);
+
+	let unchanged = match percent_delta {
+		Some(percent) => percent.abs() < UNCHANGED_THRESHOLD_PERCENT,
+		None => absolute_delta == 0.0,
+	};
+	let verdict = if unchanged {
+		Verdict::Unchanged
+	} else if (absolute_delta < 0.0) == lower_is_better {
+		Verdict::Better
+	} else {
+		Verdict::Worse
+	};
+
+	MetricDelta {
+		metric: metric.to_string(),
+		a,
+		b,
+		absolute_delta,
+		percent_delta,
+		verdict,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn result(test_id: &str, latency_ms: f64, latency_p95_ms: f64, loss: f64, jitter_ms: f64, throughput_mbps: f64) -> TestResult {
+		TestResult {
+			final_metrics: Some(TestMetrics {
+				latency_ms,
+				latency_p95_ms,
+				packet_loss_percentage: loss,
+				jitter_ms,
+				throughput_mbps,
+				..TestMetrics::default()
+			}),
+			..TestResult::sample(test_id, "192.168.1.50")
+		}
+	}
+
+	#[test]
+	fn test_compare_canned_results() {
+		let before = result("test_a", 20.0, 40.0, 2.0, 5.0, 0.5);
+		let after = result("test_b", 15.0, 40.2, 0.0, 6.0, 0.75);
+
+		let comparison = compare_results(&before, &after);
+		assert_eq!(comparison.test_a, "test_a");
+		assert_eq!(comparison.test_b, "test_b");
+
+		let delta = |name: &str| comparison.metrics.iter().find(|m| m.metric == name).unwrap();
+
+		let latency = delta("latency_ms");
+		assert_eq!(latency.absolute_delta, -5.0);
+		assert_eq!(latency.percent_delta, Some(-25.0));
+		assert_eq!(latency.verdict, Verdict::Better);
+
+		// Within 1% of the baseline counts as no change
+		assert_eq!(delta("latency_p95_ms").verdict, Verdict::Unchanged);
+
+		let loss = delta("packet_loss_percentage");
+		assert_eq!(loss.percent_delta, Some(-100.0));
+		assert_eq!(loss.verdict, Verdict::Better);
+
+		let jitter = delta("jitter_ms");
+		assert_eq!(jitter.percent_delta, Some(20.0));
+		assert_eq!(jitter.verdict, Verdict::Worse);
+
+		// Higher throughput is the better direction
+		let throughput = delta("throughput_mbps");
+		assert_eq!(throughput.absolute_delta, 0.25);
+		assert_eq!(throughput.percent_delta, Some(50.0));
+		assert_eq!(throughput.verdict, Verdict::Better);
+	}
+
+	#[test]
+	fn test_zero_baseline_has_no_percentage() {
+		let delta = metric_delta("packet_loss_percentage", 0.0, 3.0, true);
+		assert_eq!(delta.percent_delta, None);
+		assert_eq!(delta.verdict, Verdict::Worse);
+	}
+}
@@ -0,0 +1,133 @@
+// src-tauri/src/device_export.rs
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::io;
+use std::path::Path;
+
+use crate::scanner::Device;
+
+/// Device fields written to a CSV export, in column order.
+const CSV_COLUMNS: [&str; 10] = [
+	"ip_address",
+	"mac_address",
+	"manufacturer",
+	"hostname",
+	"friendly_name",
+	"interface_name",
+	"source",
+	"vlan_id",
+	"mac_conflict",
+	"answered_on",
+];
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+	/// One row per device with a header line, for spreadsheets
+	Csv,
+	/// The devices as a JSON array, for scripts and other tools
+	Json,
+}
+
+/// `devices` rendered in `format`.
+pub fn render(devices: &[Device], format: ExportFormat) -> io::Result<String> {
+	match format {
+		ExportFormat::Json => serde_json::to_string_pretty(devices).map_err(io::Error::other),
+		ExportFormat::Csv => {
+			let mut csv = CSV_COLUMNS.join(",");
+			csv.push('\n');
+			for device in devices {
+				let fields = serde_json::to_value(device).map_err(io::Error::other)?;
+				let row: Vec<String> = CSV_COLUMNS.iter().map(|column| csv_field(&fields[*column])).collect();
+				csv.push_str(&row.join(","));
+				csv.push('\n');
+			}
+			Ok(csv)
+		}
+	}
+}
+
+/// Writes `devices` to `path` in `format`, creating its directory if needed.
+pub fn export_devices(devices: &[Device], format: ExportFormat, path: &Path) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		std::fs::create_dir_all(parent)?;
+	}
+	std::fs::write(path, render(devices, format)?)
+}
+
+/// One CSV cell: empty for a missing value, lists joined with ';', and quoted (RFC 4180)
+/// when the text holds a comma, quote or line break. Text a spreadsheet would run as a
+/// formula (hostnames and aliases come off the network) is prefixed with `'` and quoted.
+fn csv_field(value: &Value) -> String {
+	let text = match value {
+		Value::Null => String::new(),
+		Value::String(text) => text.clone(),
+		Value::Array(items) => items.iter().map(csv_field).collect::<Vec<_>>().join(";"),
+		other => other.to_string(),
+	};
+	if value.is_string() && text.starts_with(['=', '+', '-', '@']) {
+		format!("\"'{}\"", text.replace('"', "\"\""))
+	} else if text.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", text.replace('"', "\"\""))
+	} else {
+		text
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn devices() -> Vec<Device> {
+		let mut router: Device = serde_json::from_value(serde_json::json!({
+			"ip_address": "192.168.1.1",
+			"mac_address": "00:0f:a0:00:00:01",
+			"manufacturer": "Netgear, Inc.",
+			"hostname": "router.lan",
+			"interface_name": "eth0",
+			"mac_conflict": false,
+			"source": "LiveReply",
+			"friendly_name": null,
+			"answered_on": ["eth0", "wlan0"],
+			"vlan_id": 10
+		}))
+		.unwrap();
+		router.set_friendly_name(Some("Main \"router\"".to_string()));
+		vec![router, Device::sample("192.168.1.20", "aa:aa:aa:aa:aa:01", "eth0")]
+	}
+
+	#[test]
+	fn test_export_to_csv_and_json() {
+		let csv = render(&devices(), ExportFormat::Csv).unwrap();
+		let lines: Vec<&str> = csv.lines().collect();
+		assert_eq!(lines.len(), 3);
+		assert_eq!(lines[0], "ip_address,mac_address,manufacturer,hostname,friendly_name,interface_name,source,vlan_id,mac_conflict,answered_on");
+		// The comma in the vendor name and the quotes in the alias are escaped
+		assert_eq!(
+			lines[1],
+			"192.168.1.1,00:0f:a0:00:00:01,\"Netgear, Inc.\",router.lan,\"Main \"\"router\"\"\",eth0,LiveReply,10,false,eth0;wlan0"
+		);
+		assert_eq!(lines[2], "192.168.1.20,aa:aa:aa:aa:aa:01,Unknown,Unknown,,eth0,LiveReply,,false,");
+
+		// A hostile alias can't become a formula when the file is opened
+		let mut hostile = devices();
+		hostile[1].set_friendly_name(Some("=HYPERLINK(\"http://x\")".to_string()));
+		let csv = render(&hostile, ExportFormat::Csv).unwrap();
+		assert_eq!(csv.lines().nth(2).unwrap(), "192.168.1.20,aa:aa:aa:aa:aa:01,Unknown,Unknown,\"'=HYPERLINK(\"\"http://x\"\")\",eth0,LiveReply,,false,");
+		assert_eq!(csv_field(&Value::from("-1")), "\"'-1\"");
+		assert_eq!(csv_field(&Value::from(-1)), "-1");
+
+		let json: Value = serde_json::from_str(&render(&devices(), ExportFormat::Json).unwrap()).unwrap();
+		let exported = json.as_array().unwrap();
+		assert_eq!(exported.len(), 2);
+		assert_eq!(exported[0]["manufacturer"], "Netgear, Inc.");
+		assert_eq!(exported[0]["vlan_id"], 10);
+		assert_eq!(exported[1]["ip_address"], "192.168.1.20");
+
+		let path = std::env::temp_dir().join(format!("cutecatnet-devices-{}.csv", std::process::id()));
+		export_devices(&devices(), ExportFormat::Csv, &path).unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), csv);
+		let _ = std::fs::remove_file(&path);
+	}
+}
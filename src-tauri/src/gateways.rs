@@ -0,0 +1,156 @@
+// src-tauri/src/gateways.rs
+
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use crate::icmp;
+
+const GATEWAY_PING_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The gateway one interface is configured with, as reported by the OS.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterfaceGateway {
+	pub interface_name: String,
+	pub gateway_ip: Option<Ipv4Addr>,
+	pub gateway_mac: Option<String>,
+	/// The interface the OS routes default traffic through
+	pub is_default: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GatewayDiagnosis {
+	pub interface_name: String,
+	pub gateway_ip: Option<String>,
+	pub gateway_mac: Option<String>,
+	pub is_default: bool,
+	pub reachable: bool,
+	pub latency_ms: Option<f64>,
+	/// More than one interface claims a default gateway
+	pub multiple_default_routes: bool,
+	pub problems: Vec<String>,
+}
+
+/// Every interface's configured gateway, per `default_net`.
+pub fn interface_gateways() -> Vec<InterfaceGateway> {
+	let default_index = default_net::get_default_interface().ok().map(|iface| iface.index);
+	default_net::get_interfaces()
+		.into_iter()
+		.map(|iface| {
+			let gateway = iface.gateway.as_ref();
+			InterfaceGateway {
+				gateway_ip: gateway.and_then(|gateway| match gateway.ip_addr {
+					IpAddr::V4(ip) => Some(ip),
+					IpAddr::V6(_) => None,
+				}),
+				gateway_mac: gateway.map(|gateway| gateway.mac_addr.address()),
+				is_default: Some(iface.index) == default_index,
+				interface_name: iface.name,
+			}
+		})
+		.collect()
+}
+
+/// Pings each interface's gateway and reports reachability, latency and routing oddities.
+/// Interfaces without a gateway are left out.
+pub async fn diagnose_gateways() -> Vec<GatewayDiagnosis> {
+	let (gateways, gateway_ips): (Vec<InterfaceGateway>, Vec<_>) = interface_gateways()
+		.into_iter()
+		.filter_map(|gateway| gateway.gateway_ip.map(|ip| (gateway, ip)))
+		.unzip();
+
+	let mut pings = Vec::with_capacity(gateway_ips.len());
+	for ip in gateway_ips {
+		pings.push(icmp::ping(ip, GATEWAY_PING_TIMEOUT).await);
+	}
+	build_diagnoses(&gateways, &pings)
+}
+
+/// `pings[i]` is the ping outcome for `gateways[i]`: a latency, `None` for no reply, or an
+/// error when the ping couldn't be sent at all.
+fn build_diagnoses(gateways: &[InterfaceGateway], pings: &[Result<Option<f64>, String>]) -> Vec<GatewayDiagnosis> {
+	let with_gateway: Vec<&str> = gateways
+		.iter()
+		.filter(|gateway| gateway.gateway_ip.is_some())
+		.map(|gateway| gateway.interface_name.as_str())
+		.collect();
+	let multiple_default_routes = with_gateway.len() > 1;
+
+	gateways
+		.iter()
+		.zip(pings)
+		.map(|(gateway, ping)| {
+			let mut problems = Vec::new();
+			if multiple_default_routes {
+				problems.push(format!("{} interfaces have a default gateway: {}", with_gateway.len(), with_gateway.join(", ")));
+			}
+			let latency_ms = match (gateway.gateway_ip, ping) {
+				(None, _) => None,
+				(Some(_), Ok(Some(latency))) => Some(*latency),
+				(Some(ip), Ok(None)) => {
+					problems.push(format!("Gateway {} did not respond to ping", ip));
+					None
+				}
+				(Some(ip), Err(e)) => {
+					problems.push(format!("Could not ping gateway {}: {}", ip, e));
+					None
+				}
+			};
+
+			GatewayDiagnosis {
+				interface_name: gateway.interface_name.clone(),
+				gateway_ip: gateway.gateway_ip.map(|ip| ip.to_string()),
+				gateway_mac: gateway.gateway_mac.clone(),
+				is_default: gateway.is_default,
+				reachable: latency_ms.is_some(),
+				latency_ms,
+				multiple_default_routes,
+				problems,
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn gateway(interface_name: &str, gateway_ip: Option<&str>, is_default: bool) -> InterfaceGateway {
+		InterfaceGateway {
+			interface_name: interface_name.to_string(),
+			gateway_ip: gateway_ip.map(|ip| ip.parse().unwrap()),
+			gateway_mac: gateway_ip.map(|_| "aa:bb:cc:dd:ee:01".to_string()),
+			is_default,
+		}
+	}
+
+	#[test]
+	fn test_multiple_default_routes_are_flagged() {
+		let gateways = [
+			gateway("eth0", Some("192.168.1.1"), true),
+			gateway("wlan0", Some("10.0.0.1"), false),
+		];
+		let diagnoses = build_diagnoses(&gateways, &[Ok(Some(1.5)), Ok(None)]);
+
+		assert!(diagnoses.iter().all(|diagnosis| diagnosis.multiple_default_routes));
+		assert!(diagnoses[0].reachable);
+		assert_eq!(diagnoses[0].latency_ms, Some(1.5));
+		assert_eq!(diagnoses[0].problems, vec!["2 interfaces have a default gateway: eth0, wlan0".to_string()]);
+
+		assert!(!diagnoses[1].reachable);
+		assert_eq!(diagnoses[1].problems.len(), 2);
+		assert!(diagnoses[1].problems[1].contains("10.0.0.1 did not respond"));
+	}
+
+	#[test]
+	fn test_single_gateway_is_healthy() {
+		let gateways = [gateway("eth0", Some("192.168.1.1"), true), gateway("docker0", None, false)];
+		let diagnoses = build_diagnoses(&gateways, &[Ok(Some(0.8)), Ok(None)]);
+
+		assert!(!diagnoses[0].multiple_default_routes);
+		assert!(diagnoses[0].reachable);
+		assert!(diagnoses[0].problems.is_empty());
+		// No gateway to ping is not a problem in itself
+		assert!(diagnoses[1].problems.is_empty());
+	}
+}
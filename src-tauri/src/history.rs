@@ -0,0 +1,311 @@
+// src-tauri/src/history.rs
+
+use log::warn;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::stresser::TestResult;
+
+pub const HISTORY_FILE: &str = "history.jsonl";
+pub const HISTORY_DB_FILE: &str = "history.sqlite3";
+pub const DEFAULT_HISTORY_LIMIT: usize = 500;
+
+/// Where finished tests are kept. The JSON-lines file needs nothing set up; SQLite keeps
+/// filtered queries fast once the history runs to thousands of tests.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryBackend {
+	#[default]
+	File,
+	Sqlite,
+}
+
+impl HistoryBackend {
+	pub fn file_name(self) -> &'static str {
+		match self {
+			HistoryBackend::File => HISTORY_FILE,
+			HistoryBackend::Sqlite => HISTORY_DB_FILE,
+		}
+	}
+}
+
+impl FromStr for HistoryBackend {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_ascii_lowercase().as_str() {
+			"file" => Ok(HistoryBackend::File),
+			"sqlite" => Ok(HistoryBackend::Sqlite),
+			other => Err(format!("Unknown history backend: {}", other)),
+		}
+	}
+}
+
+/// Narrows a history query. Unset fields match everything.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct HistoryFilter {
+	pub target_ip: Option<String>,
+	pub label: Option<String>,
+	/// Most results to return, newest first
+	pub limit: Option<usize>,
+}
+
+impl HistoryFilter {
+	fn matches(&self, result: &TestResult) -> bool {
+		self.target_ip.as_ref().is_none_or(|ip| *ip == result.target_ip)
+			&& self.label.as_ref().is_none_or(|label| result.label.as_ref() == Some(label))
+	}
+}
+
+/// Finished test results, capped at `max_entries` with the oldest rotated out. Stored as
+/// JSON lines, oldest first, or as rows of a SQLite database.
+#[derive(Clone, Debug)]
+pub struct HistoryStore {
+	path: PathBuf,
+	max_entries: usize,
+	backend: HistoryBackend,
+}
+
+impl HistoryStore {
+	pub fn new(path: PathBuf, max_entries: usize) -> Self {
+		Self { path, max_entries: max_entries.max(1), backend: HistoryBackend::File }
+	}
+
+	pub fn sqlite(path: PathBuf, max_entries: usize) -> Self {
+		Self { backend: HistoryBackend::Sqlite, ..Self::new(path, max_entries) }
+	}
+
+	/// The store for `backend` under `dir`, using the backend's usual file name.
+	pub fn in_dir(dir: &Path, backend: HistoryBackend, max_entries: usize) -> Self {
+		Self { backend, ..Self::new(dir.join(backend.file_name()), max_entries) }
+	}
+
+	/// Appends `result`, dropping the oldest entries once the cap is exceeded.
+	pub fn append(&self, result: &TestResult) -> io::Result<()> {
+		match self.backend {
+			HistoryBackend::File => self.append_line(result),
+			HistoryBackend::Sqlite => self.insert_row(result).map_err(io::Error::other),
+		}
+	}
+
+	/// Retained results, newest first.
+	pub fn load(&self) -> Vec<TestResult> {
+		self.query(&HistoryFilter::default())
+	}
+
+	/// Retained results matching `filter`, newest first. Entries that no longer parse are skipped.
+	pub fn query(&self, filter: &HistoryFilter) -> Vec<TestResult> {
+		let results = match self.backend {
+			HistoryBackend::File => self.read_lines().map(|lines| {
+				lines
+					.iter()
+					.rev()
+					.filter_map(|line| serde_json::from_str::<TestResult>(line).ok())
+					.filter(|result| filter.matches(result))
+					.take(filter.limit.unwrap_or(usize::MAX))
+					.collect()
+			}),
+			HistoryBackend::Sqlite => self.select_rows(filter).map_err(io::Error::other),
+		};
+		results.unwrap_or_else(|e| {
+			warn!("Failed to read test history from {}: {}", self.path.display(), e);
+			Vec::new()
+		})
+	}
+
+	/// The retained result of `test_id`, if any.
+	pub fn find(&self, test_id: &str) -> Option<TestResult> {
+		match self.backend {
+			HistoryBackend::File => self.load().into_iter().find(|result| result.test_id == test_id),
+			HistoryBackend::Sqlite => self.select_row(test_id).unwrap_or_else(|e| {
+				warn!("Failed to read test history from {}: {}", self.path.display(), e);
+				None
+			}),
+		}
+	}
+
+	fn append_line(&self, result: &TestResult) -> io::Result<()> {
+		let line = serde_json::to_string(result).map_err(io::Error::other)?;
+		if let Some(parent) = self.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let mut lines = self.read_lines()?;
+		if lines.len() < self.max_entries {
+			let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+			return writeln!(file, "{}", line);
+		}
+
+		lines.push(line);
+		let keep_from = lines.len() - self.max_entries;
+		let mut contents = lines[keep_from..].join("\n");
+		contents.push('\n');
+
+		// Write beside the original and rename so a crash never leaves a half-written history
+		let tmp = self.path.with_extension("jsonl.tmp");
+		fs::write(&tmp, contents)?;
+		fs::rename(&tmp, &self.path)
+	}
+
+	fn read_lines(&self) -> io::Result<Vec<String>> {
+		match fs::read_to_string(&self.path) {
+			Ok(contents) => Ok(contents
+				.lines()
+				.filter(|line| !line.trim().is_empty())
+				.map(str::to_string)
+				.collect()),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Opens the database, creating it and its table on first use. Each result is kept whole
+	/// as JSON; the columns beside it exist only to filter and order on.
+	fn connect(&self) -> rusqlite::Result<Connection> {
+		if let Some(parent) = self.path.parent() {
+			let _ = fs::create_dir_all(parent);
+		}
+		let conn = Connection::open(&self.path)?;
+		conn.execute_batch(
+			"CREATE TABLE IF NOT EXISTS test_results (
+				seq INTEGER PRIMARY KEY AUTOINCREMENT,
+				test_id TEXT NOT NULL,
+				target_ip TEXT NOT NULL,
+				label TEXT,
+				start_time INTEGER NOT NULL,
+				result TEXT NOT NULL
+			);
+			CREATE INDEX IF NOT EXISTS test_results_test_id ON test_results (test_id);
+			CREATE INDEX IF NOT EXISTS test_results_target_ip ON test_results (target_ip);
+			CREATE INDEX IF NOT EXISTS test_results_label ON test_results (label);",
+		)?;
+		Ok(conn)
+	}
+
+	fn insert_row(&self, result: &TestResult) -> rusqlite::Result<()> {
+		let json = serde_json::to_string(result).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+		let mut conn = self.connect()?;
+		let tx = conn.transaction()?;
+		tx.execute(
+			"INSERT INTO test_results (test_id, target_ip, label, start_time, result) VALUES (?1, ?2, ?3, ?4, ?5)",
+			params![result.test_id, result.target_ip, result.label, result.start_time as i64, json],
+		)?;
+		tx.execute(
+			"DELETE FROM test_results WHERE seq <= (SELECT MAX(seq) FROM test_results) - ?1",
+			params![self.max_entries as i64],
+		)?;
+		tx.commit()
+	}
+
+	fn select_rows(&self, filter: &HistoryFilter) -> rusqlite::Result<Vec<TestResult>> {
+		let conn = self.connect()?;
+		let mut statement = conn.prepare(
+			"SELECT result FROM test_results
+			WHERE (?1 IS NULL OR target_ip = ?1) AND (?2 IS NULL OR label = ?2)
+			ORDER BY seq DESC LIMIT ?3",
+		)?;
+		// SQLite reads a negative limit as no limit
+		let limit = filter.limit.map_or(-1, |limit| limit as i64);
+		let rows = statement.query_map(params![filter.target_ip, filter.label, limit], |row| row.get::<_, String>(0))?;
+		let mut results = Vec::new();
+		for json in rows {
+			if let Ok(result) = serde_json::from_str(&json?) {
+				results.push(result);
+			}
+		}
+		Ok(results)
+	}
+
+	fn select_row(&self, test_id: &str) -> rusqlite::Result<Option<TestResult>> {
+		let conn = self.connect()?;
+		let json: Option<String> = conn
+			.query_row(
+				"SELECT result FROM test_results WHERE test_id = ?1 ORDER BY seq DESC LIMIT 1",
+				params![test_id],
+				|row| row.get(0),
+			)
+			.optional()?;
+		Ok(json.and_then(|json| serde_json::from_str(&json).ok()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn result(test_id: &str) -> TestResult {
+		TestResult::sample(test_id, "192.168.1.50")
+	}
+
+	#[test]
+	fn test_cap_rotates_out_oldest() {
+		let dir = std::env::temp_dir().join(format!("cutecatnet-history-{}", std::process::id()));
+		let store = HistoryStore::new(dir.join(HISTORY_FILE), 3);
+
+		for i in 0..5 {
+			store.append(&result(&format!("test_{}", i))).unwrap();
+		}
+
+		let ids: Vec<String> = store.load().into_iter().map(|r| r.test_id).collect();
+		assert_eq!(ids, vec!["test_4", "test_3", "test_2"]);
+
+		// A fresh store over the same file sees the same rotated set
+		let reopened = HistoryStore::new(dir.join(HISTORY_FILE), 3);
+		assert_eq!(reopened.load().len(), 3);
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn test_sqlite_backend_filters_and_rotates() {
+		let dir = std::env::temp_dir().join(format!("cutecatnet-history-sqlite-{}", std::process::id()));
+		let store = HistoryStore::in_dir(&dir, HistoryBackend::Sqlite, 4);
+
+		for (i, (ip, label)) in [
+			("192.168.1.50", None),
+			("192.168.1.50", Some("before QoS")),
+			("192.168.1.51", Some("before QoS")),
+			("192.168.1.50", Some("after QoS")),
+			("192.168.1.51", Some("after QoS")),
+		]
+		.into_iter()
+		.enumerate()
+		{
+			let mut result = TestResult::sample(&format!("test_{}", i), ip);
+			result.label = label.map(str::to_string);
+			store.append(&result).unwrap();
+		}
+
+		// test_0 rotated out; the rest come back newest first
+		let ids = |results: Vec<TestResult>| results.into_iter().map(|r| r.test_id).collect::<Vec<String>>();
+		assert_eq!(ids(store.load()), vec!["test_4", "test_3", "test_2", "test_1"]);
+		assert!(store.find("test_0").is_none());
+		assert_eq!(store.find("test_2").unwrap().target_ip, "192.168.1.51");
+
+		let by_target = HistoryFilter { target_ip: Some("192.168.1.50".to_string()), ..HistoryFilter::default() };
+		assert_eq!(ids(store.query(&by_target)), vec!["test_3", "test_1"]);
+		let by_label = HistoryFilter { label: Some("after QoS".to_string()), limit: Some(1), ..HistoryFilter::default() };
+		assert_eq!(ids(store.query(&by_label)), vec!["test_4"]);
+
+		// A reopened database sees the same rows, and the file backend filters the same way
+		let reopened = HistoryStore::sqlite(dir.join(HISTORY_DB_FILE), 4);
+		assert_eq!(reopened.load().len(), 4);
+		let file = HistoryStore::in_dir(&dir, HistoryBackend::File, 4);
+		for result in store.load().into_iter().rev() {
+			file.append(&result).unwrap();
+		}
+		assert_eq!(ids(file.query(&by_target)), vec!["test_3", "test_1"]);
+		assert_eq!(ids(file.query(&by_label)), vec!["test_4"]);
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn test_backend_names_parse() {
+		assert_eq!("SQLite".parse::<HistoryBackend>(), Ok(HistoryBackend::Sqlite));
+		assert_eq!("file".parse::<HistoryBackend>(), Ok(HistoryBackend::File));
+		assert!("postgres".parse::<HistoryBackend>().is_err());
+	}
+}
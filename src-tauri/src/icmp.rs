@@ -0,0 +1,712 @@
+// src-tauri/src/icmp.rs
+
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::ipv4::{self, Ipv4Flags, Ipv4Packet, MutableIpv4Packet};
+use pnet::packet::util::checksum;
+use pnet::packet::Packet;
+use pnet::transport::{icmp_packet_iter, ipv4_packet_iter, transport_channel, TransportChannelType, TransportProtocol};
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::privileges;
+use crate::stresser;
+
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ECHO_PACKET_LEN: usize = 8;
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+const ICMP_FRAGMENTATION_NEEDED: u8 = 4;
+const ICMP_TIME_EXCEEDED: u8 = 11;
+const IPV4_HEADER_LEN: usize = 20;
+/// Every IPv4 link must carry packets this large unfragmented (RFC 791).
+const MIN_IPV4_MTU: u32 = 68;
+pub const PMTU_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+const ICMP_TIMESTAMP_REQUEST: u8 = 13;
+const ICMP_TIMESTAMP_REPLY: u8 = 14;
+const TIMESTAMP_PACKET_LEN: usize = 20;
+/// RFC 792: a set high-order bit means the timestamp isn't milliseconds since midnight UT.
+const NON_STANDARD_TIMESTAMP: u32 = 0x8000_0000;
+const MS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+pub const TIMESTAMP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The three timestamps carried by an ICMP Timestamp Reply.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct IcmpTimestamps {
+	originate: u32,
+	receive: u32,
+	transmit: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "result")]
+pub enum TimestampProbe {
+	/// `clock_offset_ms` is positive when the remote clock is ahead of ours.
+	Measured { round_trip_ms: f64, clock_offset_ms: f64 },
+	/// The host didn't answer, or answered with something other than standard UT milliseconds.
+	NotSupported { reason: String },
+}
+
+/// Sends one ICMP Timestamp Request (type 13) to `ip` and estimates the remote clock offset.
+/// Needs raw socket privileges, like the scanner.
+pub async fn probe_timestamp(ip: Ipv4Addr) -> Result<TimestampProbe, String> {
+	tokio::task::spawn_blocking(move || probe_timestamp_blocking(ip, TIMESTAMP_TIMEOUT))
+		.await
+		.map_err(|e| e.to_string())?
+}
+
+fn probe_timestamp_blocking(ip: Ipv4Addr, wait: Duration) -> Result<TimestampProbe, String> {
+	privileges::require_raw_sockets()?;
+	let protocol = TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Icmp));
+	let (mut tx, mut rx) = transport_channel(4096, protocol).map_err(|e| e.to_string())?;
+
+	let identifier = std::process::id() as u16;
+	let sequence = 1;
+	let mut request = build_timestamp_request(identifier, sequence, ms_since_midnight_utc());
+	let request = pnet::packet::icmp::MutableIcmpPacket::new(&mut request)
+		.ok_or_else(|| "Failed to build ICMP timestamp request".to_string())?;
+	tx.send_to(request, IpAddr::V4(ip)).map_err(|e| e.to_string())?;
+
+	let deadline = Instant::now() + wait;
+	let mut replies = icmp_packet_iter(&mut rx);
+	while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+		match replies.next_with_timeout(remaining) {
+			Ok(Some((packet, addr))) if addr == IpAddr::V4(ip) => {
+				if let Some(timestamps) = parse_timestamp_reply(packet.packet(), identifier, sequence) {
+					return Ok(estimate_offset(timestamps, ms_since_midnight_utc()));
+				}
+			}
+			Ok(Some(_)) => continue,
+			Ok(None) => break,
+			Err(e) => return Err(e.to_string()),
+		}
+	}
+
+	Ok(TimestampProbe::NotSupported {
+		reason: format!("No timestamp reply from {} within {:?}", ip, wait),
+	})
+}
+
+/// Sends one ICMP Echo Request to `ip` and returns the round trip in ms, `None` when no reply
+/// arrived within `wait`. Needs raw socket privileges.
+pub async fn ping(ip: Ipv4Addr, wait: Duration) -> Result<Option<f64>, String> {
+	tokio::task::spawn_blocking(move || ping_blocking(ip, wait))
+		.await
+		.map_err(|e| e.to_string())?
+}
+
+fn ping_blocking(ip: Ipv4Addr, wait: Duration) -> Result<Option<f64>, String> {
+	privileges::require_raw_sockets()?;
+	let protocol = TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Icmp));
+	let (mut tx, mut rx) = transport_channel(4096, protocol).map_err(|e| e.to_string())?;
+
+	let identifier = std::process::id() as u16;
+	let sequence = 1;
+	let mut request = build_echo_request(identifier, sequence, 0);
+	let request = pnet::packet::icmp::MutableIcmpPacket::new(&mut request)
+		.ok_or_else(|| "Failed to build ICMP echo request".to_string())?;
+	let start = Instant::now();
+	tx.send_to(request, IpAddr::V4(ip)).map_err(|e| e.to_string())?;
+
+	let deadline = start + wait;
+	let mut replies = icmp_packet_iter(&mut rx);
+	while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+		match replies.next_with_timeout(remaining) {
+			Ok(Some((packet, addr))) if addr == IpAddr::V4(ip) && is_echo_reply_for(packet.packet(), identifier, sequence) => {
+				return Ok(Some(start.elapsed().as_secs_f64() * 1000.0));
+			}
+			Ok(Some(_)) => continue,
+			Ok(None) => break,
+			Err(e) => return Err(e.to_string()),
+		}
+	}
+	Ok(None)
+}
+
+/// Most pings one `measure_latency` call may send.
+pub const MAX_LATENCY_PROBES: u32 = 100;
+/// Spacing between pings when none is requested, as with classic `ping`.
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest spacing between pings; a full series then still finishes within the hour.
+pub const MAX_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const LATENCY_PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LatencyMeasurement {
+	pub sent: u32,
+	pub received: u32,
+	/// One entry per ping, `None` for the ones that went unanswered
+	pub latencies_ms: Vec<Option<f64>>,
+	pub min_ms: Option<f64>,
+	pub avg_ms: Option<f64>,
+	pub max_ms: Option<f64>,
+}
+
+impl LatencyMeasurement {
+	fn from_latencies(latencies_ms: Vec<Option<f64>>) -> Self {
+		let answered: Vec<f64> = latencies_ms.iter().flatten().copied().collect();
+		let avg_ms = (!answered.is_empty()).then(|| answered.iter().sum::<f64>() / answered.len() as f64);
+		Self {
+			sent: latencies_ms.len() as u32,
+			received: answered.len() as u32,
+			min_ms: answered.iter().copied().reduce(f64::min),
+			max_ms: answered.iter().copied().reduce(f64::max),
+			avg_ms,
+			latencies_ms,
+		}
+	}
+}
+
+/// Shortest spacing between pings that stays within `max_packets_per_second`.
+pub fn min_probe_interval(max_packets_per_second: u32) -> Duration {
+	Duration::from_secs(1) / max_packets_per_second.max(1)
+}
+
+/// Pings `ip` `count` times (capped at `MAX_LATENCY_PROBES`), starting one every `interval`
+/// like `ping -i`. Needs raw socket privileges.
+pub async fn measure_latency(ip: Ipv4Addr, count: u32, interval: Duration) -> Result<LatencyMeasurement, String> {
+	let latencies = probe_series(count.min(MAX_LATENCY_PROBES), interval, || ping(ip, LATENCY_PROBE_TIMEOUT)).await?;
+	Ok(LatencyMeasurement::from_latencies(latencies))
+}
+
+/// Runs `count` probes whose starts are `interval` apart. A probe that outlasts the interval
+/// delays the next one rather than overlapping it.
+async fn probe_series<F, Fut>(count: u32, interval: Duration, mut probe: F) -> Result<Vec<Option<f64>>, String>
+where
+	F: FnMut() -> Fut,
+	Fut: std::future::Future<Output = Result<Option<f64>, String>>,
+{
+	let start = tokio::time::Instant::now();
+	let mut latencies = Vec::with_capacity(count as usize);
+	for i in 0..count {
+		let Some(at) = interval.checked_mul(i).and_then(|offset| start.checked_add(offset)) else {
+			return Err(format!("Interval of {:?} is too long", interval));
+		};
+		tokio::time::sleep_until(at).await;
+		latencies.push(probe().await?);
+	}
+	Ok(latencies)
+}
+
+/// What became of one don't-fragment echo of a given size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SizeProbe {
+	Delivered,
+	/// A router (or our own stack) refused it, reporting its next-hop MTU when it knows it
+	FragmentationNeeded { next_hop_mtu: Option<u32> },
+	NoReply,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "result")]
+pub enum PathMtu {
+	/// Largest IP packet, headers included, that reaches the target unfragmented
+	Discovered { mtu: u32 },
+	/// Large pings vanished without a "fragmentation needed" error, so loss and an MTU limit
+	/// can't be told apart. Packets of `at_least` bytes did get through.
+	Indeterminate { at_least: Option<u32>, reason: String },
+}
+
+/// Finds the path MTU to `ip` by binary search over don't-fragment echo sizes, up to the
+/// local interface MTU. Needs raw socket privileges.
+pub async fn discover_path_mtu(ip: Ipv4Addr) -> Result<PathMtu, String> {
+	let max = stresser::detect_mtu().mtu;
+	tokio::task::spawn_blocking(move || discover_path_mtu_blocking(ip, max))
+		.await
+		.map_err(|e| e.to_string())?
+}
+
+fn discover_path_mtu_blocking(ip: Ipv4Addr, max: u32) -> Result<PathMtu, String> {
+	privileges::require_raw_sockets()?;
+	// Layer 3 so we write the IP header ourselves, DF bit included
+	let protocol = TransportChannelType::Layer3(IpNextHeaderProtocols::Icmp);
+	let (mut tx, mut rx) = transport_channel(65_535, protocol).map_err(|e| e.to_string())?;
+
+	let identifier = std::process::id() as u16;
+	let mut sequence = 0u16;
+	search_path_mtu(max, |size| {
+		sequence = sequence.wrapping_add(1);
+		let request = build_df_echo_request(ip, identifier, sequence, size);
+		let request = Ipv4Packet::new(&request).ok_or_else(|| "Failed to build ICMP echo request".to_string())?;
+		tx.send_to(request, IpAddr::V4(ip)).map_err(|e| e.to_string())?;
+
+		let deadline = Instant::now() + PMTU_PROBE_TIMEOUT;
+		let mut replies = ipv4_packet_iter(&mut rx);
+		while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+			match replies.next_with_timeout(remaining) {
+				Ok(Some((reply, addr))) => {
+					let icmp = reply.payload();
+					if addr == IpAddr::V4(ip) && is_echo_reply_for(icmp, identifier, sequence) {
+						return Ok(SizeProbe::Delivered);
+					}
+					// Comes from whichever router couldn't forward it, not the target
+					if let Some(next_hop_mtu) = parse_fragmentation_needed(icmp, identifier, sequence) {
+						return Ok(SizeProbe::FragmentationNeeded { next_hop_mtu });
+					}
+				}
+				Ok(None) => break,
+				Err(e) => return Err(e.to_string()),
+			}
+		}
+		Ok(SizeProbe::NoReply)
+	})
+}
+
+/// Binary search for the largest packet size in `MIN_IPV4_MTU..=max` that `probe` gets
+/// through. A size that goes unanswered is treated as too large, but makes the result
+/// indeterminate: a host or firewall that drops the ICMP errors looks exactly like loss.
+fn search_path_mtu<P>(max: u32, mut probe: P) -> Result<PathMtu, String>
+where
+	P: FnMut(u32) -> Result<SizeProbe, String>,
+{
+	if probe(MIN_IPV4_MTU)? != SizeProbe::Delivered {
+		return Ok(PathMtu::Indeterminate {
+			at_least: None,
+			reason: "The host didn't answer even minimum-size pings".to_string(),
+		});
+	}
+
+	// `good` got through; everything from `too_big` up is known not to
+	let mut good = MIN_IPV4_MTU;
+	let mut too_big = max + 1;
+	let mut silent_drops = false;
+	while too_big - good > 1 {
+		let size = good + (too_big - good) / 2;
+		match probe(size)? {
+			SizeProbe::Delivered => good = size,
+			SizeProbe::FragmentationNeeded { next_hop_mtu } => {
+				too_big = size;
+				// Skip straight to the limit the router told us about
+				if let Some(mtu) = next_hop_mtu.filter(|mtu| (good..too_big).contains(mtu)) {
+					too_big = mtu + 1;
+				}
+			}
+			SizeProbe::NoReply => {
+				too_big = size;
+				silent_drops = true;
+			}
+		}
+	}
+
+	if silent_drops {
+		Ok(PathMtu::Indeterminate {
+			at_least: Some(good),
+			reason: format!(
+				"Pings larger than {} bytes went unanswered without a \"fragmentation needed\" error",
+				good
+			),
+		})
+	} else {
+		Ok(PathMtu::Discovered { mtu: good })
+	}
+}
+
+/// Hops `traceroute` tries before giving up on reaching the target.
+pub const MAX_TRACEROUTE_HOPS: u8 = 30;
+const HOP_TIMEOUT: Duration = Duration::from_secs(1);
+// Echo request with no payload, behind an option-less IPv4 header
+const HOP_PROBE_LEN: u32 = (IPV4_HEADER_LEN + ECHO_PACKET_LEN) as u32;
+
+/// One step of the path to a target. `ip` and `latency_ms` are `None` for a router that
+/// didn't answer within the hop timeout.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Hop {
+	pub ttl: u8,
+	pub ip: Option<Ipv4Addr>,
+	pub latency_ms: Option<f64>,
+}
+
+/// What came back for a probe sent with a given TTL.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HopReply {
+	/// A router on the way dropped it and sent Time Exceeded
+	TimeExceeded(Ipv4Addr),
+	/// The target itself answered
+	Reached(Ipv4Addr),
+}
+
+/// Sends echo requests to `ip` with TTL 1, 2, 3... and records which router answers at
+/// each hop, stopping once the target replies or after `MAX_TRACEROUTE_HOPS`. Needs raw
+/// socket privileges.
+pub async fn traceroute(ip: Ipv4Addr) -> Result<Vec<Hop>, String> {
+	tokio::task::spawn_blocking(move || traceroute_blocking(ip))
+		.await
+		.map_err(|e| e.to_string())?
+}
+
+fn traceroute_blocking(ip: Ipv4Addr) -> Result<Vec<Hop>, String> {
+	privileges::require_raw_sockets()?;
+	// Layer 3 so each probe carries its own TTL
+	let protocol = TransportChannelType::Layer3(IpNextHeaderProtocols::Icmp);
+	let (mut tx, mut rx) = transport_channel(4096, protocol).map_err(|e| e.to_string())?;
+
+	let identifier = std::process::id() as u16;
+	trace_path(MAX_TRACEROUTE_HOPS, |ttl| {
+		let sequence = u16::from(ttl);
+		let request = build_ipv4_echo_request(ip, identifier, sequence, HOP_PROBE_LEN, ttl, 0);
+		let request = Ipv4Packet::new(&request).ok_or_else(|| "Failed to build ICMP echo request".to_string())?;
+		let start = Instant::now();
+		tx.send_to(request, IpAddr::V4(ip)).map_err(|e| e.to_string())?;
+
+		let deadline = start + HOP_TIMEOUT;
+		let mut replies = ipv4_packet_iter(&mut rx);
+		while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+			match replies.next_with_timeout(remaining) {
+				Ok(Some((reply, IpAddr::V4(from)))) => {
+					if let Some(hop) = parse_hop_reply(reply.payload(), from, ip, identifier, sequence) {
+						return Ok(Some((hop, start.elapsed().as_secs_f64() * 1000.0)));
+					}
+				}
+				Ok(Some(_)) => continue,
+				Ok(None) => break,
+				Err(e) => return Err(e.to_string()),
+			}
+		}
+		Ok(None)
+	})
+}
+
+/// Probes TTLs from 1 up to `max_hops` until `probe` reports the target was reached.
+fn trace_path<P>(max_hops: u8, mut probe: P) -> Result<Vec<Hop>, String>
+where
+	P: FnMut(u8) -> Result<Option<(HopReply, f64)>, String>,
+{
+	let mut hops = Vec::new();
+	for ttl in 1..=max_hops {
+		match probe(ttl)? {
+			Some((HopReply::Reached(ip), latency)) => {
+				hops.push(Hop { ttl, ip: Some(ip), latency_ms: Some(latency) });
+				break;
+			}
+			Some((HopReply::TimeExceeded(ip), latency)) => hops.push(Hop { ttl, ip: Some(ip), latency_ms: Some(latency) }),
+			None => hops.push(Hop { ttl, ip: None, latency_ms: None }),
+		}
+	}
+	Ok(hops)
+}
+
+/// Reads the ICMP message `packet` that `from` sent in answer to our hop probe `sequence`.
+fn parse_hop_reply(packet: &[u8], from: Ipv4Addr, target: Ipv4Addr, identifier: u16, sequence: u16) -> Option<HopReply> {
+	if from == target && is_echo_reply_for(packet, identifier, sequence) {
+		return Some(HopReply::Reached(from));
+	}
+	if packet.first() == Some(&ICMP_TIME_EXCEEDED) && quotes_echo_request(packet, identifier, sequence) {
+		return Some(HopReply::TimeExceeded(from));
+	}
+	None
+}
+
+/// An IPv4 packet of `total_len` bytes carrying an echo request, with Don't Fragment set.
+fn build_df_echo_request(destination: Ipv4Addr, identifier: u16, sequence: u16, total_len: u32) -> Vec<u8> {
+	build_ipv4_echo_request(destination, identifier, sequence, total_len, 64, Ipv4Flags::DontFragment)
+}
+
+fn build_ipv4_echo_request(destination: Ipv4Addr, identifier: u16, sequence: u16, total_len: u32, ttl: u8, flags: u8) -> Vec<u8> {
+	let echo = build_echo_request(identifier, sequence, total_len as usize - IPV4_HEADER_LEN - ECHO_PACKET_LEN);
+	let mut buffer = vec![0u8; IPV4_HEADER_LEN + echo.len()];
+	let mut packet = MutableIpv4Packet::new(&mut buffer).expect("buffer holds an IPv4 header");
+	packet.set_version(4);
+	packet.set_header_length((IPV4_HEADER_LEN / 4) as u8);
+	packet.set_total_length(total_len as u16);
+	packet.set_ttl(ttl);
+	packet.set_flags(flags);
+	packet.set_next_level_protocol(IpNextHeaderProtocols::Icmp);
+	// Source left unspecified: the kernel fills in the outgoing interface's address
+	packet.set_destination(destination);
+	packet.set_payload(&echo);
+	let sum = ipv4::checksum(&packet.to_immutable());
+	packet.set_checksum(sum);
+	buffer
+}
+
+/// The next-hop MTU from a "fragmentation needed" error quoting our echo `sequence`.
+/// `Some(None)` when the router left the MTU field zero (pre-RFC 1191).
+fn parse_fragmentation_needed(packet: &[u8], identifier: u16, sequence: u16) -> Option<Option<u32>> {
+	if packet.len() < ECHO_PACKET_LEN || packet[0] != ICMP_DEST_UNREACHABLE || packet[1] != ICMP_FRAGMENTATION_NEEDED {
+		return None;
+	}
+	if !quotes_echo_request(packet, identifier, sequence) {
+		return None;
+	}
+
+	let next_hop_mtu = u16::from_be_bytes([packet[6], packet[7]]);
+	Some((next_hop_mtu != 0).then_some(u32::from(next_hop_mtu)))
+}
+
+/// Whether the ICMP error `packet` quotes our echo request `sequence`. Errors quote the
+/// original IP header and the first 8 bytes of its payload.
+fn quotes_echo_request(packet: &[u8], identifier: u16, sequence: u16) -> bool {
+	let Some(quoted) = packet.get(ECHO_PACKET_LEN..) else {
+		return false;
+	};
+	let header_len = quoted.first().map_or(0, |byte| usize::from(byte & 0x0f) * 4);
+	match quoted.get(header_len..header_len + ECHO_PACKET_LEN) {
+		Some(echo) => {
+			header_len > 0 && echo[0] == ICMP_ECHO_REQUEST && echo[4..6] == identifier.to_be_bytes() && echo[6..8] == sequence.to_be_bytes()
+		}
+		None => false,
+	}
+}
+
+fn build_echo_request(identifier: u16, sequence: u16, payload_len: usize) -> Vec<u8> {
+	let mut packet = vec![0u8; ECHO_PACKET_LEN + payload_len];
+	packet[0] = ICMP_ECHO_REQUEST;
+	packet[4..6].copy_from_slice(&identifier.to_be_bytes());
+	packet[6..8].copy_from_slice(&sequence.to_be_bytes());
+	let sum = checksum(&packet, 1);
+	packet[2..4].copy_from_slice(&sum.to_be_bytes());
+	packet
+}
+
+fn is_echo_reply_for(packet: &[u8], identifier: u16, sequence: u16) -> bool {
+	packet.len() >= ECHO_PACKET_LEN
+		&& packet[0] == ICMP_ECHO_REPLY
+		&& packet[4..6] == identifier.to_be_bytes()
+		&& packet[6..8] == sequence.to_be_bytes()
+}
+
+fn build_timestamp_request(identifier: u16, sequence: u16, originate: u32) -> Vec<u8> {
+	let mut packet = vec![0u8; TIMESTAMP_PACKET_LEN];
+	packet[0] = ICMP_TIMESTAMP_REQUEST;
+	packet[4..6].copy_from_slice(&identifier.to_be_bytes());
+	packet[6..8].copy_from_slice(&sequence.to_be_bytes());
+	packet[8..12].copy_from_slice(&originate.to_be_bytes());
+	let sum = checksum(&packet, 1);
+	packet[2..4].copy_from_slice(&sum.to_be_bytes());
+	packet
+}
+
+fn parse_timestamp_reply(packet: &[u8], identifier: u16, sequence: u16) -> Option<IcmpTimestamps> {
+	if packet.len() < TIMESTAMP_PACKET_LEN || packet[0] != ICMP_TIMESTAMP_REPLY {
+		return None;
+	}
+	let word = |offset: usize| u32::from_be_bytes([packet[offset], packet[offset + 1], packet[offset + 2], packet[offset + 3]]);
+	let reply_identifier = u16::from_be_bytes([packet[4], packet[5]]);
+	let reply_sequence = u16::from_be_bytes([packet[6], packet[7]]);
+	if reply_identifier != identifier || reply_sequence != sequence {
+		return None;
+	}
+
+	Some(IcmpTimestamps {
+		originate: word(8),
+		receive: word(12),
+		transmit: word(16),
+	})
+}
+
+/// NTP-style estimate from originate (t1), receive (t2), transmit (t3) and our arrival time (t4).
+fn estimate_offset(timestamps: IcmpTimestamps, arrival: u32) -> TimestampProbe {
+	if (timestamps.receive | timestamps.transmit) & NON_STANDARD_TIMESTAMP != 0 {
+		return TimestampProbe::NotSupported {
+			reason: "Host replied with non-standard timestamps".to_string(),
+		};
+	}
+
+	let t1 = timestamps.originate as i64;
+	let t2 = timestamps.receive as i64;
+	let t3 = timestamps.transmit as i64;
+	let t4 = wrap_day(arrival as i64, t1);
+
+	let round_trip_ms = ((t4 - t1) - (t3 - t2)) as f64;
+	let clock_offset_ms = (wrap_day(t2, t1) - t1 + wrap_day(t3, t4) - t4) as f64 / 2.0;
+	TimestampProbe::Measured { round_trip_ms, clock_offset_ms }
+}
+
+/// Moves `value` by whole days so it lands within half a day of `reference` (handles midnight).
+fn wrap_day(value: i64, reference: i64) -> i64 {
+	let delta = (value - reference).rem_euclid(MS_PER_DAY);
+	if delta > MS_PER_DAY / 2 {
+		reference + delta - MS_PER_DAY
+	} else {
+		reference + delta
+	}
+}
+
+fn ms_since_midnight_utc() -> u32 {
+	let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+	(now.as_millis() % MS_PER_DAY as u128) as u32
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_echo_request_and_reply() {
+		let request = build_echo_request(0x1234, 1, 0);
+		assert_eq!(request[0], ICMP_ECHO_REQUEST);
+		assert_eq!(u16::from_be_bytes([request[2], request[3]]), checksum(&request, 1));
+		// Our own request isn't mistaken for the reply
+		assert!(!is_echo_reply_for(&request, 0x1234, 1));
+
+		let mut reply = request.clone();
+		reply[0] = ICMP_ECHO_REPLY;
+		assert!(is_echo_reply_for(&reply, 0x1234, 1));
+		assert!(!is_echo_reply_for(&reply, 0x1234, 2));
+		assert!(!is_echo_reply_for(&reply[..4], 0x1234, 1));
+	}
+
+	#[tokio::test]
+	async fn test_probes_are_spaced_by_interval() {
+		let interval = Duration::from_millis(60);
+		let mut sent_at = Vec::new();
+		let latencies = probe_series(4, interval, || {
+			sent_at.push(Instant::now());
+			async { Ok(Some(1.0)) }
+		})
+		.await
+		.unwrap();
+		assert_eq!(latencies.len(), 4);
+
+		for pair in sent_at.windows(2) {
+			let gap = pair[1] - pair[0];
+			assert!(gap >= Duration::from_millis(55) && gap < Duration::from_millis(150), "gap was {:?}", gap);
+		}
+
+		let measurement = LatencyMeasurement::from_latencies(vec![Some(2.0), None, Some(4.0)]);
+		assert_eq!((measurement.sent, measurement.received), (3, 2));
+		assert_eq!((measurement.min_ms, measurement.avg_ms, measurement.max_ms), (Some(2.0), Some(3.0), Some(4.0)));
+		assert_eq!(min_probe_interval(100), Duration::from_millis(10));
+
+		// A spacing past what the clock can represent is an error, not a panic
+		let mut probes = 0;
+		let too_long = probe_series(2, Duration::MAX, || {
+			probes += 1;
+			async { Ok(Some(1.0)) }
+		})
+		.await;
+		assert!(too_long.is_err());
+		assert_eq!(probes, 1);
+	}
+
+	#[test]
+	fn test_path_mtu_binary_search() {
+		// A path that refuses anything over 1400 bytes
+		let limited = |next_hop_mtu| {
+			move |size: u32| {
+				Ok::<_, String>(if size <= 1400 {
+					SizeProbe::Delivered
+				} else {
+					SizeProbe::FragmentationNeeded { next_hop_mtu }
+				})
+			}
+		};
+		assert_eq!(search_path_mtu(1500, limited(None)), Ok(PathMtu::Discovered { mtu: 1400 }));
+
+		// Routers that report their MTU shorten the search
+		let mut probes = 0;
+		let mut counting = limited(Some(1400));
+		let result = search_path_mtu(1500, |size| {
+			probes += 1;
+			counting(size)
+		});
+		assert_eq!(result, Ok(PathMtu::Discovered { mtu: 1400 }));
+		assert!(probes < 8);
+
+		// Nothing bigger than the local interface is tried
+		assert_eq!(search_path_mtu(1500, |_| Ok(SizeProbe::Delivered)), Ok(PathMtu::Discovered { mtu: 1500 }));
+	}
+
+	#[test]
+	fn test_path_mtu_indeterminate_without_icmp_errors() {
+		let silent = |size: u32| Ok::<_, String>(if size <= 1400 { SizeProbe::Delivered } else { SizeProbe::NoReply });
+		match search_path_mtu(1500, silent) {
+			Ok(PathMtu::Indeterminate { at_least, .. }) => assert_eq!(at_least, Some(1400)),
+			other => panic!("expected an indeterminate result, got {:?}", other),
+		}
+
+		let unreachable = search_path_mtu(1500, |_| Ok(SizeProbe::NoReply));
+		assert!(matches!(unreachable, Ok(PathMtu::Indeterminate { at_least: None, .. })));
+	}
+
+	#[test]
+	fn test_df_echo_and_fragmentation_needed() {
+		let request = build_df_echo_request(Ipv4Addr::new(192, 168, 1, 1), 0x1234, 7, 1000);
+		assert_eq!(request.len(), 1000);
+		let packet = Ipv4Packet::new(&request).unwrap();
+		assert_eq!(packet.get_flags(), Ipv4Flags::DontFragment);
+		assert_eq!(packet.get_total_length(), 1000);
+
+		// Type 3 code 4 with a 1400 byte next-hop MTU, quoting our request
+		let mut error = vec![ICMP_DEST_UNREACHABLE, ICMP_FRAGMENTATION_NEEDED, 0, 0, 0, 0, 0x05, 0x78];
+		error.extend_from_slice(&request[..IPV4_HEADER_LEN + ECHO_PACKET_LEN]);
+		assert_eq!(parse_fragmentation_needed(&error, 0x1234, 7), Some(Some(1400)));
+		assert_eq!(parse_fragmentation_needed(&error, 0x1234, 8), None);
+
+		error[6..8].copy_from_slice(&[0, 0]);
+		assert_eq!(parse_fragmentation_needed(&error, 0x1234, 7), Some(None));
+	}
+
+	#[test]
+	fn test_time_exceeded_replies_become_hops() {
+		let target = Ipv4Addr::new(192, 168, 10, 20);
+		let routers = [Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(10, 0, 0, 1)];
+		let time_exceeded = |sequence: u16| {
+			let probe = build_ipv4_echo_request(target, 0x1234, sequence, HOP_PROBE_LEN, sequence as u8, 0);
+			let mut reply = vec![ICMP_TIME_EXCEEDED, 0, 0, 0, 0, 0, 0, 0];
+			reply.extend_from_slice(&probe);
+			reply
+		};
+		let mut echo_reply = build_echo_request(0x1234, 4, 0);
+		echo_reply[0] = ICMP_ECHO_REPLY;
+
+		// Canned replies: two routers, one silent hop, then the target
+		let canned: Vec<Option<(Vec<u8>, Ipv4Addr)>> =
+			vec![Some((time_exceeded(1), routers[0])), Some((time_exceeded(2), routers[1])), None, Some((echo_reply, target))];
+		let hops = trace_path(MAX_TRACEROUTE_HOPS, |ttl| {
+			let reply = canned[usize::from(ttl) - 1].as_ref();
+			Ok(reply.and_then(|(packet, from)| parse_hop_reply(packet, *from, target, 0x1234, u16::from(ttl))).map(|hop| (hop, 1.5)))
+		})
+		.unwrap();
+
+		assert_eq!(
+			hops,
+			vec![
+				Hop { ttl: 1, ip: Some(routers[0]), latency_ms: Some(1.5) },
+				Hop { ttl: 2, ip: Some(routers[1]), latency_ms: Some(1.5) },
+				Hop { ttl: 3, ip: None, latency_ms: None },
+				Hop { ttl: 4, ip: Some(target), latency_ms: Some(1.5) },
+			]
+		);
+
+		// A Time Exceeded quoting someone else's probe isn't ours
+		assert_eq!(parse_hop_reply(&time_exceeded(2), routers[0], target, 0x1234, 1), None);
+		// An unreachable target uses up every hop
+		assert_eq!(trace_path(5, |_| Ok(None)).unwrap().len(), 5);
+	}
+
+	#[test]
+	fn test_request_checksum_is_valid() {
+		let packet = build_timestamp_request(0x1234, 1, 1_000);
+		assert_eq!(packet[0], ICMP_TIMESTAMP_REQUEST);
+		assert_eq!(u16::from_be_bytes([packet[2], packet[3]]), checksum(&packet, 1));
+		assert_eq!(&packet[4..8], &[0x12, 0x34, 0x00, 0x01]);
+	}
+
+	#[test]
+	fn test_parse_canned_reply_and_offset() {
+		// Originated at 1000ms, remote received at 1510ms and replied at 1512ms, we got it at 1022ms
+		let mut reply = build_timestamp_request(0x1234, 1, 1_000);
+		reply[0] = ICMP_TIMESTAMP_REPLY;
+		reply[12..16].copy_from_slice(&1_510u32.to_be_bytes());
+		reply[16..20].copy_from_slice(&1_512u32.to_be_bytes());
+
+		let timestamps = parse_timestamp_reply(&reply, 0x1234, 1).unwrap();
+		assert_eq!(timestamps, IcmpTimestamps { originate: 1_000, receive: 1_510, transmit: 1_512 });
+		assert!(parse_timestamp_reply(&reply, 0x4321, 1).is_none());
+
+		match estimate_offset(timestamps, 1_022) {
+			TimestampProbe::Measured { round_trip_ms, clock_offset_ms } => {
+				assert_eq!(round_trip_ms, 20.0);
+				assert_eq!(clock_offset_ms, 500.0);
+			}
+			other => panic!("expected a measurement, got {:?}", other),
+		}
+	}
+
+	#[test]
+	fn test_non_standard_timestamps_not_supported() {
+		let timestamps = IcmpTimestamps { originate: 1_000, receive: NON_STANDARD_TIMESTAMP | 5, transmit: 6 };
+		assert!(matches!(estimate_offset(timestamps, 1_010), TimestampProbe::NotSupported { .. }));
+	}
+}
@@ -0,0 +1,144 @@
+// src-tauri/src/interface_policy.rs
+
+use default_net::interface::InterfaceType;
+use pnet::datalink::NetworkInterface;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// How the interface scans and stress tests go out on is picked when none is named.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InterfacePolicy {
+	/// Whatever the OS routes the default route through
+	#[default]
+	SystemDefault,
+	/// An up, wired interface with a gateway when there is one, so a laptop docked on
+	/// Ethernet doesn't test over its Wi-Fi
+	PreferWired,
+}
+
+static POLICY: Mutex<InterfacePolicy> = Mutex::new(InterfacePolicy::SystemDefault);
+
+pub fn policy() -> InterfacePolicy {
+	*POLICY.lock().unwrap()
+}
+
+/// Changes the policy for every scan and test started from now on.
+pub fn set_policy(policy: InterfacePolicy) {
+	*POLICY.lock().unwrap() = policy;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkKind {
+	Wired,
+	Wireless,
+	Other,
+}
+
+/// Guesses the link type from the OS's interface type, then its name and description.
+/// Linux reports Wi-Fi as Ethernet, so wireless-looking names win over the type.
+pub fn link_kind(name: &str, description: &str, if_type: InterfaceType) -> LinkKind {
+	classify_link(name, description, if_type, cfg!(target_os = "macos"))
+}
+
+/// `link_kind` for the given platform. macOS names Wi-Fi and Ethernet alike `en*` and
+/// reports both as Ethernet, so there only a description naming Ethernet makes one wired.
+fn classify_link(name: &str, description: &str, if_type: InterfaceType, macos: bool) -> LinkKind {
+	let name = name.to_ascii_lowercase();
+	let description = description.to_ascii_lowercase();
+	let wireless_name = ["wl", "wifi", "ath", "ra"].iter().any(|prefix| name.starts_with(prefix));
+	let wireless_description = ["wireless", "wi-fi", "wifi", "802.11", "wlan"].iter().any(|hint| description.contains(hint));
+	if if_type == InterfaceType::Wireless80211 || wireless_name || wireless_description {
+		return LinkKind::Wireless;
+	}
+
+	if macos {
+		return if description.contains("ethernet") { LinkKind::Wired } else { LinkKind::Other };
+	}
+	let wired_name = ["eth", "en", "em"].iter().any(|prefix| name.starts_with(prefix));
+	if wired_name || matches!(if_type, InterfaceType::Ethernet | InterfaceType::GigabitEthernet | InterfaceType::FastEthernetT) {
+		return LinkKind::Wired;
+	}
+	LinkKind::Other
+}
+
+/// An interface that could carry traffic out: up, with a gateway.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+	pub index: u32,
+	pub kind: LinkKind,
+}
+
+/// Up, non-loopback interfaces with a gateway, in the OS's order.
+pub fn candidates(interfaces: &[NetworkInterface]) -> Vec<Candidate> {
+	default_net::get_interfaces()
+		.into_iter()
+		.filter(|iface| iface.gateway.is_some())
+		.filter(|iface| {
+			interfaces
+				.iter()
+				.any(|pnet_iface| pnet_iface.index == iface.index && pnet_iface.is_up() && !pnet_iface.is_loopback())
+		})
+		.map(|iface| Candidate {
+			index: iface.index,
+			kind: link_kind(&iface.name, iface.description.as_deref().unwrap_or_default(), iface.if_type),
+		})
+		.collect()
+}
+
+/// Index of the interface `policy` picks among `candidates`, given the OS's default.
+/// Preferring wired keeps the default when it already is wired, and falls back to it when
+/// nothing wired is available.
+pub fn choose(candidates: &[Candidate], system_default: Option<u32>, policy: InterfacePolicy) -> Option<u32> {
+	if policy == InterfacePolicy::SystemDefault {
+		return system_default;
+	}
+	let wired = |candidate: &&Candidate| candidate.kind == LinkKind::Wired;
+	candidates
+		.iter()
+		.filter(wired)
+		.find(|candidate| Some(candidate.index) == system_default)
+		.or_else(|| candidates.iter().find(wired))
+		.map(|candidate| candidate.index)
+		.or(system_default)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_link_kind_heuristics() {
+		assert_eq!(classify_link("wlp2s0", "", InterfaceType::Ethernet, false), LinkKind::Wireless);
+		assert_eq!(classify_link("Wi-Fi", "Intel(R) Wi-Fi 6 AX201", InterfaceType::Unknown, false), LinkKind::Wireless);
+		assert_eq!(classify_link("en7", "", InterfaceType::Unknown, false), LinkKind::Wired);
+		assert_eq!(classify_link("enp0s31f6", "", InterfaceType::Ethernet, false), LinkKind::Wired);
+		assert_eq!(classify_link("tun0", "", InterfaceType::Tunnel, false), LinkKind::Other);
+	}
+
+	#[test]
+	fn test_macos_en_interfaces_are_not_assumed_wired() {
+		// en0 is the built-in Wi-Fi on most Macs, yet reports itself as Ethernet
+		assert_eq!(classify_link("en0", "", InterfaceType::Ethernet, true), LinkKind::Other);
+		assert_eq!(classify_link("en0", "Wi-Fi", InterfaceType::Ethernet, true), LinkKind::Wireless);
+		assert_eq!(classify_link("en5", "Thunderbolt Ethernet", InterfaceType::Ethernet, true), LinkKind::Wired);
+	}
+
+	#[test]
+	fn test_wired_is_preferred_over_default_wifi() {
+		let candidates = vec![
+			Candidate { index: 3, kind: LinkKind::Wireless },
+			Candidate { index: 7, kind: LinkKind::Other },
+			Candidate { index: 9, kind: LinkKind::Wired },
+		];
+		// The OS routes through Wi-Fi; the docked Ethernet link wins
+		assert_eq!(choose(&candidates, Some(3), InterfacePolicy::PreferWired), Some(9));
+		assert_eq!(choose(&candidates, Some(3), InterfacePolicy::SystemDefault), Some(3));
+
+		// A wired default stays, and without any wired link the default is kept
+		let two_wired = vec![Candidate { index: 2, kind: LinkKind::Wired }, Candidate { index: 9, kind: LinkKind::Wired }];
+		assert_eq!(choose(&two_wired, Some(9), InterfacePolicy::PreferWired), Some(9));
+		assert_eq!(choose(&candidates[..2], Some(3), InterfacePolicy::PreferWired), Some(3));
+		assert_eq!(choose(&[], None, InterfacePolicy::PreferWired), None);
+	}
+}
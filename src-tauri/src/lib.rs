@@ -1,3 +1,35 @@
+pub mod scanner;
+pub mod oui_db;
+pub mod stresser;
+pub mod monitor;
+pub mod status;
+pub mod icmp;
+pub mod history;
+pub mod syn;
+pub mod audit;
+pub mod compare;
+pub mod privileges;
+pub mod arp_cache;
+pub mod qos;
+pub mod scan_cache;
+pub mod gateways;
+pub mod quality;
+pub mod alerts;
+pub mod batch;
+pub mod source_ip;
+pub mod prometheus;
+pub mod self_test;
+pub mod rate_limit;
+pub mod target_list;
+pub mod sparkline;
+pub mod aliases;
+pub mod owd;
+pub mod scan_gate;
+pub mod interface_policy;
+pub mod scan_recording;
+pub mod device_export;
+pub mod baseline;
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -4,59 +4,348 @@
     windows_subsystem = "windows"
 )]
 
-mod scanner;
-mod oui_db;
-mod stresser;
+use cutecatnet_lib::{alerts, aliases, batch, compare, device_export, gateways, history, icmp, interface_policy, monitor, oui_db, prometheus, rate_limit, scan_cache, scan_gate, scanner, self_test, sparkline, status, stresser, target_list};
 
-use tauri::{AppHandle, Emitter};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Mutex;
 
-// Global state for the stress test engine
+/// Picks the test history backend at startup: `file` (the default) or `sqlite`
+const HISTORY_BACKEND_VAR: &str = "CUTECATNET_HISTORY_BACKEND";
+
+// Global state for the stress test engine and the device monitor
 struct AppState {
     stress_engine: Mutex<stresser::StressTestEngine>,
+    monitor: Mutex<Option<monitor::MonitorHandle>>,
+    oui_lookup: oui_db::OnlineOuiLookup,
+    scan_cache: scan_cache::ScanCache,
+    /// Combined packet budget for scans and stress tests
+    rate_limiter: rate_limit::SharedRateLimiter,
+    aliases: Arc<aliases::DeviceAliases>,
+    /// Keeps manual, monitor and batch scans from sweeping at the same time
+    scan_gate: Arc<scan_gate::ScanGate>,
+    /// Devices from the latest `scan_network` or `scan_all_interfaces`, for exporting
+    last_scan_devices: Mutex<Option<Vec<scanner::Device>>>,
+}
+
+/// A scan now would send ARP traffic through a running stress test's measurements.
+async fn stress_test_running(state: &AppState) -> bool {
+    let running = state.stress_engine.lock().await.get_current_status().await == stresser::TestStatus::Running;
+    if running {
+        log::warn!("Scanning while a stress test runs; its measurements may be affected");
+    }
+    running
+}
+
+#[tauri::command]
+async fn scan_network(
+    options: Option<scanner::ScanOptions>,
+    force: Option<bool>,
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<scanner::ScanSummary, String> {
+    let options = scanner::ScanOptions {
+        rate_limiter: Some(state.rate_limiter.clone()),
+        aliases: Some(Arc::clone(&state.aliases)),
+        ..options.unwrap_or_default()
+    };
+    let online_oui_lookup = options.online_oui_lookup;
+    let stress_test_running = stress_test_running(&state).await;
+    // Exclusions, host budgets, source addresses, interfaces, promiscuous mode and how our own
+    // manufacturer is named change the result, so scans with different ones are cached apart.
+    // Ordering, retries and buffer sizes only change how the sweep runs.
+    let key = format!(
+        "{}|{:?}|{:?}|{:?}|{:?}|{:?}|{}|{}",
+        scanner::default_scan_key().map_err(|e| e.to_string())?,
+        options.exclude_ips,
+        options.exclude_macs,
+        options.max_hosts,
+        options.source_ip,
+        options.interfaces,
+        options.promiscuous,
+        options.own_manufacturer_from_oui
+    );
+    // A cached result has no replies to record
+    let force = force.unwrap_or(false) || options.record_to.is_some();
+    let mut summary = state
+        .scan_cache
+        .get_or_scan(&key, force, || {
+            state.scan_gate.run(|| {
+                scanner::perform_scan(options, move |device| {
+                    let _ = app_handle.emit("device-found", device);
+                })
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if online_oui_lookup {
+        scanner::resolve_unknown_manufacturers(&mut summary.devices, &state.oui_lookup).await;
+    }
+    // A cached scan may predate the latest alias changes
+    state.aliases.apply(&mut summary.devices);
+    summary.stress_test_running = stress_test_running;
+    *state.last_scan_devices.lock().await = Some(summary.devices.clone());
+    Ok(summary)
+}
+
+/// The device with `mac` and its current address, stopping the sweep as soon as it answers.
+#[tauri::command]
+async fn find_by_mac(mac: String, state: tauri::State<'_, AppState>) -> Result<Option<scanner::Device>, String> {
+    let options = scanner::ScanOptions {
+        rate_limiter: Some(state.rate_limiter.clone()),
+        aliases: Some(Arc::clone(&state.aliases)),
+        ..scanner::ScanOptions::default()
+    };
+    state
+        .scan_gate
+        .run(|| scanner::find_by_mac(&mac, options))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn scan_from_recording(
+    path: String,
+    options: Option<scanner::ScanOptions>,
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<scanner::ScanSummary, String> {
+    let options = scanner::ScanOptions {
+        aliases: Some(Arc::clone(&state.aliases)),
+        ..options.unwrap_or_default()
+    };
+    scanner::perform_scan_from_recording(std::path::Path::new(&path), &options, |device| {
+        let _ = app_handle.emit("device-found", device);
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn scan_all_interfaces(
+    options: Option<scanner::ScanOptions>,
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<scanner::MultiInterfaceScan, String> {
+    let options = scanner::ScanOptions {
+        rate_limiter: Some(state.rate_limiter.clone()),
+        aliases: Some(Arc::clone(&state.aliases)),
+        ..options.unwrap_or_default()
+    };
+    let online_oui_lookup = options.online_oui_lookup;
+    let stress_test_running = stress_test_running(&state).await;
+    let mut scan = state
+        .scan_gate
+        .run(|| async move {
+            Ok(scanner::scan_all_interfaces(options, move |device| {
+                let _ = app_handle.emit("device-found", device);
+            })
+            .await)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if online_oui_lookup {
+        scanner::resolve_unknown_manufacturers(scan.devices_mut(), &state.oui_lookup).await;
+    }
+    scan.set_stress_test_running(stress_test_running);
+    *state.last_scan_devices.lock().await = Some(scan.devices_mut().to_vec());
+    Ok(scan)
+}
+
+/// Writes `devices`, or the latest scan's when none are given, to `path` as CSV or JSON.
+#[tauri::command]
+async fn export_devices(
+    format: device_export::ExportFormat,
+    path: String,
+    devices: Option<Vec<scanner::Device>>,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let devices = match devices {
+        Some(devices) => devices,
+        None => state.last_scan_devices.lock().await.clone().ok_or("No scan results to export yet")?,
+    };
+    device_export::export_devices(&devices, format, std::path::Path::new(&path)).map_err(|e| e.to_string())
+}
+
+/// Names the device with `mac`; a blank name removes the alias.
+#[tauri::command]
+async fn set_device_alias(mac: String, name: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.aliases.set(&mac, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_device_aliases(state: tauri::State<'_, AppState>) -> Result<BTreeMap<String, String>, String> {
+    Ok(state.aliases.all())
+}
+
+/// Disruptive diagnostic: neighbors that accept the announcement update their ARP tables.
+#[tauri::command]
+async fn send_gratuitous_arp(ip: String, mac: String) -> Result<(), String> {
+    let ip = ip.parse().map_err(|_| format!("Invalid IPv4 address: {}", ip))?;
+    let mac = mac.parse().map_err(|_| format!("Invalid MAC address: {}", mac))?;
+    tokio::task::spawn_blocking(move || scanner::send_gratuitous_arp(ip, mac))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn estimate_scan_time(target_cidr: String) -> Result<scanner::ScanEstimate, String> {
+    let network = target_cidr
+        .parse()
+        .map_err(|_| format!("Invalid IPv4 CIDR: {}", target_cidr))?;
+    Ok(scanner::estimate_scan_time(network))
+}
+
+#[tauri::command]
+async fn start_monitor(
+    interval_secs: u64,
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<(), String> {
+    if interval_secs < monitor::MIN_MONITOR_INTERVAL_SECS {
+        return Err(format!(
+            "Monitor interval must be at least {} seconds",
+            monitor::MIN_MONITOR_INTERVAL_SECS
+        ));
+    }
+
+    let mut current = state.monitor.lock().await;
+    if current.as_ref().is_some_and(|handle| handle.is_running()) {
+        return Err("Monitor already running".to_string());
+    }
+
+    let rate_limiter = state.rate_limiter.clone();
+    let device_aliases = Arc::clone(&state.aliases);
+    let gate = Arc::clone(&state.scan_gate);
+    let handle = monitor::spawn_monitor(
+        Duration::from_secs(interval_secs),
+        move || {
+            let options = scanner::ScanOptions {
+                rate_limiter: Some(rate_limiter.clone()),
+                aliases: Some(Arc::clone(&device_aliases)),
+                ..scanner::ScanOptions::default()
+            };
+            let gate = Arc::clone(&gate);
+            async move {
+                gate.run(|| scanner::perform_scan(options, |_: &scanner::Device| {}))
+                    .await
+                    .map(|summary| summary.devices)
+            }
+        },
+        move |event| {
+            let _ = match event {
+                monitor::MonitorEvent::Joined(device) => app_handle.emit("device-joined", device),
+                monitor::MonitorEvent::Left(device) => app_handle.emit("device-left", device),
+            };
+        },
+    );
+    *current = Some(handle);
+    Ok(())
 }
 
 #[tauri::command]
-async fn scan_network() -> Result<Vec<scanner::Device>, String> {
-    scanner::perform_scan().await.map_err(|e| e.to_string())
+async fn stop_monitor(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if let Some(handle) = state.monitor.lock().await.take() {
+        handle.stop();
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn validate_stress_target(ip: String, state: tauri::State<'_, AppState>) -> Result<bool, String> {
+async fn pause_monitor(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    match state.monitor.lock().await.as_ref() {
+        Some(handle) if handle.is_running() => {
+            handle.pause();
+            Ok(())
+        }
+        _ => Err("Monitor is not running".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn resume_monitor(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    match state.monitor.lock().await.as_ref() {
+        Some(handle) if handle.is_running() => {
+            handle.resume();
+            Ok(())
+        }
+        _ => Err("Monitor is not running".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn validate_stress_target(ip: String, state: tauri::State<'_, AppState>) -> Result<bool, stresser::ErrorResponse> {
     let engine = state.stress_engine.lock().await;
     match engine.validate_target_ip(&ip).await {
         Ok(_) => Ok(true),
-        Err(e) => Err(e.to_string()),
+        Err(e) => Err(e.into()),
     }
 }
 
+/// Validates many targets in one call; each keeps its own error code and reason.
+#[tauri::command]
+async fn validate_targets(
+    ips: Vec<String>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<stresser::TargetValidation>, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.validate_targets(&ips).await)
+}
+
+/// Seconds until `ip` can be tested again, 0 when it isn't cooling down.
+#[tauri::command]
+async fn get_target_cooldown(ip: String, state: tauri::State<'_, AppState>) -> Result<u64, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.target_cooldown_remaining(&ip).await)
+}
+
+/// Checks a whole config without starting it; one that passes may still come back with
+/// warnings about limits it's close to.
+#[tauri::command]
+async fn validate_stress_config(
+    config: stresser::StressTestConfig,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<stresser::SafetyWarning>, stresser::ErrorResponse> {
+    let engine = state.stress_engine.lock().await;
+    engine.validate_test_config(&config).await.map_err(Into::into)
+}
+
 #[tauri::command]
 async fn start_stress_test(
     config: stresser::StressTestConfig,
     state: tauri::State<'_, AppState>,
-    app_handle: AppHandle,
-) -> Result<String, String> {
+) -> Result<String, stresser::ErrorResponse> {
     let mut engine = state.stress_engine.lock().await;
+    engine.start_stress_test(config).await.map_err(Into::into)
+}
 
-    let test_result = engine.start_stress_test(config.clone()).await;
-
-    // Drop the engine guard early to avoid lifetime issues
-    drop(engine);
+#[tauri::command]
+async fn rerun_last_test(state: tauri::State<'_, AppState>) -> Result<String, stresser::ErrorResponse> {
+    let mut engine = state.stress_engine.lock().await;
+    engine.rerun_last_test().await.map_err(Into::into)
+}
 
-    match test_result {
-        Ok(test_id) => {
-            // For now, skip the real-time updates to avoid lifetime issues
-            // This can be implemented later using a different approach
-            Ok(test_id)
-        }
-        Err(e) => Err(e.to_string()),
-    }
+#[tauri::command]
+async fn replay_test(test_id: String, state: tauri::State<'_, AppState>) -> Result<String, stresser::ErrorResponse> {
+    let mut engine = state.stress_engine.lock().await;
+    engine.replay_test(&test_id).await.map_err(Into::into)
 }
 
 #[tauri::command]
-async fn stop_stress_test(state: tauri::State<'_, AppState>) -> Result<(), String> {
+async fn stop_stress_test(state: tauri::State<'_, AppState>) -> Result<(), stresser::ErrorResponse> {
     let engine = state.stress_engine.lock().await;
-    engine.stop_current_test().await.map_err(|e| e.to_string())
+    engine.stop_current_test().await.map_err(Into::into)
+}
+
+#[tauri::command]
+async fn reset_engine_state(state: tauri::State<'_, AppState>) -> Result<(), stresser::ErrorResponse> {
+    let mut engine = state.stress_engine.lock().await;
+    engine.reset_state().await.map_err(Into::into)
 }
 
 #[tauri::command]
@@ -77,31 +366,335 @@ async fn get_current_stress_test(state: tauri::State<'_, AppState>) -> Result<Op
     Ok(engine.get_current_test().await)
 }
 
+/// Current test's metrics in Prometheus text format, for scraping or a Pushgateway.
+#[tauri::command]
+async fn metrics_prometheus(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    let engine = state.stress_engine.lock().await;
+    let test = engine.get_current_test().await.ok_or("No current test")?;
+    Ok(prometheus::format_metrics(&test.test_id, &engine.get_current_metrics().await))
+}
+
+/// `metric` of a current or past test downsampled to `buckets` points (at most
+/// `MAX_SPARKLINE_BUCKETS`), for a small fixed-size chart.
+#[tauri::command]
+async fn get_metric_sparkline(
+    test_id: String,
+    metric: sparkline::SparklineMetric,
+    buckets: usize,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<f64>, stresser::ErrorResponse> {
+    let engine = state.stress_engine.lock().await;
+    let series = engine.metric_series(&test_id).await?;
+    Ok(sparkline::sparkline(&series, metric, buckets.min(sparkline::MAX_SPARKLINE_BUCKETS)))
+}
+
+#[tauri::command]
+async fn get_test_history(state: tauri::State<'_, AppState>) -> Result<Vec<stresser::TestResult>, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.get_test_history())
+}
+
+#[tauri::command]
+async fn query_test_history(
+    filter: history::HistoryFilter,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<stresser::TestResult>, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.query_test_history(&filter))
+}
+
+#[tauri::command]
+async fn compare_tests(
+    id_a: String,
+    id_b: String,
+    state: tauri::State<'_, AppState>,
+) -> Result<compare::TestComparison, stresser::ErrorResponse> {
+    let engine = state.stress_engine.lock().await;
+    engine.compare_tests(&id_a, &id_b).map_err(Into::into)
+}
+
+/// Pins `test_id` as the baseline later tests of its target and type are compared against.
+#[tauri::command]
+async fn set_baseline(test_id: String, state: tauri::State<'_, AppState>) -> Result<(), stresser::ErrorResponse> {
+    let engine = state.stress_engine.lock().await;
+    engine.set_baseline(&test_id).map_err(Into::into)
+}
+
+#[tauri::command]
+async fn set_audit_log(path: String, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.stress_engine.lock().await.set_audit_log(PathBuf::from(path));
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_safety_limits(state: tauri::State<'_, AppState>) -> Result<stresser::SafetyLimits, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.safety_limits().clone())
+}
+
+/// The stress engine's internal state, for diagnostics.
+#[tauri::command]
+async fn get_engine_snapshot(state: tauri::State<'_, AppState>) -> Result<stresser::EngineStateSnapshot, String> {
+    let engine = state.stress_engine.lock().await;
+    Ok(engine.snapshot().await)
+}
+
+#[tauri::command]
+async fn get_interface_mtu() -> Result<stresser::MtuInfo, String> {
+    Ok(stresser::detect_mtu())
+}
+
+#[tauri::command]
+async fn get_system_status(state: tauri::State<'_, AppState>) -> Result<status::SystemStatus, String> {
+    let engine_status = state.stress_engine.lock().await.get_current_status().await;
+    Ok(status::collect_system_status(engine_status))
+}
+
+/// Loopback-only smoke test; runs on its own engine so it doesn't touch the user's test state.
+#[tauri::command]
+async fn self_test() -> Result<self_test::SelfTestReport, String> {
+    Ok(self_test::run_self_test().await)
+}
+
+/// ICMP timestamp exchange with a private target.
+#[tauri::command]
+async fn probe_timestamp(ip: String, state: tauri::State<'_, AppState>) -> Result<icmp::TimestampProbe, String> {
+    let target = {
+        let engine = state.stress_engine.lock().await;
+        let target = engine.resolve_target(&ip).await.map_err(|e| e.to_string())?;
+        engine.validate_target_ip(&target.to_string()).await.map_err(|e| e.to_string())?;
+        target
+    };
+    icmp::probe_timestamp(target).await
+}
+
+/// Pings a private target `count` times, `interval_ms` apart (like `ping -i`). The interval
+/// may not be shorter than the stress test rate limit allows.
+#[tauri::command]
+async fn measure_latency(
+    ip: String,
+    count: u32,
+    interval_ms: Option<u64>,
+    state: tauri::State<'_, AppState>,
+) -> Result<icmp::LatencyMeasurement, String> {
+    let (target, min_interval) = {
+        let engine = state.stress_engine.lock().await;
+        let target = engine.resolve_target(&ip).await.map_err(|e| e.to_string())?;
+        engine.validate_target_ip(&target.to_string()).await.map_err(|e| e.to_string())?;
+        (target, icmp::min_probe_interval(engine.safety_limits().max_packets_per_second))
+    };
+    let interval = interval_ms.map_or(icmp::DEFAULT_PROBE_INTERVAL, Duration::from_millis);
+    if interval < min_interval {
+        return Err(format!("Interval must be at least {} ms", min_interval.as_millis()));
+    }
+    if interval > icmp::MAX_PROBE_INTERVAL {
+        return Err(format!("Interval must be at most {} ms", icmp::MAX_PROBE_INTERVAL.as_millis()));
+    }
+    icmp::measure_latency(target, count, interval).await
+}
+
+/// Hop-by-hop path to a private target, one probe per TTL.
+#[tauri::command]
+async fn traceroute(ip: String, state: tauri::State<'_, AppState>) -> Result<Vec<icmp::Hop>, String> {
+    let target = {
+        let engine = state.stress_engine.lock().await;
+        let target = engine.resolve_target(&ip).await.map_err(|e| e.to_string())?;
+        engine.validate_target_ip(&target.to_string()).await.map_err(|e| e.to_string())?;
+        target
+    };
+    icmp::traceroute(target).await
+}
+
+/// Largest unfragmented packet that reaches a private target.
+#[tauri::command]
+async fn discover_path_mtu(ip: String, state: tauri::State<'_, AppState>) -> Result<icmp::PathMtu, String> {
+    let target = {
+        let engine = state.stress_engine.lock().await;
+        let target = engine.resolve_target(&ip).await.map_err(|e| e.to_string())?;
+        engine.validate_target_ip(&target.to_string()).await.map_err(|e| e.to_string())?;
+        target
+    };
+    icmp::discover_path_mtu(target).await
+}
+
+#[tauri::command]
+async fn diagnose_gateways() -> Result<Vec<gateways::GatewayDiagnosis>, String> {
+    Ok(gateways::diagnose_gateways().await)
+}
+
+#[tauri::command]
+async fn execute_batch(
+    steps: Vec<batch::BatchStep>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<batch::BatchStepResult>, String> {
+    let rate_limiter = &state.rate_limiter;
+    Ok(batch::execute_batch(steps, &state.stress_engine, |options| {
+        let options = scanner::ScanOptions {
+            rate_limiter: Some(rate_limiter.clone()),
+            aliases: Some(Arc::clone(&state.aliases)),
+            ..options
+        };
+        state.scan_gate.run(|| scanner::perform_scan(options, |_: &scanner::Device| {}))
+    })
+    .await)
+}
+
+#[tauri::command]
+async fn load_target_list(path: String) -> Result<target_list::TargetList, String> {
+    target_list::load_target_list(&PathBuf::from(&path)).map_err(|e| format!("Could not read {}: {}", path, e))
+}
+
+/// Runs `config` against each target in turn; every test still goes through the engine's
+/// private-range guard, cooldowns and limits.
+#[tauri::command]
+async fn run_test_suite(
+    config: stresser::StressTestConfig,
+    targets: Vec<target_list::ListedTarget>,
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<target_list::SuiteEntry>, String> {
+    Ok(target_list::run_test_suite(config, targets, &state.stress_engine).await)
+}
+
+/// Which interface scans and tests use when none is named: the system default, or a wired
+/// link over Wi-Fi when both have a gateway.
+#[tauri::command]
+async fn set_interface_policy(policy: interface_policy::InterfacePolicy) -> Result<(), String> {
+    interface_policy::set_policy(policy);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_interface_policy() -> Result<interface_policy::InterfacePolicy, String> {
+    Ok(interface_policy::policy())
+}
+
+/// How many scans may sweep at once, and whether one over the limit waits or is refused.
+#[tauri::command]
+async fn set_scan_concurrency(
+    max_concurrent: usize,
+    reject_when_busy: bool,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    state.scan_gate.configure(max_concurrent, reject_when_busy);
+    Ok(())
+}
+
+/// Sets the combined packets-per-second ceiling scans and stress tests share.
+#[tauri::command]
+async fn set_packet_rate_ceiling(packets_per_second: u32, state: tauri::State<'_, AppState>) -> Result<(), String> {
+    if packets_per_second == 0 {
+        return Err("Packet rate ceiling must be at least 1 packet per second".to_string());
+    }
+    state.rate_limiter.set_packets_per_second(packets_per_second);
+    Ok(())
+}
+
 #[tauri::command]
 async fn confirm_stress_alive(state: tauri::State<'_, AppState>) -> Result<(), String> {
     let engine = state.stress_engine.lock().await;
-    engine.confirm_alive().await;
+    if !engine.confirm_alive().await {
+        return Err("Confirmation ignored: confirmations are arriving faster than a person could give them".to_string());
+    }
     Ok(())
 }
 
 fn main() {
     env_logger::init();
 
-    let app_state = AppState {
-        stress_engine: Mutex::new(stresser::StressTestEngine::new()),
-    };
-
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .manage(app_state)
+        .setup(|app| {
+            let data_dir = app.path().app_data_dir()?;
+            let handle = app.handle().clone();
+            let alert_handle = app.handle().clone();
+            let rate_limiter = rate_limit::SharedRateLimiter::default();
+            let history_backend = std::env::var(HISTORY_BACKEND_VAR)
+                .ok()
+                .and_then(|value| {
+                    value
+                        .parse::<history::HistoryBackend>()
+                        .map_err(|e| log::warn!("{}; using the history file", e))
+                        .ok()
+                })
+                .unwrap_or_default();
+            let scan_gate = Arc::new(scan_gate::ScanGate::default());
+            let engine = stresser::StressTestEngine::new()
+                .with_data_dir(data_dir.clone())
+                .with_scan_gate(Arc::clone(&scan_gate))
+                .with_history_backend(history_backend)
+                .with_rate_limiter(rate_limiter.clone())
+                .with_update_sink(Arc::new(move |update: stresser::StressTestUpdate| {
+                    let _ = handle.emit("stress_test_update", update);
+                }))
+                .with_alert_sink(Arc::new(move |event: alerts::AlertEvent| {
+                    let name = match event.kind {
+                        alerts::AlertKind::Alert => "stress_test_alert",
+                        alerts::AlertKind::Clear => "stress_test_alert_clear",
+                    };
+                    let _ = alert_handle.emit(name, event);
+                }));
+            app.manage(AppState {
+                stress_engine: Mutex::new(engine),
+                monitor: Mutex::new(None),
+                oui_lookup: oui_db::OnlineOuiLookup::new(data_dir.join(oui_db::OUI_CACHE_FILE)),
+                scan_cache: scan_cache::ScanCache::new(scan_cache::DEFAULT_SCAN_CACHE_TTL),
+                rate_limiter,
+                aliases: Arc::new(aliases::DeviceAliases::new(data_dir.join(aliases::ALIASES_FILE))),
+                scan_gate,
+                last_scan_devices: Mutex::new(None),
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             scan_network,
+            scan_from_recording,
+            find_by_mac,
+            scan_all_interfaces,
+            export_devices,
+            estimate_scan_time,
+            send_gratuitous_arp,
+            set_device_alias,
+            get_device_aliases,
+            start_monitor,
+            stop_monitor,
+            pause_monitor,
+            resume_monitor,
             validate_stress_target,
+            validate_targets,
+            validate_stress_config,
+            get_target_cooldown,
             start_stress_test,
+            rerun_last_test,
+            replay_test,
             stop_stress_test,
+            reset_engine_state,
             get_stress_test_status,
             get_stress_test_metrics,
             get_current_stress_test,
+            metrics_prometheus,
+            get_metric_sparkline,
+            get_safety_limits,
+            get_engine_snapshot,
+            get_test_history,
+            query_test_history,
+            set_audit_log,
+            compare_tests,
+            set_baseline,
+            get_interface_mtu,
+            get_system_status,
+            self_test,
+            probe_timestamp,
+            measure_latency,
+            discover_path_mtu,
+            traceroute,
+            diagnose_gateways,
+            execute_batch,
+            load_target_list,
+            run_test_suite,
+            set_packet_rate_ceiling,
+            set_scan_concurrency,
+            set_interface_policy,
+            get_interface_policy,
             confirm_stress_alive
         ])
         .run(tauri::generate_context!())
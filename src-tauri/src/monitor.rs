@@ -0,0 +1,237 @@
+// src-tauri/src/monitor.rs
+
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::scanner::{Device, ScanError};
+
+/// Scans take ~5 seconds; anything tighter would just keep the network busy.
+pub const MIN_MONITOR_INTERVAL_SECS: u64 = 10;
+
+/// Remembers the devices seen in the previous scan so consecutive scans can be diffed.
+#[derive(Default)]
+pub struct DeviceTracker {
+	known: HashMap<String, Device>,
+}
+
+#[derive(Default, Debug)]
+pub struct DeviceChanges {
+	pub joined: Vec<Device>,
+	pub left: Vec<Device>,
+}
+
+impl DeviceTracker {
+	/// Replaces the known set with `devices` (keyed by MAC) and returns what changed.
+	pub fn update(&mut self, devices: Vec<Device>) -> DeviceChanges {
+		let current: HashMap<String, Device> = devices
+			.into_iter()
+			.map(|device| (device.mac_address().to_string(), device))
+			.collect();
+
+		let joined = current
+			.iter()
+			.filter(|(mac, _)| !self.known.contains_key(*mac))
+			.map(|(_, device)| device.clone())
+			.collect();
+		let left = self
+			.known
+			.iter()
+			.filter(|(mac, _)| !current.contains_key(*mac))
+			.map(|(_, device)| device.clone())
+			.collect();
+
+		self.known = current;
+		DeviceChanges { joined, left }
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum MonitorEvent {
+	Joined(Device),
+	Left(Device),
+}
+
+pub struct MonitorHandle {
+	task: JoinHandle<()>,
+	paused: watch::Sender<bool>,
+}
+
+impl MonitorHandle {
+	pub fn is_running(&self) -> bool {
+		!self.task.is_finished()
+	}
+
+	/// Skips scans until `resume`, e.g. so scan traffic doesn't skew a stress test. The
+	/// device tracker is kept, so joins and leaves are diffed against the last scan before
+	/// the pause.
+	pub fn pause(&self) {
+		self.paused.send_replace(true);
+	}
+
+	/// Scans right away, then carries on at the usual interval.
+	pub fn resume(&self) {
+		self.paused.send_replace(false);
+	}
+
+	pub fn is_paused(&self) -> bool {
+		*self.paused.borrow()
+	}
+
+	pub fn stop(self) {
+		self.task.abort();
+	}
+}
+
+/// Spawns a task that calls `scan` every `interval` and reports joins/leaves through `on_event`.
+pub fn spawn_monitor<S, Fut, E>(interval: Duration, scan: S, on_event: E) -> MonitorHandle
+where
+	S: Fn() -> Fut + Send + 'static,
+	Fut: Future<Output = Result<Vec<Device>, ScanError>> + Send,
+	E: Fn(MonitorEvent) + Send + 'static,
+{
+	let (paused, mut paused_rx) = watch::channel(false);
+	let task = tokio::spawn(async move {
+		let mut tracker = DeviceTracker::default();
+		let mut ticker = tokio::time::interval(interval);
+		loop {
+			ticker.tick().await;
+			if *paused_rx.borrow() {
+				debug!("Monitor paused");
+				if paused_rx.wait_for(|paused| !*paused).await.is_err() {
+					break;
+				}
+				ticker.reset();
+			}
+			match scan().await {
+				Ok(devices) => {
+					let changes = tracker.update(devices);
+					debug!("Monitor scan: {} joined, {} left", changes.joined.len(), changes.left.len());
+					for device in changes.joined {
+						on_event(MonitorEvent::Joined(device));
+					}
+					for device in changes.left {
+						on_event(MonitorEvent::Left(device));
+					}
+				}
+				Err(e) => warn!("Monitor scan failed: {}", e),
+			}
+		}
+	});
+
+	MonitorHandle { task, paused }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::{Arc, Mutex};
+
+	#[test]
+	fn test_tracker_diffs_scans() {
+		let mut tracker = DeviceTracker::default();
+		let a = Device::sample("192.168.1.2", "aa:aa:aa:aa:aa:01", "eth0");
+		let b = Device::sample("192.168.1.3", "aa:aa:aa:aa:aa:02", "eth0");
+
+		let changes = tracker.update(vec![a.clone(), b.clone()]);
+		assert_eq!(changes.joined.len(), 2);
+		assert!(changes.left.is_empty());
+
+		let changes = tracker.update(vec![b]);
+		assert!(changes.joined.is_empty());
+		assert_eq!(changes.left.len(), 1);
+		assert_eq!(changes.left[0].mac_address(), "aa:aa:aa:aa:aa:01");
+	}
+
+	#[tokio::test]
+	async fn test_monitor_emits_join_for_new_device() {
+		let scans = Arc::new(AtomicUsize::new(0));
+		let events = Arc::new(Mutex::new(Vec::new()));
+
+		let scan_count = Arc::clone(&scans);
+		let scan = move || {
+			let n = scan_count.fetch_add(1, Ordering::SeqCst);
+			async move {
+				let mut devices = vec![Device::sample("192.168.1.2", "aa:aa:aa:aa:aa:01", "eth0")];
+				if n > 0 {
+					devices.push(Device::sample("192.168.1.3", "aa:aa:aa:aa:aa:02", "eth0"));
+				}
+				Ok::<_, ScanError>(devices)
+			}
+		};
+		let recorded = Arc::clone(&events);
+		let handle = spawn_monitor(Duration::from_millis(20), scan, move |event| {
+			recorded.lock().unwrap().push(event);
+		});
+
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		assert!(handle.is_running());
+		handle.stop();
+
+		let events = events.lock().unwrap();
+		let joined: Vec<&str> = events
+			.iter()
+			.filter_map(|event| match event {
+				MonitorEvent::Joined(device) => Some(device.mac_address()),
+				MonitorEvent::Left(_) => None,
+			})
+			.collect();
+		assert_eq!(joined, vec!["aa:aa:aa:aa:aa:01", "aa:aa:aa:aa:aa:02"]);
+		assert!(scans.load(Ordering::SeqCst) >= 2);
+	}
+
+	#[tokio::test]
+	async fn test_pause_keeps_tracker_state() {
+		let scans = Arc::new(AtomicUsize::new(0));
+		let new_device_online = Arc::new(std::sync::atomic::AtomicBool::new(false));
+		let events = Arc::new(Mutex::new(Vec::new()));
+
+		let scan_count = Arc::clone(&scans);
+		let online = Arc::clone(&new_device_online);
+		let scan = move || {
+			scan_count.fetch_add(1, Ordering::SeqCst);
+			let online = online.load(Ordering::SeqCst);
+			async move {
+				let mut devices = vec![Device::sample("192.168.1.2", "aa:aa:aa:aa:aa:01", "eth0")];
+				if online {
+					devices.push(Device::sample("192.168.1.3", "aa:aa:aa:aa:aa:02", "eth0"));
+				}
+				Ok::<_, ScanError>(devices)
+			}
+		};
+		let recorded = Arc::clone(&events);
+		let handle = spawn_monitor(Duration::from_millis(20), scan, move |event| {
+			recorded.lock().unwrap().push(event);
+		});
+
+		tokio::time::sleep(Duration::from_millis(60)).await;
+		handle.pause();
+		assert!(handle.is_paused());
+		tokio::time::sleep(Duration::from_millis(30)).await;
+		let scans_at_pause = scans.load(Ordering::SeqCst);
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		assert_eq!(scans.load(Ordering::SeqCst), scans_at_pause);
+		assert!(handle.is_running());
+
+		new_device_online.store(true, Ordering::SeqCst);
+		handle.resume();
+		tokio::time::sleep(Duration::from_millis(60)).await;
+		handle.stop();
+		assert!(scans.load(Ordering::SeqCst) > scans_at_pause);
+
+		// The device seen before the pause isn't reported as joining again
+		let events = events.lock().unwrap();
+		let joined: Vec<&str> = events
+			.iter()
+			.filter_map(|event| match event {
+				MonitorEvent::Joined(device) => Some(device.mac_address()),
+				MonitorEvent::Left(_) => None,
+			})
+			.collect();
+		assert_eq!(joined, vec!["aa:aa:aa:aa:aa:01", "aa:aa:aa:aa:aa:02"]);
+	}
+}
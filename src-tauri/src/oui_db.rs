@@ -1,32 +1,321 @@
-use std::collections::HashMap;
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What loading the OUI CSV did, so a corrupted or wrong-format file is detectable
+/// instead of every vendor silently coming back "Unknown".
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct OuiParseSummary {
+	/// Data lines read, header excluded
+	pub lines_processed: usize,
+	pub entries_loaded: usize,
+	pub lines_skipped: usize,
+}
+
+impl OuiParseSummary {
+	/// Nothing loaded, or more lines rejected than accepted: probably not an IEEE OUI CSV.
+	pub fn looks_malformed(&self) -> bool {
+		self.entries_loaded == 0 || self.lines_skipped > self.entries_loaded
+	}
+}
+
+/// How well the database covers the MACs it's been asked about, to judge when it needs
+/// updating.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct OuiLookupStats {
+	pub lookups: u64,
+	pub hits: u64,
+	pub misses: u64,
+	/// OUI prefixes (`aa:bb:cc`) no vendor was found for
+	pub missed_prefixes: BTreeSet<String>,
+}
 
 pub struct OuiDb {
 	by_prefix: HashMap<String, String>,
+	summary: OuiParseSummary,
+	stats: Mutex<OuiLookupStats>,
 }
 
 impl OuiDb {
 	pub fn new_embedded() -> Self {
-		let csv = include_str!("../assets/oui.csv");
+		Self::from_csv(include_str!("../assets/oui.csv"))
+	}
+
+	/// Parses an IEEE `registry,assignment,organizationName,...` CSV, skipping lines that
+	/// don't have an assignment and organization.
+	pub fn from_csv(csv: &str) -> Self {
 		let mut by_prefix = HashMap::new();
-		for (idx, line) in csv.lines().enumerate() {
-			if idx == 0 { continue; }
+		let mut summary = OuiParseSummary::default();
+		for line in csv.lines().skip(1) {
+			summary.lines_processed += 1;
 			let parts: Vec<&str> = line.split(',').collect();
-			if parts.len() < 3 { continue; }
+			if parts.len() < 3 || parts[1].trim().is_empty() {
+				summary.lines_skipped += 1;
+				continue;
+			}
 			let assignment = parts[1].trim();
 			let org = parts[2].trim().to_string();
 			let prefix = assignment.replace('-', ":").to_lowercase();
 			by_prefix.insert(prefix, org);
 		}
-		Self { by_prefix }
+		summary.entries_loaded = by_prefix.len();
+		Self { by_prefix, summary, stats: Mutex::default() }
+	}
+
+	pub fn len(&self) -> usize {
+		self.by_prefix.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.by_prefix.is_empty()
+	}
+
+	pub fn summary(&self) -> &OuiParseSummary {
+		&self.summary
 	}
 
 	pub fn lookup(&self, mac: &str) -> Option<&str> {
 		let mac = mac.to_lowercase();
 		// MA-L (first 3 bytes): 00:11:22
 		let pref3 = mac.get(0..8);
-		if let Some(p) = pref3.and_then(|p| self.by_prefix.get(p)) {
-			return Some(p.as_str());
+		let vendor = pref3.and_then(|p| self.by_prefix.get(p)).map(String::as_str);
+
+		let mut stats = self.stats.lock().unwrap();
+		stats.lookups += 1;
+		if vendor.is_some() {
+			stats.hits += 1;
+		} else {
+			stats.misses += 1;
+			stats.missed_prefixes.insert(pref3.unwrap_or(&mac).to_string());
+		}
+		vendor
+	}
+
+	/// Hits and misses of every `lookup` so far.
+	pub fn lookup_stats(&self) -> OuiLookupStats {
+		self.stats.lock().unwrap().clone()
+	}
+}
+
+/// How often an `OuiCache` answered without going to the database.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OuiCacheStats {
+	pub hits: u64,
+	pub misses: u64,
+}
+
+#[derive(Default)]
+struct OuiCacheState {
+	vendors: HashMap<String, Option<String>>,
+	stats: OuiCacheStats,
+}
+
+/// Vendors already resolved, keyed by OUI prefix, in front of an `OuiDb`. A large subnet is
+/// often mostly one or two vendors, so each prefix is resolved once. Clones share the cache,
+/// letting several enrichment tasks use it at once.
+#[derive(Clone)]
+pub struct OuiCache {
+	db: Arc<OuiDb>,
+	state: Arc<Mutex<OuiCacheState>>,
+}
+
+impl OuiCache {
+	pub fn new(db: Arc<OuiDb>) -> Self {
+		Self { db, state: Arc::default() }
+	}
+
+	pub fn db(&self) -> &OuiDb {
+		&self.db
+	}
+
+	/// Vendor for `mac`, from the cache when its prefix has been seen before, unknown
+	/// prefixes included.
+	pub fn lookup(&self, mac: &str) -> Option<String> {
+		let mac = mac.to_lowercase();
+		let Some(prefix) = mac.get(0..8) else {
+			return self.db.lookup(&mac).map(str::to_string);
+		};
+
+		let mut state = self.state.lock().unwrap();
+		if let Some(vendor) = state.vendors.get(prefix).cloned() {
+			state.stats.hits += 1;
+			return vendor;
+		}
+		state.stats.misses += 1;
+		let vendor = self.db.lookup(&mac).map(str::to_string);
+		state.vendors.insert(prefix.to_string(), vendor.clone());
+		vendor
+	}
+
+	pub fn stats(&self) -> OuiCacheStats {
+		self.state.lock().unwrap().stats
+	}
+}
+
+pub type VendorFuture = Pin<Box<dyn Future<Output = Option<String>> + Send>>;
+/// Resolves an OUI prefix (`aa:bb:cc`) to a vendor name, `None` when unknown or unreachable.
+pub type VendorFetch = Arc<dyn Fn(String) -> VendorFuture + Send + Sync>;
+
+const MACVENDORS_URL: &str = "https://api.macvendors.com";
+const ONLINE_LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+pub const OUI_CACHE_FILE: &str = "oui_cache.json";
+
+/// Opt-in fallback for prefixes the embedded database doesn't know. Only the OUI prefix is
+/// sent, never the full MAC, and results are cached on disk to keep calls to a minimum.
+pub struct OnlineOuiLookup {
+	cache_path: PathBuf,
+	cache: Mutex<HashMap<String, String>>,
+	fetch: VendorFetch,
+}
+
+impl OnlineOuiLookup {
+	pub fn new(cache_path: PathBuf) -> Self {
+		Self::with_fetcher(cache_path, macvendors_fetcher())
+	}
+
+	pub fn with_fetcher(cache_path: PathBuf, fetch: VendorFetch) -> Self {
+		let cache = std::fs::read_to_string(&cache_path)
+			.ok()
+			.and_then(|json| serde_json::from_str(&json).ok())
+			.unwrap_or_default();
+		Self { cache_path, cache: Mutex::new(cache), fetch }
+	}
+
+	/// Vendor for `mac`, from the cache or the online API. Failures degrade to `None`.
+	pub async fn lookup(&self, mac: &str) -> Option<String> {
+		let prefix = mac.to_lowercase().get(0..8)?.to_string();
+		if let Some(vendor) = self.cache.lock().unwrap().get(&prefix) {
+			return Some(vendor.clone());
 		}
-		None
+
+		let vendor = tokio::time::timeout(ONLINE_LOOKUP_TIMEOUT, (self.fetch)(prefix.clone()))
+			.await
+			.ok()
+			.flatten()?;
+
+		let mut cache = self.cache.lock().unwrap();
+		cache.insert(prefix, vendor.clone());
+		let written = self
+			.cache_path
+			.parent()
+			.map_or(Ok(()), std::fs::create_dir_all)
+			.and_then(|_| serde_json::to_string(&*cache).map_err(std::io::Error::other))
+			.and_then(|json| std::fs::write(&self.cache_path, json));
+		if let Err(e) = written {
+			warn!("Failed to persist OUI cache to {}: {}", self.cache_path.display(), e);
+		}
+		Some(vendor)
+	}
+}
+
+fn macvendors_fetcher() -> VendorFetch {
+	let client = reqwest::Client::builder()
+		.timeout(ONLINE_LOOKUP_TIMEOUT)
+		.build()
+		.unwrap_or_default();
+	Arc::new(move |prefix: String| -> VendorFuture {
+		let client = client.clone();
+		Box::pin(async move {
+			let response = client.get(format!("{}/{}", MACVENDORS_URL, prefix)).send().await.ok()?;
+			if !response.status().is_success() {
+				return None;
+			}
+			let vendor = response.text().await.ok()?.trim().to_string();
+			(!vendor.is_empty()).then_some(vendor)
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	fn mock_fetcher(calls: Arc<AtomicUsize>, response: Option<&'static str>) -> VendorFetch {
+		Arc::new(move |_prefix: String| -> VendorFuture {
+			calls.fetch_add(1, Ordering::SeqCst);
+			Box::pin(async move { response.map(str::to_string) })
+		})
+	}
+
+	#[test]
+	fn test_lookup_stats() {
+		let csv = "registry,assignment,organizationName,organizationAddress\n\
+			MA-L,00-00-00,Xerox Corporation,Webster NY US\n";
+		let db = OuiDb::from_csv(csv);
+
+		db.lookup("00:00:00:12:34:56");
+		db.lookup("00:00:00:AB:CD:EF");
+		db.lookup("DE:AD:BE:EF:00:01");
+		db.lookup("de:ad:be:00:00:02");
+		db.lookup("aa:bb:cc:00:00:03");
+
+		let stats = db.lookup_stats();
+		assert_eq!((stats.lookups, stats.hits, stats.misses), (5, 2, 3));
+		assert_eq!(stats.missed_prefixes, BTreeSet::from(["aa:bb:cc".to_string(), "de:ad:be".to_string()]));
+	}
+
+	#[test]
+	fn test_cache_serves_repeated_prefixes() {
+		let csv = "registry,assignment,organizationName,organizationAddress\n\
+			MA-L,00-00-00,Xerox Corporation,Webster NY US\n";
+		let cache = OuiCache::new(Arc::new(OuiDb::from_csv(csv)));
+		let shared = cache.clone();
+
+		assert_eq!(cache.lookup("00:00:00:12:34:56").as_deref(), Some("Xerox Corporation"));
+		assert_eq!(shared.lookup("00:00:00:AB:CD:EF").as_deref(), Some("Xerox Corporation"));
+		assert_eq!(cache.lookup("de:ad:be:ef:00:01"), None);
+		assert_eq!(shared.lookup("DE:AD:BE:00:00:02"), None);
+
+		assert_eq!(cache.stats(), OuiCacheStats { hits: 2, misses: 2 });
+		// Only the first device of each prefix reached the database
+		assert_eq!(cache.db().lookup_stats().lookups, 2);
+	}
+
+	#[test]
+	fn test_malformed_csv_is_detected() {
+		let csv = "registry,assignment,organizationName,organizationAddress\n\
+			MA-L,00-00-00,Xerox Corporation,Webster NY US\n\
+			this is not a csv line\n\
+			MA-L;00-00-01;Semicolon Separated;Nowhere\n\
+			MA-L,,Missing Assignment,Nowhere\n";
+		let db = OuiDb::from_csv(csv);
+		assert_eq!(
+			db.summary(),
+			&OuiParseSummary { lines_processed: 4, entries_loaded: 1, lines_skipped: 3 }
+		);
+		assert!(db.summary().looks_malformed());
+		assert_eq!(db.lookup("00:00:00:12:34:56"), Some("Xerox Corporation"));
+
+		// The bundled database passes the sanity check
+		let embedded = OuiDb::new_embedded();
+		assert_eq!(embedded.summary().lines_skipped, 0);
+		assert!(embedded.len() > 0);
+		assert!(!embedded.summary().looks_malformed());
+	}
+
+	#[tokio::test]
+	async fn test_online_lookup_caches_results() {
+		let dir = std::env::temp_dir().join(format!("cutecatnet-oui-{}", std::process::id()));
+		let cache_path = dir.join(OUI_CACHE_FILE);
+		let calls = Arc::new(AtomicUsize::new(0));
+
+		let lookup = OnlineOuiLookup::with_fetcher(cache_path.clone(), mock_fetcher(Arc::clone(&calls), Some("Acme Devices")));
+		assert_eq!(lookup.lookup("AA:BB:CC:11:22:33").await.as_deref(), Some("Acme Devices"));
+		// Same prefix, different device: served from the cache
+		assert_eq!(lookup.lookup("aa:bb:cc:44:55:66").await.as_deref(), Some("Acme Devices"));
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+		// The cache survives a restart, and failures degrade silently
+		let failing = OnlineOuiLookup::with_fetcher(cache_path, mock_fetcher(Arc::clone(&calls), None));
+		assert_eq!(failing.lookup("aa:bb:cc:77:88:99").await.as_deref(), Some("Acme Devices"));
+		assert_eq!(failing.lookup("de:ad:be:ef:00:01").await, None);
+		assert_eq!(calls.load(Ordering::SeqCst), 2);
+		let _ = std::fs::remove_dir_all(&dir);
 	}
 }
@@ -0,0 +1,153 @@
+// src-tauri/src/owd.rs
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+use crate::stresser::{ProbeFn, ProbeFuture, ProbeReply, StressError};
+
+/// Marks a payload as carrying our timestamps, so the reflector leaves other traffic alone.
+const TIMESTAMP_MAGIC: [u8; 4] = *b"CCTS";
+/// Magic, send time, reflector receive time.
+pub const TIMESTAMP_PAYLOAD_LEN: usize = 4 + 8 + 8;
+const REFLECTOR_REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+/// Largest datagram the reflector will echo; anything bigger is dropped, not truncated.
+const MAX_REFLECTED_DATAGRAM: usize = 65_507;
+
+/// Timestamps read back from a reflected payload, in nanoseconds since the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PayloadTimestamps {
+	pub sent_ns: u64,
+	/// When the reflector received the packet; `None` if it came back unstamped, as from a
+	/// plain echo responder
+	pub reflected_ns: Option<u64>,
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+	time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_nanos() as u64)
+}
+
+/// Writes the send time into the front of `payload`, clearing the reflector's slot. False
+/// when the payload is too short to carry it.
+pub fn stamp_send(payload: &mut [u8], sent_at: SystemTime) -> bool {
+	if payload.len() < TIMESTAMP_PAYLOAD_LEN {
+		return false;
+	}
+	payload[0..4].copy_from_slice(&TIMESTAMP_MAGIC);
+	payload[4..12].copy_from_slice(&unix_nanos(sent_at).to_be_bytes());
+	payload[12..20].fill(0);
+	true
+}
+
+/// The reflector's half: records when a timestamped payload arrived. Payloads without our
+/// marker are left untouched.
+pub fn stamp_reflect(payload: &mut [u8], received_at: SystemTime) -> bool {
+	if payload.len() < TIMESTAMP_PAYLOAD_LEN || payload[0..4] != TIMESTAMP_MAGIC {
+		return false;
+	}
+	payload[12..20].copy_from_slice(&unix_nanos(received_at).max(1).to_be_bytes());
+	true
+}
+
+pub fn read_timestamps(payload: &[u8]) -> Option<PayloadTimestamps> {
+	if payload.len() < TIMESTAMP_PAYLOAD_LEN || payload[0..4] != TIMESTAMP_MAGIC {
+		return None;
+	}
+	let word = |range: std::ops::Range<usize>| u64::from_be_bytes(payload[range].try_into().expect("eight bytes"));
+	let reflected_ns = word(12..20);
+	Some(PayloadTimestamps { sent_ns: word(4..12), reflected_ns: (reflected_ns != 0).then_some(reflected_ns) })
+}
+
+/// Forward (outbound) delay in ms: the reflector's receive time less our send time. Only as
+/// good as the two clocks' agreement, so both ends should be NTP or PTP synced;
+/// `clock_offset_ms` (positive when the reflector's clock is ahead) corrects a known skew.
+/// `None` when the payload wasn't stamped by a reflector or skew makes the result negative.
+pub fn one_way_delay_ms(timestamps: &PayloadTimestamps, clock_offset_ms: f64) -> Option<f64> {
+	let reflected_ns = timestamps.reflected_ns?;
+	let delay_ms = (reflected_ns as f64 - timestamps.sent_ns as f64) / 1_000_000.0 - clock_offset_ms;
+	(delay_ms >= 0.0).then_some(delay_ms)
+}
+
+/// Echoes every datagram on `socket` back to its sender, stamping the receive time into
+/// timestamped payloads. Run on the far endpoint so a test can measure one-way delay to it.
+/// Replies are never larger than the request, so the reflector can't amplify traffic.
+pub async fn run_reflector(socket: UdpSocket) -> std::io::Result<()> {
+	let mut buffer = vec![0u8; MAX_REFLECTED_DATAGRAM];
+	loop {
+		let (len, from) = socket.recv_from(&mut buffer).await?;
+		stamp_reflect(&mut buffer[..len], SystemTime::now());
+		socket.send_to(&buffer[..len], from).await?;
+	}
+}
+
+/// Probe for tests with a one-way delay reflector on the target: each call sends one UDP
+/// datagram of `payload_size` bytes (at least `TIMESTAMP_PAYLOAD_LEN`) to `port` and
+/// resolves with the round trip and, when the reflector stamped it, the one-way delay.
+pub fn reflector_probe(port: u16, payload_size: u32) -> ProbeFn {
+	let payload_size = (payload_size as usize).max(TIMESTAMP_PAYLOAD_LEN);
+	Arc::new(move |target: Ipv4Addr| -> ProbeFuture {
+		Box::pin(async move {
+			let network_error = |e: std::io::Error| StressError::NetworkError(e.to_string());
+			let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await.map_err(network_error)?;
+			let target = SocketAddr::from((target, port));
+
+			let mut payload = vec![0u8; payload_size];
+			stamp_send(&mut payload, SystemTime::now());
+			let start = Instant::now();
+			socket.send_to(&payload, target).await.map_err(network_error)?;
+
+			let mut reply = vec![0u8; payload_size];
+			loop {
+				let (len, from) = tokio::time::timeout(REFLECTOR_REPLY_TIMEOUT, socket.recv_from(&mut reply))
+					.await
+					.map_err(|_| StressError::NetworkError("Reflector reply timeout".to_string()))?
+					.map_err(network_error)?;
+				let timestamps = read_timestamps(&reply[..len]);
+				if from != target || timestamps.is_none_or(|timestamps| payload[4..12] != timestamps.sent_ns.to_be_bytes()) {
+					continue;
+				}
+				let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+				let one_way_delay_ms = timestamps.and_then(|timestamps| one_way_delay_ms(&timestamps, 0.0));
+				return Ok(ProbeReply { one_way_delay_ms, ..ProbeReply::from(latency_ms) });
+			}
+		})
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_delay_from_stamped_payload() {
+		let sent = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+		let mut payload = vec![0u8; 56];
+		assert!(stamp_send(&mut payload, sent));
+		assert_eq!(read_timestamps(&payload), Some(PayloadTimestamps { sent_ns: unix_nanos(sent), reflected_ns: None }));
+		assert_eq!(read_timestamps(&payload).and_then(|t| one_way_delay_ms(&t, 0.0)), None);
+
+		assert!(stamp_reflect(&mut payload, sent + Duration::from_micros(2_500)));
+		let timestamps = read_timestamps(&payload).unwrap();
+		assert_eq!(one_way_delay_ms(&timestamps, 0.0), Some(2.5));
+		assert_eq!(one_way_delay_ms(&timestamps, 1.0), Some(1.5));
+		// A reflector clock running far ahead can't produce a negative delay
+		assert_eq!(one_way_delay_ms(&timestamps, 5.0), None);
+
+		// Too short, or someone else's traffic
+		assert!(!stamp_send(&mut [0u8; 8], sent));
+		assert!(!stamp_reflect(&mut [0u8; 56], sent));
+	}
+
+	#[tokio::test]
+	async fn test_timestamped_payload_round_trips_through_reflector() {
+		let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+		let port = socket.local_addr().unwrap().port();
+		let reflector = tokio::spawn(run_reflector(socket));
+
+		let reply = reflector_probe(port, 0)(Ipv4Addr::LOCALHOST).await.unwrap();
+		let one_way = reply.one_way_delay_ms.unwrap();
+		assert!(one_way >= 0.0 && one_way <= reply.latency_ms, "{} of {}", one_way, reply.latency_ms);
+		reflector.abort();
+	}
+}
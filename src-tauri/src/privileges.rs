@@ -0,0 +1,67 @@
+// src-tauri/src/privileges.rs
+
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::transport::{transport_channel, TransportChannelType, TransportProtocol};
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RawSocketCapability {
+	pub available: bool,
+	/// How to grant the missing privileges on this platform, set when unavailable
+	pub guidance: Option<String>,
+}
+
+/// Probes whether this process may open a raw socket, which the scanner, SYN and ICMP
+/// probes all need.
+pub fn check_raw_socket_capability() -> RawSocketCapability {
+	let protocol = TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Icmp));
+	match transport_channel(64, protocol) {
+		Ok(_) => RawSocketCapability { available: true, guidance: None },
+		Err(e) => RawSocketCapability {
+			available: false,
+			guidance: Some(format!("Raw sockets unavailable ({}). {}", e, platform_guidance())),
+		},
+	}
+}
+
+/// `Err` with the guidance message when raw sockets can't be opened.
+pub fn require_raw_sockets() -> Result<(), String> {
+	let capability = check_raw_socket_capability();
+	match capability.guidance {
+		Some(guidance) if !capability.available => Err(guidance),
+		_ => Ok(()),
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn platform_guidance() -> &'static str {
+	"Run with sudo, or grant the binary the capability once: sudo setcap cap_net_raw,cap_net_admin=eip <path-to-cutecatnet>"
+}
+
+#[cfg(target_os = "windows")]
+fn platform_guidance() -> &'static str {
+	"Run CuteCatNet as Administrator and make sure Npcap is installed."
+}
+
+#[cfg(target_os = "macos")]
+fn platform_guidance() -> &'static str {
+	"Run with sudo; signed builds also need the network client/server entitlements to open raw sockets and BPF devices."
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn platform_guidance() -> &'static str {
+	"Run with root/administrator privileges."
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_capability_is_definite() {
+		// Depends on how the tests are run; either way the answer must be consistent
+		let capability = check_raw_socket_capability();
+		assert_eq!(capability.available, capability.guidance.is_none());
+		assert_eq!(require_raw_sockets().is_ok(), capability.available);
+	}
+}
@@ -0,0 +1,64 @@
+// src-tauri/src/prometheus.rs
+
+use std::fmt::Write;
+
+use crate::stresser::TestMetrics;
+
+/// `metrics` in the Prometheus text exposition format, every sample labelled with `test_id`,
+/// for scraping the tool or pushing to a Pushgateway.
+pub fn format_metrics(test_id: &str, metrics: &TestMetrics) -> String {
+	let label = format!("{{test_id=\"{}\"}}", escape_label(test_id));
+	let samples: [(&str, &str, &str, f64); 9] = [
+		("stress_latency_ms", "gauge", "Average round-trip latency in milliseconds", metrics.latency_ms),
+		("stress_latency_p95_ms", "gauge", "95th percentile round-trip latency in milliseconds", metrics.latency_p95_ms),
+		("stress_jitter_ms", "gauge", "Standard deviation of recent latencies in milliseconds", metrics.jitter_ms),
+		("stress_packet_loss_ratio", "gauge", "Share of packets lost, between 0 and 1", metrics.packet_loss_percentage / 100.0),
+		("stress_throughput_mbps", "gauge", "Achieved throughput in megabits per second", metrics.throughput_mbps),
+		("stress_packets_sent_total", "counter", "Packets sent, warmup excluded", f64::from(metrics.packets_sent)),
+		("stress_packets_received_total", "counter", "Replies received, duplicates excluded", f64::from(metrics.packets_received)),
+		("stress_bytes_sent_total", "counter", "Bytes put on the wire, IP headers included", metrics.bytes_sent as f64),
+		("stress_duplicate_replies_total", "counter", "Extra replies to already answered packets", f64::from(metrics.duplicate_replies)),
+	];
+
+	let mut out = String::new();
+	for (name, kind, help, value) in samples {
+		let _ = writeln!(out, "# HELP {} {}", name, help);
+		let _ = writeln!(out, "# TYPE {} {}", name, kind);
+		let _ = writeln!(out, "{}{} {}", name, label, value);
+	}
+	out
+}
+
+/// Label values may hold anything but backslashes, quotes and newlines must be escaped.
+fn escape_label(value: &str) -> String {
+	value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_exposition_format() {
+		let metrics = TestMetrics {
+			latency_ms: 4.5,
+			packet_loss_percentage: 2.0,
+			packets_sent: 100,
+			packets_received: 98,
+			..TestMetrics::default()
+		};
+		let output = format_metrics("test_1700000000", &metrics);
+
+		assert!(output.contains("# TYPE stress_latency_ms gauge\n"));
+		assert!(output.contains("stress_latency_ms{test_id=\"test_1700000000\"} 4.5\n"));
+		assert!(output.contains("stress_packet_loss_ratio{test_id=\"test_1700000000\"} 0.02\n"));
+		assert!(output.contains("# TYPE stress_packets_sent_total counter\n"));
+		assert!(output.contains("stress_packets_sent_total{test_id=\"test_1700000000\"} 100\n"));
+	}
+
+	#[test]
+	fn test_label_is_escaped() {
+		let output = format_metrics("a\"b", &TestMetrics::default());
+		assert!(output.contains("{test_id=\"a\\\"b\"}"));
+	}
+}
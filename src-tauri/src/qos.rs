@@ -0,0 +1,102 @@
+// src-tauri/src/qos.rs
+
+use socket2::SockRef;
+use std::io;
+use std::net::UdpSocket;
+
+/// DSCP is the upper six bits of the IPv4 ToS byte.
+pub const MAX_DSCP: u8 = 0b11_1111;
+/// ECN-Capable Transport, ECT(0), in the low two bits of the ToS byte (RFC 3168)
+pub const ECN_ECT0: u8 = 0b10;
+/// Congestion Experienced, set by an AQM router instead of dropping the packet
+pub const ECN_CE: u8 = 0b11;
+const ECN_MASK: u8 = 0b11;
+
+/// ToS byte carrying `dscp` (0 when unset) and, when `ecn_capable`, the ECT(0) codepoint.
+pub fn tos_byte(dscp: Option<u8>, ecn_capable: bool) -> u32 {
+	let ecn = if ecn_capable { ECN_ECT0 } else { 0 };
+	u32::from(dscp.unwrap_or(0) << 2 | ecn)
+}
+
+/// True when a received ToS byte carries the Congestion Experienced mark.
+pub fn is_congestion_experienced(tos: u8) -> bool {
+	tos & ECN_MASK == ECN_CE
+}
+
+/// Marks everything sent on `socket` with `dscp` and, optionally, ECT(0).
+pub fn set_marking(socket: SockRef<'_>, dscp: Option<u8>, ecn_capable: bool) -> io::Result<()> {
+	socket.set_tos_v4(tos_byte(dscp, ecn_capable))
+}
+
+/// Same as `set_marking` for the raw sockets pnet opens, which only expose their descriptor.
+#[cfg(unix)]
+pub fn set_marking_raw(fd: std::os::fd::RawFd, dscp: Option<u8>, ecn_capable: bool) -> io::Result<()> {
+	// Safety: the channel owning `fd` is alive for the duration of this call
+	let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) };
+	set_marking(SockRef::from(&fd), dscp, ecn_capable)
+}
+
+#[cfg(windows)]
+pub fn set_marking_raw(socket: std::os::windows::io::RawSocket, dscp: Option<u8>, ecn_capable: bool) -> io::Result<()> {
+	// Safety: the channel owning `socket` is alive for the duration of this call
+	let socket = unsafe { std::os::windows::io::BorrowedSocket::borrow_raw(socket) };
+	set_marking(SockRef::from(&socket), dscp, ecn_capable)
+}
+
+/// Whether this host lets us set the ECN bits: some stacks (notably Windows) ignore or
+/// reject them. Checked on a scratch UDP socket by setting ECT(0) and reading it back.
+pub fn ecn_marking_supported() -> bool {
+	let Ok(socket) = UdpSocket::bind("127.0.0.1:0") else {
+		return false;
+	};
+	let socket = SockRef::from(&socket);
+	set_marking(socket, None, true).is_ok() && socket.tos_v4().is_ok_and(|tos| tos as u8 & ECN_MASK == ECN_ECT0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tos_byte() {
+		assert_eq!(tos_byte(None, false), 0);
+		// Expedited Forwarding
+		assert_eq!(tos_byte(Some(46), false), 0xb8);
+		assert_eq!(tos_byte(Some(MAX_DSCP), false), 0xfc);
+		assert_eq!(tos_byte(None, true), 0x02);
+		assert_eq!(tos_byte(Some(46), true), 0xba);
+
+		assert!(is_congestion_experienced(0xbb));
+		assert!(!is_congestion_experienced(0xba));
+		assert!(!is_congestion_experienced(0x00));
+	}
+
+	#[test]
+	fn test_socket_option_is_set() {
+		let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+		set_marking(SockRef::from(&socket), Some(46), false).unwrap();
+		assert_eq!(SockRef::from(&socket).tos_v4().unwrap(), 0xb8);
+	}
+
+	#[test]
+	fn test_ecn_codepoint_is_set() {
+		if !ecn_marking_supported() {
+			return;
+		}
+		let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+		set_marking(SockRef::from(&socket), Some(10), true).unwrap();
+		let tos = SockRef::from(&socket).tos_v4().unwrap() as u8;
+		assert_eq!(tos & ECN_MASK, ECN_ECT0);
+		assert_eq!(tos >> 2, 10);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn test_raw_descriptor_option_is_set() {
+		use std::os::fd::AsRawFd;
+
+		let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+		set_marking_raw(socket.as_raw_fd(), Some(10), false).unwrap();
+		assert_eq!(SockRef::from(&socket).tos_v4().unwrap(), tos_byte(Some(10), false));
+	}
+}
@@ -0,0 +1,115 @@
+// src-tauri/src/quality.rs
+
+use serde::{Deserialize, Serialize};
+
+use crate::stresser::TestMetrics;
+
+/// One-glance verdict on a test's latency, jitter and loss, for users to whom the raw
+/// numbers mean little.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityGrade {
+	Excellent,
+	Good,
+	Fair,
+	Poor,
+}
+
+/// Upper bounds (inclusive) of each grade for one metric. Anything above `fair` is Poor.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GradeCutoffs {
+	pub excellent: f64,
+	pub good: f64,
+	pub fair: f64,
+}
+
+impl GradeCutoffs {
+	pub fn grade(&self, value: f64) -> QualityGrade {
+		if value <= self.excellent {
+			QualityGrade::Excellent
+		} else if value <= self.good {
+			QualityGrade::Good
+		} else if value <= self.fair {
+			QualityGrade::Fair
+		} else {
+			QualityGrade::Poor
+		}
+	}
+}
+
+/// Grade cutoffs per metric. The defaults match what a wired or decent Wi-Fi home network
+/// delivers to a device on the same LAN.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QualityThresholds {
+	pub latency_ms: GradeCutoffs,
+	pub jitter_ms: GradeCutoffs,
+	pub packet_loss_percentage: GradeCutoffs,
+}
+
+impl Default for QualityThresholds {
+	fn default() -> Self {
+		Self {
+			latency_ms: GradeCutoffs { excellent: 10.0, good: 30.0, fair: 100.0 },
+			jitter_ms: GradeCutoffs { excellent: 2.0, good: 10.0, fair: 30.0 },
+			packet_loss_percentage: GradeCutoffs { excellent: 0.1, good: 1.0, fair: 5.0 },
+		}
+	}
+}
+
+impl QualityThresholds {
+	/// The worst of the latency, jitter and loss grades. `None` until a packet has been sent;
+	/// with nothing received, latency and jitter mean nothing and loss alone decides.
+	pub fn grade(&self, metrics: &TestMetrics) -> Option<QualityGrade> {
+		if metrics.packets_sent == 0 {
+			return None;
+		}
+
+		let loss = self.packet_loss_percentage.grade(metrics.packet_loss_percentage);
+		if metrics.packets_received == 0 {
+			return Some(loss);
+		}
+		let latency = self.latency_ms.grade(metrics.latency_ms);
+		let jitter = self.jitter_ms.grade(metrics.jitter_ms);
+		Some(loss.max(latency).max(jitter))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn metrics(latency_ms: f64, jitter_ms: f64, packet_loss_percentage: f64) -> TestMetrics {
+		TestMetrics {
+			latency_ms,
+			jitter_ms,
+			packet_loss_percentage,
+			packets_sent: 100,
+			packets_received: (100.0 - packet_loss_percentage) as u32,
+			..TestMetrics::default()
+		}
+	}
+
+	#[test]
+	fn test_grades() {
+		let thresholds = QualityThresholds::default();
+
+		assert_eq!(thresholds.grade(&metrics(2.0, 0.5, 0.0)), Some(QualityGrade::Excellent));
+		assert_eq!(thresholds.grade(&metrics(23.0, 4.0, 0.4)), Some(QualityGrade::Good));
+		// The worst metric decides: low latency doesn't make up for jitter
+		assert_eq!(thresholds.grade(&metrics(3.0, 25.0, 0.0)), Some(QualityGrade::Fair));
+		assert_eq!(thresholds.grade(&metrics(5.0, 1.0, 12.0)), Some(QualityGrade::Poor));
+		assert_eq!(thresholds.grade(&metrics(0.0, 0.0, 100.0)), Some(QualityGrade::Poor));
+		assert_eq!(thresholds.grade(&TestMetrics::default()), None);
+	}
+
+	#[test]
+	fn test_custom_thresholds() {
+		let strict = QualityThresholds {
+			latency_ms: GradeCutoffs { excellent: 1.0, good: 2.0, fair: 5.0 },
+			..QualityThresholds::default()
+		};
+		assert_eq!(strict.grade(&metrics(4.0, 0.5, 0.0)), Some(QualityGrade::Fair));
+
+		let json = serde_json::to_string(&strict).unwrap();
+		assert_eq!(serde_json::from_str::<QualityThresholds>(&json).unwrap(), strict);
+	}
+}
@@ -0,0 +1,126 @@
+// src-tauri/src/rate_limit.rs
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Combined outbound packet ceiling when none is configured. Comfortably above a High
+/// intensity test, so the limiter only bites when a scan runs alongside one.
+pub const DEFAULT_SHARED_PACKETS_PER_SECOND: u32 = 500;
+/// Share of a second's worth of packets that may go out back to back.
+const BURST_FRACTION: f64 = 0.1;
+
+struct Bucket {
+	packets_per_second: f64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl Bucket {
+	fn capacity(&self) -> f64 {
+		(self.packets_per_second * BURST_FRACTION).max(1.0)
+	}
+
+	fn refill(&mut self) {
+		let now = Instant::now();
+		let earned = now.duration_since(self.last_refill).as_secs_f64() * self.packets_per_second;
+		self.tokens = (self.tokens + earned).min(self.capacity());
+		self.last_refill = now;
+	}
+}
+
+/// Token bucket every traffic source draws from (the scanner's ARP sweep and the stress
+/// test loop), so running both at once stays under one combined packet rate. Clones share
+/// the same bucket.
+#[derive(Clone)]
+pub struct SharedRateLimiter {
+	bucket: Arc<Mutex<Bucket>>,
+}
+
+impl SharedRateLimiter {
+	pub fn new(packets_per_second: u32) -> Self {
+		let packets_per_second = f64::from(packets_per_second.max(1));
+		let bucket = Bucket { packets_per_second, tokens: 0.0, last_refill: Instant::now() };
+		let tokens = bucket.capacity();
+		Self { bucket: Arc::new(Mutex::new(Bucket { tokens, ..bucket })) }
+	}
+
+	pub fn packets_per_second(&self) -> u32 {
+		self.bucket.lock().unwrap().packets_per_second as u32
+	}
+
+	/// Changes the ceiling for every holder of this limiter.
+	pub fn set_packets_per_second(&self, packets_per_second: u32) {
+		let mut bucket = self.bucket.lock().unwrap();
+		bucket.refill();
+		bucket.packets_per_second = f64::from(packets_per_second.max(1));
+		bucket.tokens = bucket.tokens.min(bucket.capacity());
+	}
+
+	/// Waits until one packet may be sent.
+	pub async fn acquire(&self) {
+		loop {
+			let wait = {
+				let mut bucket = self.bucket.lock().unwrap();
+				bucket.refill();
+				if bucket.tokens >= 1.0 {
+					bucket.tokens -= 1.0;
+					return;
+				}
+				Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.packets_per_second)
+			};
+			tokio::time::sleep(wait).await;
+		}
+	}
+}
+
+impl Default for SharedRateLimiter {
+	fn default() -> Self {
+		Self::new(DEFAULT_SHARED_PACKETS_PER_SECOND)
+	}
+}
+
+impl fmt::Debug for SharedRateLimiter {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("SharedRateLimiter").field("packets_per_second", &self.packets_per_second()).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	#[tokio::test]
+	async fn test_sources_share_one_ceiling() {
+		let limiter = SharedRateLimiter::new(50);
+		let sent = Arc::new(AtomicU32::new(0));
+		let start = Instant::now();
+
+		let mut tasks = Vec::new();
+		for _ in 0..2 {
+			let (limiter, sent) = (limiter.clone(), Arc::clone(&sent));
+			tasks.push(tokio::spawn(async move {
+				while start.elapsed() < Duration::from_millis(500) {
+					limiter.acquire().await;
+					sent.fetch_add(1, Ordering::SeqCst);
+				}
+			}));
+		}
+		for task in tasks {
+			task.await.unwrap();
+		}
+
+		// 25 packets in half a second plus the initial burst of 5, whatever the split
+		let sent = sent.load(Ordering::SeqCst);
+		assert!((20..=32).contains(&sent), "sent {}", sent);
+	}
+
+	#[test]
+	fn test_ceiling_is_adjustable() {
+		let limiter = SharedRateLimiter::new(500);
+		let shared = limiter.clone();
+		shared.set_packets_per_second(0);
+		assert_eq!(limiter.packets_per_second(), 1);
+	}
+}
@@ -0,0 +1,110 @@
+// src-tauri/src/scan_cache.rs
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::scanner::{ScanError, ScanSummary};
+
+pub const DEFAULT_SCAN_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Recent scan results per interface/subnet, so repeated `scan_network` calls from UI
+/// navigation don't each cost a full sweep.
+pub struct ScanCache {
+	ttl: Duration,
+	entries: Mutex<HashMap<String, (Instant, ScanSummary)>>,
+}
+
+impl Default for ScanCache {
+	fn default() -> Self {
+		Self::new(DEFAULT_SCAN_CACHE_TTL)
+	}
+}
+
+impl ScanCache {
+	pub fn new(ttl: Duration) -> Self {
+		Self { ttl, entries: Mutex::new(HashMap::new()) }
+	}
+
+	/// Cached scan for `key` if younger than the TTL, otherwise runs `scan` and caches its
+	/// result. `force` always rescans. Failed scans are not cached.
+	pub async fn get_or_scan<S, Fut>(&self, key: &str, force: bool, scan: S) -> Result<ScanSummary, ScanError>
+	where
+		S: FnOnce() -> Fut,
+		Fut: Future<Output = Result<ScanSummary, ScanError>>,
+	{
+		if !force {
+			if let Some(summary) = self.fresh(key) {
+				return Ok(summary);
+			}
+		}
+
+		let summary = scan().await?;
+		self.entries.lock().unwrap().insert(key.to_string(), (Instant::now(), summary.clone()));
+		Ok(summary)
+	}
+
+	fn fresh(&self, key: &str) -> Option<ScanSummary> {
+		let entries = self.entries.lock().unwrap();
+		let (scanned_at, summary) = entries.get(key)?;
+		(scanned_at.elapsed() < self.ttl).then(|| summary.clone())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scanner::Device;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	async fn scan(cache: &ScanCache, key: &str, force: bool, scans: &AtomicUsize) -> ScanSummary {
+		cache
+			.get_or_scan(key, force, || async {
+				scans.fetch_add(1, Ordering::SeqCst);
+				Ok(ScanSummary {
+					devices: vec![Device::sample("192.168.1.20", "aa:aa:aa:aa:aa:01", "eth0")],
+					..ScanSummary::default()
+				})
+			})
+			.await
+			.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_second_call_within_ttl_is_cached() {
+		let cache = ScanCache::default();
+		let scans = AtomicUsize::new(0);
+
+		let first = scan(&cache, "eth0/192.168.1.0/24", false, &scans).await;
+		let second = scan(&cache, "eth0/192.168.1.0/24", false, &scans).await;
+		assert_eq!(scans.load(Ordering::SeqCst), 1);
+		assert_eq!(first.devices.len(), second.devices.len());
+
+		// Other subnets and forced scans go to the network
+		scan(&cache, "wlan0/10.0.0.0/24", false, &scans).await;
+		scan(&cache, "eth0/192.168.1.0/24", true, &scans).await;
+		assert_eq!(scans.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn test_expired_entry_rescans() {
+		let cache = ScanCache::new(Duration::ZERO);
+		let scans = AtomicUsize::new(0);
+
+		scan(&cache, "eth0/192.168.1.0/24", false, &scans).await;
+		scan(&cache, "eth0/192.168.1.0/24", false, &scans).await;
+		assert_eq!(scans.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test]
+	async fn test_failed_scan_is_not_cached() {
+		let cache = ScanCache::default();
+		let failed = cache.get_or_scan("eth0/192.168.1.0/24", false, || async { Err(ScanError::ChannelCreationFailure) }).await;
+		assert!(failed.is_err());
+
+		let scans = AtomicUsize::new(0);
+		scan(&cache, "eth0/192.168.1.0/24", false, &scans).await;
+		assert_eq!(scans.load(Ordering::SeqCst), 1);
+	}
+}
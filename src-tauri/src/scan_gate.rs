@@ -0,0 +1,179 @@
+// src-tauri/src/scan_gate.rs
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::scanner::ScanError;
+
+/// Scans allowed to sweep at once. One keeps manual, monitor and batch scans from opening
+/// competing datalink channels and flooding the same ARP requests.
+pub const DEFAULT_MAX_CONCURRENT_SCANS: usize = 1;
+
+struct GateConfig {
+	max_concurrent: usize,
+	reject_when_busy: bool,
+	/// Slots to retire as running scans finish, after the limit was lowered below the number
+	/// of scans holding one
+	owed: usize,
+}
+
+/// Limits how many scans run at the same time. Scans over the limit wait their turn, or
+/// with `reject_when_busy` fail straight away with `ScanError::ScanInProgress`.
+pub struct ScanGate {
+	/// One slot per scan allowed to sweep; resized in place so running scans stay counted
+	permits: Semaphore,
+	config: Mutex<GateConfig>,
+	/// Scans past the gate and sweeping right now
+	running: AtomicUsize,
+	/// Scans that have got past the gate since it was created
+	started: AtomicU64,
+}
+
+/// A scan's slot, counted as running for as long as it's held, even if the scan is dropped
+/// midway. Released, it goes back to the gate unless a lowered limit still owes one.
+struct Running<'a> {
+	gate: &'a ScanGate,
+	permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for Running<'_> {
+	fn drop(&mut self) {
+		self.gate.running.fetch_sub(1, Ordering::SeqCst);
+		let mut config = self.gate.config.lock().unwrap();
+		if let Some(permit) = self.permit.take().filter(|_| config.owed > 0) {
+			config.owed -= 1;
+			permit.forget();
+		}
+	}
+}
+
+impl Default for ScanGate {
+	fn default() -> Self {
+		Self::new(DEFAULT_MAX_CONCURRENT_SCANS, false)
+	}
+}
+
+impl ScanGate {
+	pub fn new(max_concurrent: usize, reject_when_busy: bool) -> Self {
+		let max_concurrent = max_concurrent.max(1);
+		Self {
+			permits: Semaphore::new(max_concurrent),
+			config: Mutex::new(GateConfig { max_concurrent, reject_when_busy, owed: 0 }),
+			running: AtomicUsize::new(0),
+			started: AtomicU64::new(0),
+		}
+	}
+
+	pub fn scans_running(&self) -> usize {
+		self.running.load(Ordering::SeqCst)
+	}
+
+	/// Total scans started so far; a change between two reads means a scan ran in between.
+	pub fn scans_started(&self) -> u64 {
+		self.started.load(Ordering::SeqCst)
+	}
+
+	pub fn max_concurrent(&self) -> usize {
+		self.config.lock().unwrap().max_concurrent
+	}
+
+	/// Changes the limit and busy behaviour. Scans already running keep their slot and still
+	/// count against the new limit; lowering it below them retires slots as they finish.
+	pub fn configure(&self, max_concurrent: usize, reject_when_busy: bool) {
+		let max_concurrent = max_concurrent.max(1);
+		let mut config = self.config.lock().unwrap();
+		if max_concurrent > config.max_concurrent {
+			let added = max_concurrent - config.max_concurrent;
+			let repaid = added.min(config.owed);
+			config.owed -= repaid;
+			self.permits.add_permits(added - repaid);
+		} else {
+			let removed = config.max_concurrent - max_concurrent;
+			config.owed += removed - self.permits.forget_permits(removed);
+		}
+		config.max_concurrent = max_concurrent;
+		config.reject_when_busy = reject_when_busy;
+	}
+
+	/// Runs `scan` once a slot is free.
+	pub async fn run<S, Fut, T>(&self, scan: S) -> Result<T, ScanError>
+	where
+		S: FnOnce() -> Fut,
+		Fut: Future<Output = Result<T, ScanError>>,
+	{
+		let reject_when_busy = self.config.lock().unwrap().reject_when_busy;
+		let permit = if reject_when_busy {
+			self.permits.try_acquire().map_err(|_| ScanError::ScanInProgress)?
+		} else {
+			self.permits.acquire().await.map_err(|_| ScanError::ScanInProgress)?
+		};
+		self.started.fetch_add(1, Ordering::SeqCst);
+		self.running.fetch_add(1, Ordering::SeqCst);
+		let _running = Running { gate: self, permit: Some(permit) };
+		scan().await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use std::time::Duration;
+	use tokio::sync::oneshot;
+
+	#[tokio::test]
+	async fn test_second_scan_waits_or_is_rejected() {
+		let gate = Arc::new(ScanGate::default());
+		let (release_tx, release_rx) = oneshot::channel::<()>();
+		let first = tokio::spawn({
+			let gate = Arc::clone(&gate);
+			async move { gate.run(|| async { release_rx.await.map_err(|_| ScanError::ScanInProgress) }).await }
+		});
+		tokio::time::sleep(Duration::from_millis(20)).await;
+
+		// Queued: the second scan doesn't start until the first has finished
+		let second = tokio::spawn({
+			let gate = Arc::clone(&gate);
+			async move { gate.run(|| async { Ok(()) }).await }
+		});
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		assert!(!second.is_finished());
+
+		// Rejecting: reconfiguring doesn't free a slot, so a scan started while the first still
+		// runs fails straight away
+		gate.configure(1, true);
+		assert!(matches!(gate.run(|| async { Ok(()) }).await, Err(ScanError::ScanInProgress)));
+		assert_eq!(gate.scans_running(), 1);
+		assert!(!second.is_finished());
+
+		release_tx.send(()).unwrap();
+		assert!(first.await.unwrap().is_ok());
+		assert!(second.await.unwrap().is_ok());
+		assert!(gate.run(|| async { Ok(()) }).await.is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_lowered_limit_counts_running_scans() {
+		let gate = Arc::new(ScanGate::new(2, true));
+		let (release_tx, release_rx) = oneshot::channel::<()>();
+		let first = tokio::spawn({
+			let gate = Arc::clone(&gate);
+			async move { gate.run(|| async { release_rx.await.map_err(|_| ScanError::ScanInProgress) }).await }
+		});
+		tokio::time::sleep(Duration::from_millis(20)).await;
+
+		// Down to one while one runs: no room for another, and the slot isn't lost afterwards
+		gate.configure(1, true);
+		assert!(matches!(gate.run(|| async { Ok(()) }).await, Err(ScanError::ScanInProgress)));
+		release_tx.send(()).unwrap();
+		assert!(first.await.unwrap().is_ok());
+		assert!(gate.run(|| async { Ok(()) }).await.is_ok());
+
+		// Raised again, two sweep side by side
+		gate.configure(2, true);
+		let both = gate.run(|| async { gate.run(|| async { Ok(()) }).await });
+		assert!(both.await.is_ok());
+	}
+}
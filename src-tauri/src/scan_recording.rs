@@ -0,0 +1,84 @@
+// src-tauri/src/scan_recording.rs
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// The ARP replies one scan received, kept as raw Ethernet frames along with what's needed
+/// to push them through the scan pipeline again: the interface, the subnet and the address
+/// the scan sent from. Lets demos and CI replay a scan with no network at all.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScanRecording {
+	pub interface_name: String,
+	/// The swept subnet, e.g. "192.168.1.0/24"
+	pub network: String,
+	pub source_ip: String,
+	pub source_mac: String,
+	pub promiscuous: bool,
+	pub hosts_probed: usize,
+	/// Each reply frame as it came off the wire, hex encoded, in arrival order
+	pub frames: Vec<String>,
+}
+
+impl ScanRecording {
+	pub fn push_frame(&mut self, frame: &[u8]) {
+		self.frames.push(frame.iter().map(|byte| format!("{:02x}", byte)).collect());
+	}
+
+	/// The recorded frames as bytes.
+	pub fn frame_bytes(&self) -> io::Result<Vec<Vec<u8>>> {
+		self.frames.iter().map(|frame| decode_hex(frame)).collect()
+	}
+
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(path, serde_json::to_string_pretty(self).map_err(io::Error::other)?)
+	}
+
+	pub fn load(path: &Path) -> io::Result<Self> {
+		serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+}
+
+fn decode_hex(hex: &str) -> io::Result<Vec<u8>> {
+	let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid frame in recording: {}", hex));
+	if hex.len() % 2 != 0 || !hex.is_ascii() {
+		return Err(invalid());
+	}
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid()))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_frames_survive_a_save_and_load() {
+		let mut recording = ScanRecording {
+			interface_name: "eth0".to_string(),
+			network: "192.168.1.0/24".to_string(),
+			source_ip: "192.168.1.10".to_string(),
+			source_mac: "02:00:00:00:00:01".to_string(),
+			promiscuous: false,
+			hosts_probed: 253,
+			frames: Vec::new(),
+		};
+		recording.push_frame(&[0x00, 0x0f, 0xa0, 0xff]);
+		assert_eq!(recording.frames, vec!["000fa0ff"]);
+
+		let path = std::env::temp_dir().join(format!("cutecatnet-recording-{}.json", std::process::id()));
+		recording.save(&path).unwrap();
+		let loaded = ScanRecording::load(&path).unwrap();
+		assert_eq!(loaded, recording);
+		assert_eq!(loaded.frame_bytes().unwrap(), vec![vec![0x00, 0x0f, 0xa0, 0xff]]);
+
+		recording.frames.push("abc".to_string());
+		assert!(recording.frame_bytes().is_err());
+		let _ = std::fs::remove_file(&path);
+	}
+}
@@ -1,16 +1,68 @@
 // src-tauri/src/scanner.rs
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use pnet::datalink::MacAddr;
 use std::net::{IpAddr, Ipv4Addr};
 use thiserror::Error;
 
-#[derive(Serialize, Clone, Debug)]
+/// How a device was discovered.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DeviceSource {
+	/// Answered our ARP request during the scan
+	#[default]
+	LiveReply,
+	/// Didn't answer, but the OS ARP table knows it from earlier traffic
+	ArpCache,
+	/// Overheard answering another host's ARP request (promiscuous scans only)
+	PassiveObservation,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Device {
 	ip_address: String,
 	mac_address: String,
 	manufacturer: String,
 	hostname: String,
+	interface_name: String,
+	mac_conflict: bool,
+	source: DeviceSource,
+	/// Name the user gave this device, from the alias store
+	friendly_name: Option<String>,
+	/// Interfaces the device answered ARP on, filled in when several are scanned at once.
+	/// More than one means a multi-homed device or a redundant link.
+	answered_on: Vec<String>,
+	/// 802.1Q VLAN the device's reply was tagged with, seen on trunk ports
+	vlan_id: Option<u16>,
+}
+
+impl Device {
+	pub fn mac_address(&self) -> &str {
+		&self.mac_address
+	}
+
+	pub fn friendly_name(&self) -> Option<&str> {
+		self.friendly_name.as_deref()
+	}
+
+	pub fn set_friendly_name(&mut self, name: Option<String>) {
+		self.friendly_name = name;
+	}
+
+	#[cfg(test)]
+	pub(crate) fn sample(ip: &str, mac: &str, interface_name: &str) -> Self {
+		Device {
+			ip_address: ip.to_string(),
+			mac_address: mac.to_string(),
+			manufacturer: "Unknown".to_string(),
+			hostname: "Unknown".to_string(),
+			interface_name: interface_name.to_string(),
+			mac_conflict: false,
+			source: DeviceSource::LiveReply,
+			friendly_name: None,
+			answered_on: Vec::new(),
+			vlan_id: None,
+		}
+	}
 }
 
 #[derive(Error, Debug)]
@@ -23,13 +75,139 @@ pub enum ScanError {
 	IoError(#[from] std::io::Error),
 	#[error("Default network interface not found.")]
 	DefaultInterfaceNotFound,
+	#[error("Insufficient privileges: {0}")]
+	InsufficientPrivileges(String),
+	#[error("Gratuitous ARP refused: {0}")]
+	GratuitousArpRefused(String),
+	#[error("Local ARP discovery isn't meaningful on {interface} ({network}): it has no local subnet to sweep, as on a VPN or point-to-point link. Ping the hosts you expect instead.")]
+	ScanNotApplicable { interface: String, network: String },
+	#[error("Another scan is already running")]
+	ScanInProgress,
+	#[error("Invalid MAC address: {0}")]
+	InvalidMac(String),
+}
+
+use crate::oui_db::{OnlineOuiLookup, OuiCache, OuiDb};
+use crate::aliases::DeviceAliases;
+use crate::arp_cache;
+use crate::interface_policy::{self, InterfacePolicy};
+use crate::privileges;
+use crate::rate_limit::SharedRateLimiter;
+use crate::scan_recording::ScanRecording;
+
+fn get_manufacturer_with_cache(cache: &OuiCache, mac: &MacAddr) -> String {
+	cache.lookup(&mac.to_string()).unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Fills in "Unknown" manufacturers from the online fallback, leaving them as-is on failure.
+pub async fn resolve_unknown_manufacturers(devices: &mut [Device], lookup: &OnlineOuiLookup) {
+	for device in devices.iter_mut().filter(|device| device.manufacturer == "Unknown") {
+		if let Some(vendor) = lookup.lookup(&device.mac_address).await {
+			device.manufacturer = vendor;
+		}
+	}
+}
+
+/// Device record for an ARP sender, with its vendor looked up. Hostname and interface are
+/// left for the caller to fill in, keeping this free of I/O.
+pub fn build_device(ip: Ipv4Addr, mac: MacAddr, oui: &OuiCache) -> Device {
+	Device {
+		ip_address: ip.to_string(),
+		mac_address: mac.to_string(),
+		manufacturer: get_manufacturer_with_cache(oui, &mac),
+		hostname: "Unknown".to_string(),
+		interface_name: String::new(),
+		mac_conflict: false,
+		source: DeviceSource::LiveReply,
+		friendly_name: None,
+		answered_on: Vec::new(),
+		vlan_id: None,
+	}
+}
+
+/// Device record for this machine. Its manufacturer is the OS's name for the adapter when
+/// there is one, as a virtual or randomized local MAC looks up as "Unknown".
+pub fn build_own_device(ip: Ipv4Addr, mac: MacAddr, oui: &OuiCache, adapter_name: Option<String>) -> Device {
+	let device = build_device(ip, mac, oui);
+	match adapter_name.filter(|name| !name.trim().is_empty()) {
+		Some(name) => Device { manufacturer: name, ..device },
+		None => device,
+	}
 }
 
-use crate::oui_db::OuiDb;
+/// The OS's friendly name or description for `interface`, e.g. "Intel(R) Ethernet
+/// Connection I219-V".
+fn adapter_name(interface: &NetworkInterface) -> Option<String> {
+	let os_name = default_net::get_interfaces()
+		.into_iter()
+		.find(|iface| iface.index == interface.index)
+		.and_then(|iface| iface.friendly_name.or(iface.description));
+	os_name.or_else(|| Some(interface.description.clone())).filter(|name| !name.trim().is_empty())
+}
 
-fn get_manufacturer_with_db(db: &OuiDb, mac: &MacAddr) -> String {
-	let mac_string = mac.to_string().to_lowercase();
-	db.lookup(&mac_string).unwrap_or("Unknown").to_string()
+/// The ARP packet in an Ethernet frame, looking inside an 802.1Q tag, and the VLAN it was
+/// tagged with.
+fn arp_in_frame(frame: &[u8]) -> Option<(ArpPacket<'_>, Option<u16>)> {
+	let header_len = EthernetPacket::minimum_packet_size();
+	match EthernetPacket::new(frame)?.get_ethertype() {
+		EtherTypes::Arp => Some((ArpPacket::new(&frame[header_len..])?, None)),
+		EtherTypes::Vlan => {
+			let tag = VlanPacket::new(&frame[header_len..])?;
+			if tag.get_ethertype() != EtherTypes::Arp {
+				return None;
+			}
+			let arp_packet = ArpPacket::new(&frame[header_len + VlanPacket::minimum_packet_size()..])?;
+			Some((arp_packet, Some(tag.get_vlan_identifier())))
+		}
+		_ => None,
+	}
+}
+
+/// The 802.1Q VLAN an ARP frame was tagged with; `None` when untagged or not ARP.
+pub fn frame_vlan_id(frame: &[u8]) -> Option<u16> {
+	arp_in_frame(frame)?.1
+}
+
+/// Sender IP and MAC of an Ethernet frame carrying an ARP reply, `None` for anything else.
+pub fn parse_arp_reply(frame: &[u8]) -> Option<(Ipv4Addr, MacAddr)> {
+	let (arp_packet, _) = arp_in_frame(frame)?;
+	if arp_packet.get_operation() != ArpOperations::Reply {
+		return None;
+	}
+	Some((arp_packet.get_sender_proto_addr(), arp_packet.get_sender_hw_addr()))
+}
+
+/// Like `parse_arp_reply`, also telling a reply to us (`LiveReply`) from one between two
+/// other hosts that a promiscuous channel overheard (`PassiveObservation`).
+pub fn classify_arp_reply(frame: &[u8], own_mac: MacAddr) -> Option<(Ipv4Addr, MacAddr, DeviceSource)> {
+	let (ip, mac) = parse_arp_reply(frame)?;
+	let (arp_packet, _) = arp_in_frame(frame)?;
+	let source = if arp_packet.get_target_hw_addr() == own_mac {
+		DeviceSource::LiveReply
+	} else {
+		DeviceSource::PassiveObservation
+	};
+	Some((ip, mac, source))
+}
+
+/// An ARP reply read off the wire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ArpReply {
+	ip: Ipv4Addr,
+	mac: MacAddr,
+	source: DeviceSource,
+	vlan_id: Option<u16>,
+}
+
+/// The ARP reply `frame` carries, if any. On a normal channel every reply is to us; a
+/// promiscuous one also overhears replies between other hosts.
+fn read_reply(frame: &[u8], promiscuous: bool, own_mac: MacAddr) -> Option<ArpReply> {
+	let (ip, mac, source) = if promiscuous {
+		classify_arp_reply(frame, own_mac)?
+	} else {
+		parse_arp_reply(frame).map(|(ip, mac)| (ip, mac, DeviceSource::LiveReply))?
+	};
+	Some(ArpReply { ip, mac, source, vlan_id: frame_vlan_id(frame) })
 }
 
 fn resolve_hostname(ip: Ipv4Addr) -> String {
@@ -39,123 +217,1659 @@ fn resolve_hostname(ip: Ipv4Addr) -> String {
 	}
 }
 
-use pnet::datalink::{self, Channel};
+/// Reverse DNS lookups for the devices a scan finds, run on the blocking pool a bounded
+/// number at a time so neither the collector lock nor an async worker waits on DNS.
+struct HostnameLookups {
+	tasks: JoinSet<()>,
+	permits: Arc<Semaphore>,
+}
+
+impl HostnameLookups {
+	fn new() -> Self {
+		Self { tasks: JoinSet::new(), permits: Arc::new(Semaphore::new(MAX_HOSTNAME_LOOKUPS)) }
+	}
+
+	/// Resolves the device at `key` in the background, filling its hostname into `collector`
+	/// and reporting it again through `on_device` once it has one.
+	fn spawn<F>(&mut self, key: DeviceKey, collector: Arc<Mutex<ScanCollector>>, on_device: Arc<F>)
+	where
+		F: Fn(&Device) + Send + Sync + 'static,
+	{
+		let permits = Arc::clone(&self.permits);
+		self.tasks.spawn(async move {
+			let Ok(_permit) = permits.acquire_owned().await else {
+				return;
+			};
+			let Ok(hostname) = tokio::task::spawn_blocking(move || resolve_hostname(key.1)).await else {
+				return;
+			};
+			let named = collector.lock().unwrap().set_hostname(key, hostname);
+			if let Some(device) = named {
+				on_device(&device);
+			}
+		});
+	}
+
+	/// Waits up to `limit` for the outstanding lookups, then gives up on the rest.
+	async fn finish(mut self, limit: Duration) {
+		let _ = timeout(limit, async { while self.tasks.join_next().await.is_some() {} }).await;
+	}
+}
+
+use pnet::datalink::{self, Channel, NetworkInterface};
 use pnet::packet::arp::{ArpOperations, ArpPacket, MutableArpPacket};
 use pnet::packet::ethernet::{EtherTypes, EthernetPacket, MutableEthernetPacket};
+use pnet::packet::vlan::VlanPacket;
 use pnet::packet::Packet;
 use ipnetwork::Ipv4Network;
+use std::future::Future;
 use std::time::Duration;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::task::JoinSet;
 use tokio::time::timeout;
-use std::collections::HashMap;
+use log::{info, warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ScanOptions {
+	/// Probe the subnet in a shuffled order instead of .1 upwards
+	pub randomize_order: bool,
+	/// Seed for the shuffle so a randomized scan can be reproduced
+	pub seed: Option<u64>,
+	/// Ask an online OUI API about vendors the embedded database doesn't know.
+	/// Off by default: it sends MAC prefixes to a third party.
+	pub online_oui_lookup: bool,
+	/// Devices to leave out of the results and `device-found` events
+	pub exclude_ips: Vec<String>,
+	/// Full MACs or vendor prefixes (e.g. "AA-BB-CC"), matched case-insensitively
+	pub exclude_macs: Vec<String>,
+	/// Probe at most this many addresses: the first N, or a random N when randomized
+	pub max_hosts: Option<usize>,
+	/// Extra attempts at opening the interface, which can briefly be unavailable after a
+	/// network change. Defaults to 3.
+	pub channel_retries: Option<u32>,
+	/// Wait before the first retry, doubling on each one after. Defaults to 200 ms.
+	pub channel_retry_delay_ms: Option<u64>,
+	/// Size of the datalink channel's read buffer. On macOS/BSD and Windows that's the
+	/// capture buffer the kernel fills between reads, so raise it on busy networks; on Linux
+	/// it only needs to hold one frame. Defaults to 256 KiB, kept between 4 KiB and 16 MiB.
+	pub read_buffer_size: Option<usize>,
+	/// Which of the interface's IPv4 addresses to scan from, and so which subnet to sweep,
+	/// when it has several. Defaults to the first.
+	pub source_ip: Option<String>,
+	/// Open the channel in promiscuous mode and also learn devices from ARP replies meant
+	/// for other hosts. Falls back to a normal scan where the mode can't be enabled.
+	pub promiscuous: bool,
+	/// Packet budget shared with running stress tests, set by the app rather than the UI
+	#[serde(skip)]
+	pub rate_limiter: Option<SharedRateLimiter>,
+	/// Friendly names to put on the devices found, set by the app
+	#[serde(skip)]
+	pub aliases: Option<Arc<DeviceAliases>>,
+	/// Interfaces `scan_all_interfaces` sweeps at once, e.g. both links of a bond. Empty
+	/// scans every usable interface.
+	pub interfaces: Vec<String>,
+	/// Save the ARP replies the scan receives to this file, to replay later with
+	/// `perform_scan_from_recording`
+	pub record_to: Option<String>,
+	/// Look up this machine's manufacturer from its MAC like any other device's, instead of
+	/// showing the name the OS gives its network adapter
+	pub own_manufacturer_from_oui: bool,
+	/// Stop listening as soon as this MAC answers, set by `find_by_mac`
+	#[serde(skip)]
+	pub stop_at_mac: Option<MacAddr>,
+}
+
+impl ScanOptions {
+	pub fn channel_retries(&self) -> u32 {
+		self.channel_retries.unwrap_or(DEFAULT_CHANNEL_RETRIES).min(MAX_CHANNEL_RETRIES)
+	}
+
+	pub fn channel_retry_delay(&self) -> Duration {
+		Duration::from_millis(self.channel_retry_delay_ms.unwrap_or(DEFAULT_CHANNEL_RETRY_DELAY_MS)).min(MAX_CHANNEL_RETRY_DELAY)
+	}
+
+	/// Settings for the scan's datalink channel. The read timeout wakes the receive loop
+	/// regularly, so it notices when the scan has stopped listening.
+	pub fn channel_config(&self, promiscuous: bool) -> datalink::Config {
+		datalink::Config {
+			promiscuous,
+			read_timeout: Some(CHANNEL_READ_TIMEOUT),
+			read_buffer_size: self.read_buffer_size.unwrap_or(DEFAULT_READ_BUFFER_SIZE).clamp(MIN_READ_BUFFER_SIZE, MAX_READ_BUFFER_SIZE),
+			..Default::default()
+		}
+	}
+
+	/// True when the user asked to hide `device`.
+	pub fn excludes(&self, device: &Device) -> bool {
+		let mac = device.mac_address.to_lowercase();
+		self.exclude_ips.iter().any(|ip| ip.trim() == device.ip_address)
+			|| self
+				.exclude_macs
+				.iter()
+				.map(|prefix| prefix.trim().to_lowercase().replace('-', ":"))
+				.any(|prefix| !prefix.is_empty() && mac.starts_with(&prefix))
+	}
+}
+
+/// Several MACs answering ARP for the same IP: a duplicate-IP misconfiguration (or spoofing).
+/// One MAC holding several IPs is normal for routers and multi-homed hosts and isn't reported.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct IpConflict {
+	pub ip_address: String,
+	pub mac_addresses: Vec<String>,
+	/// The VLAN the conflicting replies were tagged with; `None` on the untagged network
+	pub vlan_id: Option<u16>,
+}
+
+/// Outcome of a single-interface sweep.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ScanSummary {
+	pub devices: Vec<Device>,
+	pub ip_conflicts: Vec<IpConflict>,
+	/// Addresses ARP requests were sent to, out of `subnet_hosts`
+	pub hosts_probed: usize,
+	pub subnet_hosts: usize,
+	/// The channel ran in promiscuous mode; false when it was requested but unavailable
+	pub promiscuous: bool,
+	/// A stress test was running when the scan was requested, so the scan's ARP traffic may
+	/// have skewed its measurements. Set by the app.
+	pub stress_test_running: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct InterfaceScanError {
+	interface_name: String,
+	message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MultiInterfaceScan {
+	devices: Vec<Device>,
+	ip_conflicts: Vec<IpConflict>,
+	errors: Vec<InterfaceScanError>,
+	stress_test_running: bool,
+}
+
+impl MultiInterfaceScan {
+	pub fn devices_mut(&mut self) -> &mut [Device] {
+		&mut self.devices
+	}
 
-pub async fn perform_scan() -> Result<Vec<Device>, ScanError> {
+	/// Notes that a stress test was running when the scan was requested.
+	pub fn set_stress_test_running(&mut self, running: bool) {
+		self.stress_test_running = running;
+	}
+}
+
+pub(crate) fn interface_ipv4(interface: &NetworkInterface) -> Option<Ipv4Addr> {
+	interface.ips.iter().find_map(|ip| match ip.ip() {
+		IpAddr::V4(ip) => Some(ip),
+		_ => None,
+	})
+}
+
+/// The interface to scan and test from when none is named, as picked by the current
+/// `InterfacePolicy`.
+pub(crate) fn default_interface() -> Result<NetworkInterface, ScanError> {
 	let interfaces = datalink::interfaces();
-	let default_interface = match default_net::get_default_interface() {
-		Ok(iface) => iface,
-		Err(_) => return Err(ScanError::DefaultInterfaceNotFound),
-	};
+	let system_default = default_net::get_default_interface().ok().map(|iface| iface.index);
+	let index = match interface_policy::policy() {
+		InterfacePolicy::SystemDefault => system_default,
+		policy => interface_policy::choose(&interface_policy::candidates(&interfaces), system_default, policy),
+	}
+	.ok_or(ScanError::DefaultInterfaceNotFound)?;
 
-	let interface = interfaces
+	interfaces
 		.into_iter()
-		.find(|iface| iface.index == default_interface.index)
-		.ok_or(ScanError::NoActiveInterface)?;
+		.find(|iface| iface.index == index)
+		.ok_or(ScanError::NoActiveInterface)
+}
 
-	let source_ipv4 = interface
+/// The address (and its subnet) a scan of `interface` sends from: the one matching `hint`
+/// when the interface has it, else the first IPv4 address.
+fn select_ipv4(interface: &NetworkInterface, hint: Option<Ipv4Addr>) -> Option<Ipv4Network> {
+	let addresses: Vec<Ipv4Network> = interface
 		.ips
 		.iter()
-		.find(|ip| ip.is_ipv4())
-		.map(|ip| match ip.ip() {
-			std::net::IpAddr::V4(ip) => ip,
-			_ => unreachable!(),
+		.filter_map(|ip| match ip {
+			ipnetwork::IpNetwork::V4(network) => Some(*network),
+			_ => None,
+		})
+		.collect();
+	if let Some(hint) = hint {
+		if let Some(network) = addresses.iter().find(|network| network.ip() == hint) {
+			return Some(*network);
+		}
+		warn!("{} has no address {}, scanning from its first IPv4 address instead", interface.name, hint);
+	}
+	addresses.first().copied()
+}
+
+/// A /32 address or a point-to-point link leaves no neighbours for an ARP sweep to find;
+/// scanning anyway would only ever report this host. A /31 on an Ethernet link still has
+/// its peer (RFC 3021), so it is swept.
+fn check_arp_applicable(interface: &NetworkInterface, network: Ipv4Network) -> Result<(), ScanError> {
+	if network.prefix() == 32 || is_point_to_point(interface) {
+		return Err(ScanError::ScanNotApplicable { interface: interface.name.clone(), network: network.to_string() });
+	}
+	Ok(())
+}
+
+#[cfg(unix)]
+fn is_point_to_point(interface: &NetworkInterface) -> bool {
+	interface.is_point_to_point()
+}
+
+#[cfg(not(unix))]
+fn is_point_to_point(_interface: &NetworkInterface) -> bool {
+	false
+}
+
+/// Identifies the subnet a scan of `interface` covers, e.g. "eth0/192.168.1.0/24".
+pub fn scan_key(interface: &NetworkInterface) -> Option<String> {
+	let ip = interface.ips.iter().find(|ip| ip.is_ipv4())?;
+	Some(format!("{}/{}/{}", interface.name, ip.network(), ip.prefix()))
+}
+
+/// `scan_key` of the interface `perform_scan` would sweep.
+pub fn default_scan_key() -> Result<String, ScanError> {
+	let interface = default_interface()?;
+	scan_key(&interface).ok_or(ScanError::NoActiveInterface)
+}
+
+/// Interfaces an ARP sweep makes sense on: up, not loopback, with an IPv4 address and a MAC.
+fn scannable_interfaces(interfaces: Vec<NetworkInterface>) -> Vec<NetworkInterface> {
+	interfaces
+		.into_iter()
+		.filter(|iface| iface.is_up() && !iface.is_loopback())
+		.filter(|iface| iface.mac.is_some() && iface.ips.iter().any(|ip| ip.is_ipv4()))
+		.collect()
+}
+
+/// Where a device sits: its VLAN (`None` when untagged) and IP. The same address on two
+/// VLANs is two devices.
+type DeviceKey = (Option<u16>, Ipv4Addr);
+
+/// Devices found during a single sweep, keyed by VLAN and IP.
+#[derive(Default)]
+struct ScanCollector {
+	devices: HashMap<DeviceKey, Device>,
+	/// Every MAC seen claiming an address that more than one MAC answered for
+	ip_conflicts: BTreeMap<DeviceKey, BTreeSet<String>>,
+	/// Replies discarded because their sender wasn't in the scanned subnet
+	off_subnet_replies: usize,
+}
+
+impl ScanCollector {
+	fn insert(&mut self, ip: Ipv4Addr, device: Device) {
+		self.devices.insert((None, ip), device);
+	}
+
+	/// Records an ARP reply. Returns the device the first time its IP is seen; a later reply
+	/// for the same IP from a different MAC flags the device instead, since that can mean
+	/// ARP spoofing (or a VM that migrated mid-scan).
+	fn record_reply<B>(&mut self, ip: Ipv4Addr, mac: MacAddr, build: B) -> Option<Device>
+	where
+		B: FnOnce() -> Device,
+	{
+		self.record_vlan_reply(None, ip, mac, build)
+	}
+
+	/// `record_reply` for a reply tagged with `vlan_id`, kept apart from the same IP elsewhere.
+	fn record_vlan_reply<B>(&mut self, vlan_id: Option<u16>, ip: Ipv4Addr, mac: MacAddr, build: B) -> Option<Device>
+	where
+		B: FnOnce() -> Device,
+	{
+		let key = (vlan_id, ip);
+		match self.devices.get_mut(&key) {
+			Some(existing) => {
+				if existing.mac_address != mac.to_string() {
+					if !existing.mac_conflict {
+						warn!("Conflicting ARP replies for {}: {} and {}", ip, existing.mac_address, mac);
+						existing.mac_conflict = true;
+					}
+					let claimants = self.ip_conflicts.entry(key).or_default();
+					claimants.insert(existing.mac_address.clone());
+					claimants.insert(mac.to_string());
+				}
+				None
+			}
+			None => {
+				let device = build();
+				self.devices.insert(key, device.clone());
+				Some(device)
+			}
+		}
+	}
+
+	/// `record_reply`, but only for senders inside `network`. Replies from elsewhere (a bridged
+	/// or misconfigured segment) are counted and dropped.
+	fn record_subnet_reply<B>(&mut self, network: Ipv4Network, ip: Ipv4Addr, mac: MacAddr, build: B) -> Option<Device>
+	where
+		B: FnOnce() -> Device,
+	{
+		if !network.contains(ip) {
+			self.off_subnet_replies += 1;
+			return None;
+		}
+		self.record_reply(ip, mac, build)
+	}
+
+	/// Folds a parsed reply in, building its device with `build` the first time it's seen.
+	/// A VLAN-tagged reply comes from another VLAN's subnet, so it isn't held to `network`.
+	fn record_parsed_reply<B>(&mut self, network: Ipv4Network, reply: ArpReply, build: B) -> Option<Device>
+	where
+		B: FnOnce() -> Device,
+	{
+		if reply.source == DeviceSource::LiveReply {
+			self.confirm_live(reply.vlan_id, reply.ip, reply.mac);
+		}
+		if reply.vlan_id.is_some() {
+			return self.record_vlan_reply(reply.vlan_id, reply.ip, reply.mac, build);
+		}
+		self.record_subnet_reply(network, reply.ip, reply.mac, build)
+	}
+
+	/// A device first overheard passively that has now answered us directly is a live reply.
+	fn confirm_live(&mut self, vlan_id: Option<u16>, ip: Ipv4Addr, mac: MacAddr) {
+		if let Some(device) = self.devices.get_mut(&(vlan_id, ip)) {
+			if device.source == DeviceSource::PassiveObservation && device.mac_address == mac.to_string() {
+				device.source = DeviceSource::LiveReply;
+			}
+		}
+	}
+
+	/// Adds a device seen only in the OS ARP cache, unless the scan already found its IP.
+	fn record_cached<B>(&mut self, ip: Ipv4Addr, build: B) -> Option<Device>
+	where
+		B: FnOnce() -> Device,
+	{
+		if self.devices.contains_key(&(None, ip)) {
+			return None;
+		}
+		let device = build();
+		self.devices.insert((None, ip), device.clone());
+		Some(device)
+	}
+
+	/// Sets the hostname of the device at `key`, returning it when the name is a real one.
+	fn set_hostname(&mut self, key: DeviceKey, hostname: String) -> Option<Device> {
+		if hostname == "Unknown" {
+			return None;
+		}
+		let device = self.devices.get_mut(&key)?;
+		device.hostname = hostname;
+		Some(device.clone())
+	}
+
+	fn devices(&self) -> Vec<Device> {
+		let mut devices: Vec<Device> = self.devices.values().cloned().collect();
+		sort_by_ip(&mut devices);
+		devices
+	}
+
+	fn ip_conflicts(&self) -> Vec<IpConflict> {
+		self.ip_conflicts
+			.iter()
+			.map(|((vlan_id, ip), macs)| IpConflict {
+				ip_address: ip.to_string(),
+				mac_addresses: macs.iter().cloned().collect(),
+				vlan_id: *vlan_id,
+			})
+			.collect()
+	}
+}
+
+fn sort_by_ip(devices: &mut [Device]) {
+	devices.sort_by(|a, b| a.ip_address.parse::<Ipv4Addr>().unwrap().cmp(&b.ip_address.parse::<Ipv4Addr>().unwrap()));
+}
+
+/// Merges per-interface scan outcomes, keeping failures instead of aborting.
+fn merge_interface_results(results: Vec<(String, Result<ScanSummary, ScanError>)>) -> MultiInterfaceScan {
+	let mut devices = Vec::new();
+	let mut ip_conflicts = Vec::new();
+	let mut errors = Vec::new();
+	for (interface_name, result) in results {
+		match result {
+			Ok(summary) => {
+				devices.extend(summary.devices);
+				ip_conflicts.extend(summary.ip_conflicts);
+			}
+			Err(e) => errors.push(InterfaceScanError { interface_name, message: e.to_string() }),
+		}
+	}
+	let mut devices = merge_devices(devices);
+	sort_by_ip(&mut devices);
+	MultiInterfaceScan { devices, ip_conflicts, errors, stress_test_running: false }
+}
+
+/// Folds sightings of the same device (same MAC, IP and VLAN) on several interfaces into one
+/// entry listing every interface it answered on. Entries only known from an ARP cache
+/// don't count as answers, and give way to a live sighting.
+fn merge_devices(devices: Vec<Device>) -> Vec<Device> {
+	let mut merged: Vec<Device> = Vec::new();
+	let mut positions: HashMap<(String, String, Option<u16>), usize> = HashMap::new();
+	for mut device in devices {
+		let answered = device.source != DeviceSource::ArpCache;
+		let key = (device.mac_address.clone(), device.ip_address.clone(), device.vlan_id);
+		let Some(&position) = positions.get(&key) else {
+			if answered {
+				device.answered_on = vec![device.interface_name.clone()];
+			}
+			positions.insert(key, merged.len());
+			merged.push(device);
+			continue;
+		};
+
+		let existing = &mut merged[position];
+		if answered && !existing.answered_on.contains(&device.interface_name) {
+			existing.answered_on.push(device.interface_name.clone());
+		}
+		if answered && existing.source == DeviceSource::ArpCache {
+			existing.source = device.source;
+			existing.interface_name = device.interface_name;
+		}
+		if existing.hostname == "Unknown" {
+			existing.hostname = device.hostname;
+		}
+		existing.mac_conflict |= device.mac_conflict;
+		if existing.friendly_name.is_none() {
+			existing.friendly_name = device.friendly_name;
+		}
+	}
+	merged
+}
+
+/// How long the receiver keeps listening for ARP replies after the last request goes out.
+pub const ARP_LISTEN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Conservative rate the unpaced send loop gets ARP requests onto the wire, one syscall each.
+pub const ESTIMATED_ARP_SEND_RATE_PPS: u32 = 1_000;
+const DEFAULT_CHANNEL_RETRIES: u32 = 3;
+const DEFAULT_CHANNEL_RETRY_DELAY_MS: u64 = 200;
+const MAX_CHANNEL_RETRIES: u32 = 10;
+/// Longest wait between two attempts at opening the interface, however far it has doubled
+const MAX_CHANNEL_RETRY_DELAY: Duration = Duration::from_secs(10);
+const CHANNEL_READ_TIMEOUT: Duration = Duration::from_millis(100);
+/// Reverse DNS lookups a scan runs at once
+const MAX_HOSTNAME_LOOKUPS: usize = 16;
+/// How long a scan waits for outstanding hostname lookups once it's done listening; devices
+/// still waiting keep "Unknown"
+const HOSTNAME_LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Where pnet's read buffer is the kernel capture buffer (BPF on macOS/BSD, the packet
+/// driver on Windows) its 4 KiB default fills up with a /24's worth of replies. On Linux it
+/// is only the userspace buffer each frame is read into, so this costs memory and nothing else.
+const DEFAULT_READ_BUFFER_SIZE: usize = 256 * 1024;
+/// Holds one full Ethernet frame
+const MIN_READ_BUFFER_SIZE: usize = 4 * 1024;
+/// pnet allocates the whole buffer up front
+const MAX_READ_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
+/// Calls `open` until it succeeds, up to `retries` extra times, waiting `base_delay` and
+/// doubling it between attempts, up to `MAX_CHANNEL_RETRY_DELAY`. The last attempt's error
+/// is returned unchanged.
+async fn open_with_retry<T, E, F>(retries: u32, base_delay: Duration, mut open: F) -> Result<T, E>
+where
+	E: std::fmt::Display,
+	F: FnMut() -> Result<T, E>,
+{
+	let mut delay = base_delay;
+	let mut attempt = 0;
+	loop {
+		match open() {
+			Ok(opened) => return Ok(opened),
+			Err(e) if attempt < retries => {
+				attempt += 1;
+				warn!("Opening the interface failed ({}), retry {}/{} in {:?}", e, attempt, retries, delay);
+				tokio::time::sleep(delay).await;
+				delay = delay.saturating_mul(2).min(MAX_CHANNEL_RETRY_DELAY);
+			}
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+/// Opens the scan's channel with `factory` (`datalink::channel` outside tests), retrying as
+/// `options` allow.
+async fn open_scan_channel<C>(interface: &NetworkInterface, options: &ScanOptions, promiscuous: bool, factory: C) -> std::io::Result<Channel>
+where
+	C: Fn(&NetworkInterface, datalink::Config) -> std::io::Result<Channel>,
+{
+	let config = options.channel_config(promiscuous);
+	open_with_retry(options.channel_retries(), options.channel_retry_delay(), || factory(interface, config)).await
+}
+
+/// Projected cost of sweeping a subnet, computed without sending anything.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ScanEstimate {
+	pub network: String,
+	pub host_count: u64,
+	pub estimated_seconds: f64,
+}
+
+/// Host count and duration of a scan of `network`: sending one request per host, then
+/// listening for `ARP_LISTEN_TIMEOUT`.
+pub fn estimate_scan_time(network: Ipv4Network) -> ScanEstimate {
+	let size = 1u64 << (32 - network.prefix());
+	let host_count = if network.prefix() >= 31 { size } else { size - 2 };
+	let send_seconds = host_count as f64 / ESTIMATED_ARP_SEND_RATE_PPS as f64;
+	ScanEstimate {
+		network: network.to_string(),
+		host_count,
+		estimated_seconds: send_seconds + ARP_LISTEN_TIMEOUT.as_secs_f64(),
+	}
+}
+
+/// Addresses the send loop will probe, in the order they'll be probed.
+/// False for the network and broadcast addresses, which never answer ARP. /31 point-to-point
+/// links and /32s have no such addresses (RFC 3021), so every address there is a host.
+fn is_host_address(network: Ipv4Network, ip: Ipv4Addr) -> bool {
+	network.prefix() >= 31 || (ip != network.network() && ip != network.broadcast())
+}
+
+/// Every host address of `network`, ascending: both addresses of a /31, the single one of
+/// a /32, and everything but the network and broadcast addresses otherwise.
+fn host_addresses(network: Ipv4Network) -> impl Iterator<Item = Ipv4Addr> {
+	network.iter().filter(move |ip| is_host_address(network, *ip))
+}
+
+fn scan_targets(network: Ipv4Network, source_ipv4: Ipv4Addr, options: &ScanOptions) -> Vec<Ipv4Addr> {
+	let mut targets: Vec<Ipv4Addr> = host_addresses(network).filter(|ip| *ip != source_ipv4).collect();
+	if options.randomize_order {
+		let mut rng = match options.seed {
+			Some(seed) => StdRng::seed_from_u64(seed),
+			None => StdRng::from_entropy(),
+		};
+		targets.shuffle(&mut rng);
+	}
+	if let Some(max_hosts) = options.max_hosts {
+		targets.truncate(max_hosts);
+	}
+	targets
+}
+
+/// Runs an ARP sweep of the default interface's subnet.
+///
+/// `on_device` is called once per device, the moment it is discovered, so callers can
+/// stream results to the UI; the returned list still contains the complete set.
+pub async fn perform_scan<F>(options: ScanOptions, on_device: F) -> Result<ScanSummary, ScanError>
+where
+	F: Fn(&Device) + Send + Sync + 'static,
+{
+	let interface = default_interface()?;
+	scan_interface(interface, options, on_device).await
+}
+
+/// Sweeps the default interface only until the device with `mac` answers, for finding the
+/// address a known device was given. `None` when it didn't answer within the listen window.
+pub async fn find_by_mac(mac: &str, options: ScanOptions) -> Result<Option<Device>, ScanError> {
+	let target: MacAddr = mac.trim().replace('-', ":").parse().map_err(|_| ScanError::InvalidMac(mac.to_string()))?;
+	let options = ScanOptions { stop_at_mac: Some(target), ..options };
+	let summary = perform_scan(options, |_: &Device| {}).await?;
+	Ok(summary.devices.into_iter().find(|device| device.mac_address == target.to_string()))
+}
+
+/// Scans every usable interface concurrently and merges the results.
+pub async fn scan_all_interfaces<F>(options: ScanOptions, on_device: F) -> MultiInterfaceScan
+where
+	F: Fn(&Device) + Send + Sync + 'static,
+{
+	let on_device = Arc::new(on_device);
+	let interfaces: Vec<NetworkInterface> = scannable_interfaces(datalink::interfaces())
+		.into_iter()
+		.filter(|iface| options.interfaces.is_empty() || options.interfaces.contains(&iface.name))
+		.collect();
+	let mut results = Vec::new();
+	for requested in &options.interfaces {
+		if !interfaces.iter().any(|iface| &iface.name == requested) {
+			let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "interface not found or not scannable");
+			results.push((requested.clone(), Err(ScanError::IoError(missing))));
+		}
+	}
+
+	let mut tasks = Vec::new();
+	for interface in interfaces {
+		let name = interface.name.clone();
+		let on_device = Arc::clone(&on_device);
+		let task = tokio::spawn(scan_interface(interface, options.clone(), move |device: &Device| on_device(device)));
+		tasks.push((name, task));
+	}
+
+	for (name, task) in tasks {
+		let result = match task.await {
+			Ok(result) => result,
+			Err(e) => Err(ScanError::IoError(std::io::Error::other(e.to_string()))),
+		};
+		results.push((name, result));
+	}
+	merge_interface_results(results)
+}
+
+/// A broadcast ARP request from `sender_mac`/`sender_ip` asking who has `target_ip`.
+fn build_arp_request(sender_mac: MacAddr, sender_ip: Ipv4Addr, target_ip: Ipv4Addr) -> [u8; 42] {
+	let mut ethernet_buffer = [0u8; 42];
+	let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer).unwrap();
+
+	ethernet_packet.set_destination(MacAddr::broadcast());
+	ethernet_packet.set_source(sender_mac);
+	ethernet_packet.set_ethertype(EtherTypes::Arp);
+
+	let mut arp_buffer = [0u8; 28];
+	let mut arp_packet = MutableArpPacket::new(&mut arp_buffer).unwrap();
+
+	arp_packet.set_hardware_type(pnet::packet::arp::ArpHardwareTypes::Ethernet);
+	arp_packet.set_protocol_type(EtherTypes::Ipv4);
+	arp_packet.set_hw_addr_len(6);
+	arp_packet.set_proto_addr_len(4);
+	arp_packet.set_operation(ArpOperations::Request);
+	arp_packet.set_sender_hw_addr(sender_mac);
+	arp_packet.set_sender_proto_addr(sender_ip);
+	arp_packet.set_target_hw_addr(MacAddr::zero());
+	arp_packet.set_target_proto_addr(target_ip);
+
+	ethernet_packet.set_payload(arp_packet.packet());
+	ethernet_buffer
+}
+
+/// Where the target protocol address sits in an ARP request frame: the 14-byte Ethernet
+/// header plus 24 bytes into the ARP packet.
+const ARP_TARGET_IP_OFFSET: usize = 38;
+
+/// ARP requests for every target, in sweep order, built before sending so the send loop only
+/// pushes bytes. Only the target address differs between them, so each one is a patched copy
+/// of a single template.
+fn build_arp_requests(sender_mac: MacAddr, sender_ip: Ipv4Addr, targets: &[Ipv4Addr]) -> Vec<[u8; 42]> {
+	let template = build_arp_request(sender_mac, sender_ip, Ipv4Addr::UNSPECIFIED);
+	targets
+		.iter()
+		.map(|target| {
+			let mut frame = template;
+			frame[ARP_TARGET_IP_OFFSET..].copy_from_slice(&target.octets());
+			frame
 		})
-		.ok_or(ScanError::NoActiveInterface)?;
-		
-	let network = Ipv4Network::new(source_ipv4, interface.ips.iter().find(|ip| ip.is_ipv4()).unwrap().prefix())
-		.expect("Invalid network configuration");
+		.collect()
+}
+
+/// A gratuitous ARP announcing that `ip` is at `mac`: a broadcast request whose sender and
+/// target protocol addresses are both `ip` (RFC 5227 announcement).
+fn build_gratuitous_arp(mac: MacAddr, ip: Ipv4Addr) -> [u8; 42] {
+	build_arp_request(mac, ip, ip)
+}
 
-	let (mut tx, mut rx) = match datalink::channel(&interface, Default::default()) {
+/// Broadcasts a gratuitous ARP for `ip` at `mac` on the default interface.
+///
+/// Potentially disruptive: every neighbor that accepts it rewrites its ARP table. To keep it
+/// a diagnostic rather than a spoofing tool, `mac` must be this interface's own MAC, `ip` must
+/// be a private address on its subnet, and no other device may currently hold `ip`.
+pub fn send_gratuitous_arp(ip: Ipv4Addr, mac: MacAddr) -> Result<(), ScanError> {
+	privileges::require_raw_sockets().map_err(ScanError::InsufficientPrivileges)?;
+	let interface = default_interface()?;
+	let source_ipv4 = interface_ipv4(&interface).ok_or(ScanError::NoActiveInterface)?;
+	let prefix = interface.ips.iter().find(|ip| ip.is_ipv4()).map(|ip| ip.prefix()).ok_or(ScanError::NoActiveInterface)?;
+	let network = Ipv4Network::new(source_ipv4, prefix).map_err(|_| ScanError::NoActiveInterface)?;
+
+	if !crate::stresser::is_private_ip(&ip) {
+		return Err(ScanError::GratuitousArpRefused(format!("{} is not a private address", ip)));
+	}
+	if !network.contains(ip) || !is_host_address(network, ip) {
+		return Err(ScanError::GratuitousArpRefused(format!("{} is not a host address on {}", ip, network)));
+	}
+	if interface.mac != Some(mac) {
+		return Err(ScanError::GratuitousArpRefused(format!("{} is not the MAC of {}", mac, interface.name)));
+	}
+	if let Some((_, owner)) = arp_cache::read_arp_cache().into_iter().find(|(cached_ip, owner)| *cached_ip == ip && *owner != mac) {
+		return Err(ScanError::GratuitousArpRefused(format!("{} is in use by {}", ip, owner)));
+	}
+
+	let (mut tx, _rx) = match datalink::channel(&interface, Default::default()) {
 		Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
 		Ok(_) => return Err(ScanError::ChannelCreationFailure),
 		Err(e) => return Err(ScanError::IoError(e)),
 	};
-	
+	warn!("Sending gratuitous ARP: {} is at {} on {}", ip, mac, interface.name);
+	match tx.send_to(&build_gratuitous_arp(mac, ip), None) {
+		Some(Ok(())) => Ok(()),
+		Some(Err(e)) => Err(ScanError::IoError(e)),
+		None => Err(ScanError::ChannelCreationFailure),
+	}
+}
+
+/// Hands each reply from `replies` to `on_reply` while `sending` runs and until `listen`
+/// runs out after it, or straight away once `stop_at` has answered, dropping whatever is
+/// left of `sending`. True when it did.
+async fn listen_for_replies<S, R>(
+	replies: &mut mpsc::UnboundedReceiver<ArpReply>,
+	sending: S,
+	listen: Duration,
+	stop_at: Option<MacAddr>,
+	mut on_reply: R,
+) -> bool
+where
+	S: Future<Output = ()>,
+	R: FnMut(ArpReply),
+{
+	let sweep = async {
+		sending.await;
+		tokio::time::sleep(listen).await;
+	};
+	tokio::pin!(sweep);
+	loop {
+		tokio::select! {
+			_ = &mut sweep => return false,
+			reply = replies.recv() => match reply {
+				Some(reply) => {
+					on_reply(reply);
+					if stop_at == Some(reply.mac) {
+						return true;
+					}
+				}
+				// The receiver is gone; nothing more can answer
+				None => {
+					sweep.await;
+					return false;
+				}
+			},
+		}
+	}
+}
+
+async fn scan_interface<F>(interface: NetworkInterface, options: ScanOptions, on_device: F) -> Result<ScanSummary, ScanError>
+where
+	F: Fn(&Device) + Send + Sync + 'static,
+{
+	let hint = options.source_ip.as_deref().and_then(|ip| ip.trim().parse().ok());
+	let selected = select_ipv4(&interface, hint).ok_or(ScanError::NoActiveInterface)?;
+	check_arp_applicable(&interface, selected)?;
+	privileges::require_raw_sockets().map_err(ScanError::InsufficientPrivileges)?;
+	let source_ipv4 = selected.ip();
+	let network = Ipv4Network::new(selected.network(), selected.prefix()).expect("Invalid network configuration");
+	let ipv4_count = interface.ips.iter().filter(|ip| ip.is_ipv4()).count();
+	if ipv4_count > 1 {
+		info!("{} has {} IPv4 addresses, scanning {} from {}", interface.name, ipv4_count, network, source_ipv4);
+	}
+
+	// Every request is built before the channel opens, so the send phase is a tight loop
 	let source_mac = interface.mac.unwrap();
-	let found_devices = Arc::new(Mutex::new(HashMap::new()));
-	let db = Arc::new(OuiDb::new_embedded());
+	let targets = scan_targets(network, source_ipv4, &options);
+	let hosts_probed = targets.len();
+	let requests = build_arp_requests(source_mac, source_ipv4, &targets);
+
+	let open = |promiscuous: bool| open_scan_channel(&interface, &options, promiscuous, datalink::channel);
+	let mut promiscuous = options.promiscuous;
+	let mut channel = open(promiscuous).await;
+	if promiscuous && channel.is_err() {
+		warn!("Promiscuous mode unavailable on {}, scanning without it", interface.name);
+		promiscuous = false;
+		channel = open(false).await;
+	}
+	let (mut tx, mut rx) = match channel {
+		Ok(Channel::Ethernet(tx, rx)) => (tx, rx),
+		Ok(_) => return Err(ScanError::ChannelCreationFailure),
+		Err(e) => return Err(ScanError::IoError(e)),
+	};
+	
+	let exclusions = options.clone();
+	let on_device = Arc::new(move |device: &Device| {
+		if exclusions.excludes(device) {
+			return;
+		}
+		match &exclusions.aliases {
+			Some(aliases) => on_device(&Device { friendly_name: aliases.get(&device.mac_address), ..device.clone() }),
+			None => on_device(device),
+		}
+	});
+	let found_devices = Arc::new(Mutex::new(ScanCollector::default()));
+	let oui = OuiCache::new(Arc::new(OuiDb::new_embedded()));
+	if oui.db().summary().looks_malformed() {
+		warn!("OUI database looks malformed ({:?}); manufacturers may show as Unknown", oui.db().summary());
+	}
 
+	let own_adapter = if options.own_manufacturer_from_oui { None } else { adapter_name(&interface) };
 	let own_device = Device {
-		ip_address: source_ipv4.to_string(),
-		mac_address: source_mac.to_string(),
-		manufacturer: get_manufacturer_with_db(&db, &source_mac),
 		hostname: resolve_hostname(source_ipv4),
+		interface_name: interface.name.clone(),
+		..build_own_device(source_ipv4, source_mac, &oui, own_adapter)
 	};
+	on_device(&own_device);
 	found_devices.lock().unwrap().insert(source_ipv4, own_device);
 
-	for target_ipv4 in network.iter() {
-		if target_ipv4 == source_ipv4 { continue; }
+	let subnet_hosts = host_addresses(network).count();
+
+	// The receiver starts before the first request so early replies aren't missed, and only
+	// parses frames; vendor and hostname lookups happen in the enrichment stage below, so a
+	// slow reverse DNS lookup never leaves replies sitting in the socket
+	let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+	let recorded_frames = Arc::new(Mutex::new(Vec::new()));
+	let recorder = options.record_to.is_some().then(|| Arc::clone(&recorded_frames));
+	tokio::task::spawn_blocking(move || {
+		loop {
+			let packet = match rx.next() {
+				Ok(packet) => packet,
+				// Woken by the read timeout: carry on unless the scan stopped listening
+				Err(e) if matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock) => {
+					if reply_tx.is_closed() {
+						break;
+					}
+					continue;
+				}
+				Err(_) => break,
+			};
+			let reply = read_reply(packet, promiscuous, source_mac);
+			if let Some(frames) = recorder.as_ref().filter(|_| reply.is_some()) {
+				frames.lock().unwrap().push(packet.to_vec());
+			}
+			// The scan stopped listening
+			if reply.is_some_and(|reply| reply_tx.send(reply).is_err()) {
+				break;
+			}
+		}
+	});
+
+	// Devices are reported as soon as they answer, and again once their hostname resolves
+	let mut lookups = HostnameLookups::new();
+	// Yielding after each request lets replies be handled mid-sweep, so a `stop_at_mac`
+	// match ends the sending too
+	let sending = async {
+		for request in &requests {
+			if let Some(limiter) = &options.rate_limiter {
+				limiter.acquire().await;
+			}
+			let _ = tx.send_to(request, None);
+			tokio::task::yield_now().await;
+		}
+	};
+	let matched = listen_for_replies(&mut reply_rx, sending, ARP_LISTEN_TIMEOUT, options.stop_at_mac, |reply| {
+		let found = found_devices.lock().unwrap().record_parsed_reply(network, reply, || Device {
+			interface_name: interface.name.clone(),
+			source: reply.source,
+			vlan_id: reply.vlan_id,
+			..build_device(reply.ip, reply.mac, &oui)
+		});
+		if let Some(device) = found {
+			println!("Device found: {:?}", device);
+			on_device(&device);
+			lookups.spawn((reply.vlan_id, reply.ip), Arc::clone(&found_devices), Arc::clone(&on_device));
+		}
+	})
+	.await;
+	if let Some(path) = &options.record_to {
+		let mut recording = ScanRecording {
+			interface_name: interface.name.clone(),
+			network: network.to_string(),
+			source_ip: source_ipv4.to_string(),
+			source_mac: source_mac.to_string(),
+			promiscuous,
+			hosts_probed,
+			frames: Vec::new(),
+		};
+		for frame in recorded_frames.lock().unwrap().iter() {
+			recording.push_frame(frame);
+		}
+		if let Err(e) = recording.save(Path::new(path)) {
+			warn!("Failed to save scan recording to {}: {}", path, e);
+		}
+	}
+
+	// Quiet devices that skipped our requests may still be in the OS ARP table. They're
+	// recorded under the lock and named after it's released, like live replies.
+	let cached = if matched { Vec::new() } else { arp_cache::read_arp_cache() };
+	let added: Vec<(Ipv4Addr, Device)> = {
+		let mut collector = found_devices.lock().unwrap();
+		cached
+			.into_iter()
+			.filter(|(ip, _)| network.contains(*ip) && is_host_address(network, *ip))
+			.filter_map(|(ip, mac)| {
+				let device = collector.record_cached(ip, || Device {
+					interface_name: interface.name.clone(),
+					source: DeviceSource::ArpCache,
+					..build_device(ip, mac, &oui)
+				});
+				device.map(|device| (ip, device))
+			})
+			.collect()
+	};
+	for (ip, device) in added {
+		on_device(&device);
+		lookups.spawn((None, ip), Arc::clone(&found_devices), Arc::clone(&on_device));
+	}
+	lookups.finish(HOSTNAME_LOOKUP_TIMEOUT).await;
 
-		let mut ethernet_buffer = [0u8; 42];
-		let mut ethernet_packet = MutableEthernetPacket::new(&mut ethernet_buffer).unwrap();
+	let collector = found_devices.lock().unwrap();
+	if collector.off_subnet_replies > 0 {
+		warn!("Ignored {} ARP replies from outside {} on {}", collector.off_subnet_replies, network, interface.name);
+	}
+	let oui_stats = oui.db().lookup_stats();
+	if oui_stats.misses > 0 {
+		info!(
+			"OUI database identified {} of {} prefixes on {} ({} lookups served from cache); unknown prefixes: {:?}",
+			oui_stats.hits, oui_stats.lookups, interface.name, oui.stats().hits, oui_stats.missed_prefixes
+		);
+	}
+	let mut devices = collector.devices();
+	devices.retain(|device| !options.excludes(device));
+	if let Some(aliases) = &options.aliases {
+		aliases.apply(&mut devices);
+	}
+	let ip_conflicts = collector.ip_conflicts();
+	if !ip_conflicts.is_empty() {
+		warn!("{} IP address(es) claimed by more than one device on {}", ip_conflicts.len(), interface.name);
+	}
+	Ok(ScanSummary { devices, ip_conflicts, hosts_probed, subnet_hosts, promiscuous, stress_test_running: false })
+}
 
-		ethernet_packet.set_destination(MacAddr::broadcast());
-		ethernet_packet.set_source(source_mac);
-		ethernet_packet.set_ethertype(EtherTypes::Arp);
+/// Replays a scan saved with `ScanOptions::record_to` through the same reply parsing and
+/// device building as a live scan, with `options`' exclusions and aliases. Nothing is sent,
+/// no hostnames are resolved and the OS ARP table isn't read, so a recording always gives
+/// the same devices.
+pub fn perform_scan_from_recording<F>(path: &Path, options: &ScanOptions, on_device: F) -> Result<ScanSummary, ScanError>
+where
+	F: Fn(&Device),
+{
+	replay_recording(&ScanRecording::load(path)?, options, on_device)
+}
 
-		let mut arp_buffer = [0u8; 28];
-		let mut arp_packet = MutableArpPacket::new(&mut arp_buffer).unwrap();
+fn replay_recording<F>(recording: &ScanRecording, options: &ScanOptions, on_device: F) -> Result<ScanSummary, ScanError>
+where
+	F: Fn(&Device),
+{
+	let invalid = |what: &str| ScanError::IoError(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("Invalid {} in recording", what)));
+	let network: Ipv4Network = recording.network.parse().map_err(|_| invalid("network"))?;
+	let source_ipv4: Ipv4Addr = recording.source_ip.parse().map_err(|_| invalid("source IP"))?;
+	let source_mac: MacAddr = recording.source_mac.parse().map_err(|_| invalid("source MAC"))?;
+	let oui = OuiCache::new(Arc::new(OuiDb::new_embedded()));
+	let build = |reply: ArpReply| Device {
+		interface_name: recording.interface_name.clone(),
+		source: reply.source,
+		vlan_id: reply.vlan_id,
+		..build_device(reply.ip, reply.mac, &oui)
+	};
+	let report = |device: &Device| {
+		if !options.excludes(device) {
+			on_device(&Device { friendly_name: options.aliases.as_ref().and_then(|a| a.get(&device.mac_address)), ..device.clone() });
+		}
+	};
 
-		arp_packet.set_hardware_type(pnet::packet::arp::ArpHardwareTypes::Ethernet);
-		arp_packet.set_protocol_type(EtherTypes::Ipv4);
-		arp_packet.set_hw_addr_len(6);
-		arp_packet.set_proto_addr_len(4);
-		arp_packet.set_operation(ArpOperations::Request);
-		arp_packet.set_sender_hw_addr(source_mac);
-		arp_packet.set_sender_proto_addr(source_ipv4);
-		arp_packet.set_target_hw_addr(MacAddr::zero());
-		arp_packet.set_target_proto_addr(target_ipv4);
-		
-		ethernet_packet.set_payload(arp_packet.packet());
+	let mut collector = ScanCollector::default();
+	let own_device = build(ArpReply { ip: source_ipv4, mac: source_mac, source: DeviceSource::LiveReply, vlan_id: None });
+	report(&own_device);
+	collector.insert(source_ipv4, own_device);
+	for frame in recording.frame_bytes()? {
+		let Some(reply) = read_reply(&frame, recording.promiscuous, source_mac) else {
+			continue;
+		};
+		if let Some(device) = collector.record_parsed_reply(network, reply, || build(reply)) {
+			report(&device);
+		}
+	}
 
-		let _ = tx.send_to(ethernet_packet.packet(), None);
+	let mut devices = collector.devices();
+	devices.retain(|device| !options.excludes(device));
+	if let Some(aliases) = &options.aliases {
+		aliases.apply(&mut devices);
 	}
-	
-	let devices_clone = Arc::clone(&found_devices);
-	let db_clone = Arc::clone(&db);
-	let receiver_task = tokio::spawn(async move {
-		loop {
-			match rx.next() {
-				Ok(packet) => {
-					if let Some(ethernet_packet) = EthernetPacket::new(packet) {
-						if ethernet_packet.get_ethertype() == EtherTypes::Arp {
-							if let Some(arp_packet) = ArpPacket::new(ethernet_packet.payload()) {
-								if arp_packet.get_operation() == ArpOperations::Reply {
-									let sender_ip = arp_packet.get_sender_proto_addr();
-									let sender_mac = arp_packet.get_sender_hw_addr();
-									let mut devices = devices_clone.lock().unwrap();
-									if !devices.contains_key(&sender_ip) {
-										let device = Device {
-											ip_address: sender_ip.to_string(),
-											mac_address: sender_mac.to_string(),
-											manufacturer: get_manufacturer_with_db(&db_clone, &sender_mac),
-											hostname: resolve_hostname(sender_ip),
-										};
-										println!("Device found: {:?}", device);
-										devices.insert(sender_ip, device);
-									}
-								}
-							}
-						}
-					}
+	Ok(ScanSummary {
+		devices,
+		ip_conflicts: collector.ip_conflicts(),
+		hosts_probed: recording.hosts_probed,
+		subnet_hosts: host_addresses(network).count(),
+		promiscuous: recording.promiscuous,
+		stress_test_running: false,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ipnetwork::IpNetwork;
+
+	fn interface(name: &str, index: u32, ip: Option<&str>, mac: Option<MacAddr>, flags: u32) -> NetworkInterface {
+		NetworkInterface {
+			name: name.to_string(),
+			description: String::new(),
+			index,
+			mac,
+			ips: ip.map(|ip| vec![ip.parse::<IpNetwork>().unwrap()]).unwrap_or_default(),
+			flags,
+		}
+	}
+
+	const UP: u32 = 0x1;
+	const LOOPBACK: u32 = 0x8;
+	#[cfg(unix)]
+	const POINT_TO_POINT: u32 = 0x10;
+
+	#[test]
+	fn test_scannable_interfaces() {
+		let mac = Some(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55));
+		let interfaces = vec![
+			interface("eth0", 1, Some("192.168.1.10/24"), mac, UP),
+			interface("wlan0", 2, Some("10.0.0.5/24"), mac, UP),
+			interface("lo", 3, Some("127.0.0.1/8"), None, UP | LOOPBACK),
+			interface("eth1", 4, Some("172.16.0.2/16"), mac, 0),
+			interface("tun0", 5, None, mac, UP),
+		];
+
+		let names: Vec<String> = scannable_interfaces(interfaces).into_iter().map(|i| i.name).collect();
+		assert_eq!(names, vec!["eth0", "wlan0"]);
+	}
+
+	#[test]
+	fn test_scan_key() {
+		let mac = Some(MacAddr::new(0x00, 0x11, 0x22, 0x33, 0x44, 0x55));
+		assert_eq!(scan_key(&interface("eth0", 2, Some("192.168.1.10/24"), mac, UP)).as_deref(), Some("eth0/192.168.1.0/24"));
+		assert_eq!(scan_key(&interface("eth0", 2, None, mac, UP)), None);
+	}
+
+	#[test]
+	fn test_select_ipv4_among_several() {
+		let mut eth0 = interface("eth0", 2, Some("192.168.1.10/24"), None, UP);
+		eth0.ips.push("fe80::1/64".parse().unwrap());
+		eth0.ips.push("10.20.0.4/16".parse().unwrap());
+
+		let first = select_ipv4(&eth0, None).unwrap();
+		assert_eq!(first.ip(), Ipv4Addr::new(192, 168, 1, 10));
+		assert_eq!(first.prefix(), 24);
+
+		let secondary = select_ipv4(&eth0, Some(Ipv4Addr::new(10, 20, 0, 4))).unwrap();
+		assert_eq!(secondary.ip(), Ipv4Addr::new(10, 20, 0, 4));
+		assert_eq!(secondary.prefix(), 16);
+
+		// A hint the interface doesn't have falls back to the first address
+		assert_eq!(select_ipv4(&eth0, Some(Ipv4Addr::new(172, 16, 0, 1))), Some(first));
+		assert_eq!(select_ipv4(&interface("tun0", 5, None, None, UP), None), None);
+	}
+
+	#[test]
+	fn test_estimate_scan_time() {
+		let estimate = estimate_scan_time("192.168.1.0/24".parse().unwrap());
+		assert_eq!(estimate.network, "192.168.1.0/24");
+		assert_eq!(estimate.host_count, 254);
+		assert!((estimate.estimated_seconds - 5.254).abs() < 1e-9);
+
+		let estimate = estimate_scan_time("10.0.0.0/16".parse().unwrap());
+		assert_eq!(estimate.host_count, 65_534);
+		assert!((estimate.estimated_seconds - 70.534).abs() < 1e-9);
+
+		// Point-to-point links have no network or broadcast address to skip
+		assert_eq!(estimate_scan_time("10.0.0.0/31".parse().unwrap()).host_count, 2);
+		assert_eq!(estimate_scan_time("10.0.0.1/32".parse().unwrap()).host_count, 1);
+	}
+
+	#[test]
+	fn test_excluded_ips_and_macs() {
+		let options = ScanOptions {
+			exclude_ips: vec!["192.168.1.1".to_string()],
+			exclude_macs: vec!["AA-BB-CC".to_string(), "11:22:33:44:55:66".to_string()],
+			..ScanOptions::default()
+		};
+
+		assert!(options.excludes(&Device::sample("192.168.1.1", "00:00:00:00:00:01", "eth0")));
+		// A vendor prefix hides every device of that vendor
+		assert!(options.excludes(&Device::sample("192.168.1.20", "aa:bb:cc:00:00:01", "eth0")));
+		assert!(options.excludes(&Device::sample("192.168.1.21", "aa:bb:cc:ff:ff:ff", "eth0")));
+		assert!(options.excludes(&Device::sample("192.168.1.30", "11:22:33:44:55:66", "eth0")));
+
+		assert!(!options.excludes(&Device::sample("192.168.1.10", "aa:bb:cd:00:00:01", "eth0")));
+		assert!(!options.excludes(&Device::sample("192.168.1.11", "11:22:33:44:55:67", "eth0")));
+		assert!(!ScanOptions::default().excludes(&Device::sample("192.168.1.1", "aa:bb:cc:00:00:01", "eth0")));
+	}
+
+	#[test]
+	fn test_scan_targets_order() {
+		let network: Ipv4Network = "192.168.1.0/24".parse().unwrap();
+		let source: Ipv4Addr = "192.168.1.10".parse().unwrap();
+
+		let sequential = scan_targets(network, source, &ScanOptions::default());
+		assert_eq!(sequential.len(), 253);
+		assert_eq!(sequential[0], Ipv4Addr::new(192, 168, 1, 1));
+		assert!(!sequential.contains(&source));
+
+		let seeded = |seed| ScanOptions { randomize_order: true, seed: Some(seed), ..ScanOptions::default() };
+		let first = scan_targets(network, source, &seeded(42));
+		let again = scan_targets(network, source, &seeded(42));
+		let other = scan_targets(network, source, &seeded(7));
+		assert_eq!(first, again);
+		assert_ne!(first, other);
+		assert_ne!(first, sequential);
+
+		// Shuffling never changes which addresses are probed
+		let mut sorted = first.clone();
+		sorted.sort();
+		assert_eq!(sorted, sequential);
+	}
+
+	#[test]
+	fn test_max_hosts_budget() {
+		let network: Ipv4Network = "192.168.1.0/24".parse().unwrap();
+		let source: Ipv4Addr = "192.168.1.10".parse().unwrap();
+
+		let budget = ScanOptions { max_hosts: Some(50), ..ScanOptions::default() };
+		let targets = scan_targets(network, source, &budget);
+		assert_eq!(targets.len(), 50);
+		assert_eq!(targets[0], Ipv4Addr::new(192, 168, 1, 1));
+		// The first 50 hosts, skipping our own address
+		assert_eq!(targets[49], Ipv4Addr::new(192, 168, 1, 51));
+
+		// Randomized scans sample across the whole subnet
+		let sampled = ScanOptions { randomize_order: true, seed: Some(42), ..budget.clone() };
+		let targets = scan_targets(network, source, &sampled);
+		assert_eq!(targets.len(), 50);
+		assert!(targets.iter().any(|ip| ip.octets()[3] > 51));
+
+		// A budget above the subnet size changes nothing
+		let generous = ScanOptions { max_hosts: Some(1000), ..ScanOptions::default() };
+		assert_eq!(scan_targets(network, source, &generous).len(), 253);
+	}
+
+	#[tokio::test]
+	async fn test_host_route_is_not_scanned() {
+		let tun = interface("tun0", 5, Some("10.8.0.2/32"), Some(MacAddr::zero()), UP);
+		let result = scan_interface(tun, ScanOptions::default(), |_: &Device| {}).await;
+		match result {
+			Err(ScanError::ScanNotApplicable { interface, network }) => {
+				assert_eq!(interface, "tun0");
+				assert_eq!(network, "10.8.0.2/32");
+			}
+			other => panic!("expected ScanNotApplicable, got {:?}", other),
+		}
+
+		#[cfg(unix)]
+		{
+			let point_to_point = interface("ppp0", 6, Some("10.64.0.1/31"), Some(MacAddr::zero()), UP | POINT_TO_POINT);
+			let network = select_ipv4(&point_to_point, None).unwrap();
+			assert!(check_arp_applicable(&point_to_point, network).is_err());
+		}
+		// The same /31 on Ethernet still has a peer to find
+		let link = interface("eth1", 7, Some("10.64.0.1/31"), Some(MacAddr::zero()), UP);
+		assert!(check_arp_applicable(&link, select_ipv4(&link, None).unwrap()).is_ok());
+		let lan = interface("eth0", 2, Some("192.168.1.5/24"), Some(MacAddr::zero()), UP);
+		assert!(check_arp_applicable(&lan, select_ipv4(&lan, None).unwrap()).is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_channel_open_retries() {
+		let attempts = std::cell::Cell::new(0);
+		let opened = open_with_retry(3, Duration::from_millis(1), || {
+			attempts.set(attempts.get() + 1);
+			if attempts.get() <= 2 {
+				Err(std::io::Error::other("interface not ready"))
+			} else {
+				Ok("channel")
+			}
+		})
+		.await;
+		assert_eq!(opened.unwrap(), "channel");
+		assert_eq!(attempts.get(), 3);
+
+		// Once the retries run out the error comes back as is
+		attempts.set(0);
+		let failed: Result<(), _> = open_with_retry(2, Duration::from_millis(1), || {
+			attempts.set(attempts.get() + 1);
+			Err(std::io::Error::other("interface not ready"))
+		})
+		.await;
+		assert_eq!(failed.unwrap_err().to_string(), "interface not ready");
+		assert_eq!(attempts.get(), 3);
+
+		// Out-of-range options are held to sane bounds
+		let extreme = ScanOptions { channel_retries: Some(u32::MAX), channel_retry_delay_ms: Some(u64::MAX), ..ScanOptions::default() };
+		assert_eq!(extreme.channel_retries(), MAX_CHANNEL_RETRIES);
+		assert_eq!(extreme.channel_retry_delay(), MAX_CHANNEL_RETRY_DELAY);
+	}
+
+	#[test]
+	fn test_scan_targets_skip_network_and_broadcast() {
+		let network: Ipv4Network = "192.168.1.0/24".parse().unwrap();
+		let targets = scan_targets(network, Ipv4Addr::new(192, 168, 1, 10), &ScanOptions::default());
+		assert!(!targets.contains(&Ipv4Addr::new(192, 168, 1, 0)));
+		assert!(!targets.contains(&Ipv4Addr::new(192, 168, 1, 255)));
+		assert_eq!(targets.last(), Some(&Ipv4Addr::new(192, 168, 1, 254)));
+
+		// A /31 has no network or broadcast address: the peer is the only target
+		let point_to_point: Ipv4Network = "10.0.0.0/31".parse().unwrap();
+		let targets = scan_targets(point_to_point, Ipv4Addr::new(10, 0, 0, 0), &ScanOptions::default());
+		assert_eq!(targets, vec![Ipv4Addr::new(10, 0, 0, 1)]);
+	}
+
+	#[test]
+	fn test_small_subnets_enumerate_their_hosts() {
+		let hosts = |cidr: &str| host_addresses(cidr.parse().unwrap()).map(|ip| ip.to_string()).collect::<Vec<String>>();
+		assert_eq!(hosts("192.168.1.4/30"), vec!["192.168.1.5", "192.168.1.6"]);
+		assert_eq!(hosts("10.0.0.0/31"), vec!["10.0.0.0", "10.0.0.1"]);
+		assert_eq!(hosts("10.8.0.2/32"), vec!["10.8.0.2"]);
+
+		// Each matches the scan time estimate and the targets a sweep from one of them probes
+		for cidr in ["192.168.1.4/30", "10.0.0.0/31", "10.8.0.2/32"] {
+			let network: Ipv4Network = cidr.parse().unwrap();
+			assert_eq!(estimate_scan_time(network).host_count, hosts(cidr).len() as u64);
+		}
+		let targets = scan_targets("192.168.1.4/30".parse().unwrap(), Ipv4Addr::new(192, 168, 1, 5), &ScanOptions::default());
+		assert_eq!(targets, vec![Ipv4Addr::new(192, 168, 1, 6)]);
+	}
+
+	#[test]
+	fn test_conflicting_mac_is_flagged() {
+		let ip = Ipv4Addr::new(192, 168, 1, 20);
+		let first = MacAddr::new(0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x01);
+		let second = MacAddr::new(0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x02);
+		let mut collector = ScanCollector::default();
+
+		let found = collector.record_reply(ip, first, || Device::sample("192.168.1.20", &first.to_string(), "eth0"));
+		assert!(found.is_some());
+
+		// The same MAC answering twice is just a duplicate
+		let found = collector.record_reply(ip, first, || unreachable!());
+		assert!(found.is_none());
+		assert!(!collector.devices()[0].mac_conflict);
+
+		// A different MAC for the same IP is reported, keeping the first MAC
+		let found = collector.record_reply(ip, second, || unreachable!());
+		assert!(found.is_none());
+		let devices = collector.devices();
+		assert_eq!(devices.len(), 1);
+		assert!(devices[0].mac_conflict);
+		assert_eq!(devices[0].mac_address, first.to_string());
+	}
+
+	#[test]
+	fn test_ip_conflict_is_reported() {
+		let shared = Ipv4Addr::new(192, 168, 1, 20);
+		let first = MacAddr::new(0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x01);
+		let second = MacAddr::new(0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x02);
+		let mut collector = ScanCollector::default();
+
+		collector.record_reply(shared, first, || Device::sample("192.168.1.20", &first.to_string(), "eth0"));
+		collector.record_reply(shared, second, || unreachable!());
+		// One MAC on two IPs (a router, say) is not an IP conflict
+		collector.record_reply(Ipv4Addr::new(192, 168, 1, 21), first, || Device::sample("192.168.1.21", &first.to_string(), "eth0"));
+
+		assert_eq!(
+			collector.ip_conflicts(),
+			vec![IpConflict {
+				ip_address: "192.168.1.20".to_string(),
+				mac_addresses: vec![first.to_string(), second.to_string()],
+				vlan_id: None,
+			}]
+		);
+	}
+
+	#[test]
+	fn test_off_subnet_replies_are_dropped() {
+		let network: Ipv4Network = "192.168.1.0/24".parse().unwrap();
+		let mut collector = ScanCollector::default();
+
+		let (ip, mac) = parse_arp_reply(&arp_frame([0x08, 0x06], 2)).unwrap();
+		let found = collector.record_subnet_reply(network, ip, mac, || Device::sample("192.168.1.42", &mac.to_string(), "eth0"));
+		assert!(found.is_some());
+
+		let mut stray = arp_frame([0x08, 0x06], 2);
+		stray[28..32].copy_from_slice(&[10, 0, 0, 42]);
+		let (ip, mac) = parse_arp_reply(&stray).unwrap();
+		let found = collector.record_subnet_reply(network, ip, mac, || unreachable!());
+		assert!(found.is_none());
+
+		let devices = collector.devices();
+		assert_eq!(devices.len(), 1);
+		assert_eq!(devices[0].ip_address, "192.168.1.42");
+		assert_eq!(collector.off_subnet_replies, 1);
+	}
+
+	#[test]
+	fn test_arp_cache_fills_gaps_only() {
+		let live_mac = MacAddr::new(0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x01);
+		let mut collector = ScanCollector::default();
+		collector.record_reply(Ipv4Addr::new(192, 168, 1, 20), live_mac, || Device::sample("192.168.1.20", &live_mac.to_string(), "eth0"));
+
+		// A cached entry never overrides or conflicts with a live reply
+		let found = collector.record_cached(Ipv4Addr::new(192, 168, 1, 20), || unreachable!());
+		assert!(found.is_none());
+
+		let found = collector.record_cached(Ipv4Addr::new(192, 168, 1, 30), || Device {
+			source: DeviceSource::ArpCache,
+			..Device::sample("192.168.1.30", "aa:aa:aa:aa:aa:02", "eth0")
+		});
+		assert!(found.is_some());
+
+		let devices = collector.devices();
+		assert_eq!(devices.len(), 2);
+		assert_eq!(devices[0].source, DeviceSource::LiveReply);
+		assert!(!devices[0].mac_conflict);
+		assert_eq!(devices[1].source, DeviceSource::ArpCache);
+	}
+
+	#[test]
+	fn test_merge_interface_results() {
+		let results = vec![
+			("eth0".to_string(), Ok(ScanSummary {
+				devices: vec![Device::sample("192.168.1.20", "aa:aa:aa:aa:aa:01", "eth0")],
+				..ScanSummary::default()
+			})),
+			("wlan0".to_string(), Ok(ScanSummary {
+				devices: vec![
+					Device::sample("10.0.0.7", "aa:aa:aa:aa:aa:02", "wlan0"),
+					Device::sample("10.0.0.3", "aa:aa:aa:aa:aa:03", "wlan0"),
+				],
+				..ScanSummary::default()
+			})),
+			("eth1".to_string(), Err(ScanError::ChannelCreationFailure)),
+		];
+
+		let merged = merge_interface_results(results);
+		let ips: Vec<&str> = merged.devices.iter().map(|d| d.ip_address.as_str()).collect();
+		assert_eq!(ips, vec!["10.0.0.3", "10.0.0.7", "192.168.1.20"]);
+		assert_eq!(merged.devices[2].interface_name, "eth0");
+		assert_eq!(merged.errors.len(), 1);
+		assert_eq!(merged.errors[0].interface_name, "eth1");
+	}
+
+	#[test]
+	fn test_device_answering_on_two_interfaces_is_merged() {
+		let on = |interface_name: &str, source: DeviceSource| Device {
+			source,
+			..Device::sample("192.168.1.20", "aa:aa:aa:aa:aa:01", interface_name)
+		};
+		let results = vec![
+			("eth0".to_string(), Ok(ScanSummary {
+				devices: vec![on("eth0", DeviceSource::LiveReply), Device::sample("192.168.1.30", "aa:aa:aa:aa:aa:02", "eth0")],
+				..ScanSummary::default()
+			})),
+			("eth1".to_string(), Ok(ScanSummary {
+				devices: vec![on("eth1", DeviceSource::LiveReply)],
+				..ScanSummary::default()
+			})),
+			("eth2".to_string(), Ok(ScanSummary {
+				devices: vec![on("eth2", DeviceSource::ArpCache)],
+				..ScanSummary::default()
+			})),
+		];
+
+		let merged = merge_interface_results(results);
+		assert_eq!(merged.devices.len(), 2);
+		assert_eq!(merged.devices[0].answered_on, vec!["eth0".to_string(), "eth1".to_string()]);
+		assert_eq!(merged.devices[0].interface_name, "eth0");
+		assert_eq!(merged.devices[1].answered_on, vec!["eth0".to_string()]);
+	}
+
+	fn arp_frame(ethertype: [u8; 2], operation: u8) -> Vec<u8> {
+		let mut frame = Vec::new();
+		frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01]); // destination (us)
+		frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x12, 0x34, 0x56]); // source
+		frame.extend_from_slice(&ethertype);
+		frame.extend_from_slice(&[0x00, 0x01, 0x08, 0x00, 0x06, 0x04, 0x00, operation]);
+		frame.extend_from_slice(&[0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 192, 168, 1, 42]); // sender
+		frame.extend_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 192, 168, 1, 10]); // target
+		frame
+	}
+
+	#[test]
+	fn test_passively_observed_reply() {
+		let us = MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01);
+		let (ip, mac, source) = classify_arp_reply(&arp_frame([0x08, 0x06], 2), us).unwrap();
+		assert_eq!(ip, Ipv4Addr::new(192, 168, 1, 42));
+		assert_eq!(mac, MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56));
+		assert_eq!(source, DeviceSource::LiveReply);
+
+		// The same reply, but answering some other host's request
+		let mut overheard = arp_frame([0x08, 0x06], 2);
+		overheard[0..6].copy_from_slice(&[0x00, 0x00, 0x00, 0xab, 0xcd, 0xef]);
+		overheard[32..38].copy_from_slice(&[0x00, 0x00, 0x00, 0xab, 0xcd, 0xef]);
+		let (ip, _, source) = classify_arp_reply(&overheard, us).unwrap();
+		assert_eq!(ip, Ipv4Addr::new(192, 168, 1, 42));
+		assert_eq!(source, DeviceSource::PassiveObservation);
+
+		// Answering us later makes it a live reply
+		let mut collector = ScanCollector::default();
+		collector.record_reply(ip, mac, || Device { source, ..Device::sample("192.168.1.42", &mac.to_string(), "eth0") });
+		collector.confirm_live(None, ip, mac);
+		assert_eq!(collector.devices()[0].source, DeviceSource::LiveReply);
+	}
+
+	#[test]
+	fn test_parse_arp_reply() {
+		let reply = arp_frame([0x08, 0x06], 2);
+		assert_eq!(reply.len(), 42);
+		assert_eq!(
+			parse_arp_reply(&reply),
+			Some((Ipv4Addr::new(192, 168, 1, 42), MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56)))
+		);
+
+		// Requests, non-ARP frames and truncated frames are ignored
+		assert_eq!(parse_arp_reply(&arp_frame([0x08, 0x06], 1)), None);
+		assert_eq!(parse_arp_reply(&arp_frame([0x08, 0x00], 2)), None);
+		assert_eq!(parse_arp_reply(&reply[..20]), None);
+	}
+
+	#[test]
+	fn test_vlan_tagged_arp_reply() {
+		// An 802.1Q tag for VLAN 10 between the source MAC and the ARP ethertype
+		let mut tagged = arp_frame([0x08, 0x06], 2);
+		tagged.splice(12..12, [0x81, 0x00, 0x00, 0x0a]);
+		assert_eq!(
+			parse_arp_reply(&tagged),
+			Some((Ipv4Addr::new(192, 168, 1, 42), MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56)))
+		);
+		assert_eq!(frame_vlan_id(&tagged), Some(10));
+		assert_eq!(frame_vlan_id(&arp_frame([0x08, 0x06], 2)), None);
+
+		let reply = read_reply(&tagged, false, MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01)).unwrap();
+		assert_eq!(reply.vlan_id, Some(10));
+
+		// Tagged replies come from another VLAN's subnet and aren't dropped as off-subnet
+		let mut collector = ScanCollector::default();
+		let network: Ipv4Network = "10.0.0.0/24".parse().unwrap();
+		let found = collector.record_parsed_reply(network, reply, || Device { vlan_id: reply.vlan_id, ..Device::sample("192.168.1.42", "00:00:00:12:34:56", "eth0") });
+		assert_eq!(found.unwrap().vlan_id, Some(10));
+		let untagged = ArpReply { vlan_id: None, ip: Ipv4Addr::new(192, 168, 1, 43), ..reply };
+		assert!(collector.record_parsed_reply(network, untagged, || unreachable!()).is_none());
+	}
+
+	#[test]
+	fn test_same_ip_on_two_vlans_is_two_devices() {
+		let ip = Ipv4Addr::new(192, 168, 1, 1);
+		let (gateway_10, gateway_20) = (MacAddr::new(0, 0, 0, 0, 0, 0x10), MacAddr::new(0, 0, 0, 0, 0, 0x20));
+		let network: Ipv4Network = "192.168.1.0/24".parse().unwrap();
+		let reply = |mac: MacAddr, vlan_id: Option<u16>| ArpReply { ip, mac, source: DeviceSource::LiveReply, vlan_id };
+		let device = |reply: ArpReply| Device { vlan_id: reply.vlan_id, ..Device::sample("192.168.1.1", &reply.mac.to_string(), "eth0") };
+
+		let mut collector = ScanCollector::default();
+		for answer in [reply(gateway_10, None), reply(gateway_10, Some(10)), reply(gateway_20, Some(20))] {
+			assert!(collector.record_parsed_reply(network, answer, || device(answer)).is_some());
+		}
+		assert_eq!(collector.devices().len(), 3);
+		assert!(collector.ip_conflicts().is_empty());
+
+		// A second MAC for the address on VLAN 20 is a conflict there only
+		let spoofed = reply(MacAddr::new(0, 0, 0, 0, 0, 0x99), Some(20));
+		assert!(collector.record_parsed_reply(network, spoofed, || unreachable!()).is_none());
+		let conflicts = collector.ip_conflicts();
+		assert_eq!(conflicts.len(), 1);
+		assert_eq!(conflicts[0].vlan_id, Some(20));
+		assert_eq!(conflicts[0].mac_addresses, vec![gateway_20.to_string(), spoofed.mac.to_string()]);
+	}
+
+	#[test]
+	fn test_gratuitous_arp_frame() {
+		let mac = MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01);
+		let ip = Ipv4Addr::new(192, 168, 1, 77);
+		let frame = build_gratuitous_arp(mac, ip);
+
+		let ethernet = EthernetPacket::new(&frame).unwrap();
+		assert_eq!(ethernet.get_destination(), MacAddr::broadcast());
+		assert_eq!(ethernet.get_ethertype(), EtherTypes::Arp);
+
+		let arp = ArpPacket::new(ethernet.payload()).unwrap();
+		assert_eq!(arp.get_operation(), ArpOperations::Request);
+		assert_eq!(arp.get_sender_proto_addr(), ip);
+		assert_eq!(arp.get_target_proto_addr(), ip);
+		assert_eq!(arp.get_sender_hw_addr(), mac);
+		assert_eq!(arp.get_target_hw_addr(), MacAddr::zero());
+	}
+
+	#[test]
+	fn test_precomputed_arp_requests_match_built_ones() {
+		let mac = MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01);
+		let source = Ipv4Addr::new(10, 0, 0, 10);
+		let targets = scan_targets("10.0.0.0/22".parse().unwrap(), source, &ScanOptions::default());
+		assert_eq!(targets.len(), 1021);
+
+		let requests = build_arp_requests(mac, source, &targets);
+		assert_eq!(requests.len(), targets.len());
+		for (request, target) in requests.iter().zip(&targets) {
+			assert_eq!(request, &build_arp_request(mac, source, *target));
+			let arp = ArpPacket::new(&request[EthernetPacket::minimum_packet_size()..]).unwrap();
+			assert_eq!(arp.get_target_proto_addr(), *target);
+			assert_eq!(arp.get_sender_proto_addr(), source);
+		}
+	}
+
+	#[test]
+	fn test_build_device() {
+		let oui = OuiCache::new(Arc::new(OuiDb::new_embedded()));
+		let (ip, mac) = parse_arp_reply(&arp_frame([0x08, 0x06], 2)).unwrap();
+		let device = build_device(ip, mac, &oui);
+		assert_eq!(device.ip_address, "192.168.1.42");
+		assert_eq!(device.mac_address, "00:00:00:12:34:56");
+		assert_eq!(device.manufacturer, "Xerox Corporation");
+		assert!(!device.mac_conflict);
+	}
+
+	#[tokio::test]
+	async fn test_channel_opened_with_timeout_and_buffer() {
+		let interface = interface("eth0", 2, None, None, 0);
+		let opened = Mutex::new(Vec::new());
+		let factory = |iface: &NetworkInterface, config: datalink::Config| -> std::io::Result<Channel> {
+			opened.lock().unwrap().push((iface.name.clone(), config.promiscuous, config.read_timeout, config.read_buffer_size));
+			Err(std::io::Error::other("no channel in tests"))
+		};
+
+		let defaults = ScanOptions { channel_retries: Some(0), ..ScanOptions::default() };
+		assert!(open_scan_channel(&interface, &defaults, false, factory).await.is_err());
+		let tuned = ScanOptions { read_buffer_size: Some(4 * 1024 * 1024), ..defaults.clone() };
+		assert!(open_scan_channel(&interface, &tuned, true, factory).await.is_err());
+		// Sizes that would break reads or exhaust memory are clamped
+		for size in [0, usize::MAX] {
+			let extreme = ScanOptions { read_buffer_size: Some(size), ..defaults.clone() };
+			assert!(open_scan_channel(&interface, &extreme, false, factory).await.is_err());
+		}
+
+		assert_eq!(
+			*opened.lock().unwrap(),
+			vec![
+				("eth0".to_string(), false, Some(CHANNEL_READ_TIMEOUT), DEFAULT_READ_BUFFER_SIZE),
+				("eth0".to_string(), true, Some(CHANNEL_READ_TIMEOUT), 4 * 1024 * 1024),
+				("eth0".to_string(), false, Some(CHANNEL_READ_TIMEOUT), MIN_READ_BUFFER_SIZE),
+				("eth0".to_string(), false, Some(CHANNEL_READ_TIMEOUT), MAX_READ_BUFFER_SIZE),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_matching_reply_ends_listening_early() {
+		let printer = MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56);
+		let reply = |host: u8, mac: MacAddr| ArpReply { ip: Ipv4Addr::new(192, 168, 1, host), mac, source: DeviceSource::LiveReply, vlan_id: None };
+		let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+		reply_tx.send(reply(7, MacAddr::new(0x00, 0x00, 0x00, 0xaa, 0xaa, 0xaa))).unwrap();
+		reply_tx.send(reply(42, printer)).unwrap();
+		reply_tx.send(reply(99, MacAddr::new(0x00, 0x00, 0x00, 0xbb, 0xbb, 0xbb))).unwrap();
+
+		// The channel stays open, so only the match can end the wait before the window does
+		let mut seen = Vec::new();
+		let started = std::time::Instant::now();
+		let matched = listen_for_replies(&mut reply_rx, async {}, Duration::from_secs(5), Some(printer), |reply| seen.push(reply.ip)).await;
+		assert!(matched);
+		assert!(started.elapsed() < Duration::from_secs(1));
+		assert_eq!(seen, vec![Ipv4Addr::new(192, 168, 1, 7), Ipv4Addr::new(192, 168, 1, 42)]);
+
+		let quiet = Duration::from_millis(50);
+		assert!(!listen_for_replies(&mut reply_rx, async {}, quiet, Some(MacAddr::new(0x02, 0, 0, 0, 0, 9)), |_| {}).await);
+		drop(reply_tx);
+	}
+
+	#[tokio::test]
+	async fn test_matching_reply_stops_the_sweep() {
+		let printer = MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56);
+		let (reply_tx, mut reply_rx) = mpsc::unbounded_channel();
+		let mut sent = 0;
+
+		// The printer answers the tenth request of a hundred
+		let sending = async {
+			for host in 1..=100u8 {
+				sent += 1;
+				if host == 10 {
+					let reply = ArpReply { ip: Ipv4Addr::new(192, 168, 1, host), mac: printer, source: DeviceSource::LiveReply, vlan_id: None };
+					reply_tx.send(reply).unwrap();
 				}
-				Err(_) => break,
+				tokio::task::yield_now().await;
 			}
+		};
+		let matched = listen_for_replies(&mut reply_rx, sending, Duration::from_secs(5), Some(printer), |_| {}).await;
+		assert!(matched);
+		assert!(sent < 20, "sent {} requests after the match", sent);
+	}
+
+	#[tokio::test]
+	async fn test_find_by_mac_rejects_malformed_mac() {
+		assert!(matches!(find_by_mac("not-a-mac", ScanOptions::default()).await, Err(ScanError::InvalidMac(_))));
+		assert!(matches!(find_by_mac("00:11:22:33:44", ScanOptions::default()).await, Err(ScanError::InvalidMac(_))));
+	}
+
+	#[test]
+	fn test_own_device_shows_adapter_name() {
+		let oui = OuiCache::new(Arc::new(OuiDb::new_embedded()));
+		let ip = Ipv4Addr::new(192, 168, 1, 10);
+		let randomized = MacAddr::new(0x02, 0x00, 0x00, 0x00, 0x00, 0x01);
+		let named = build_own_device(ip, randomized, &oui, Some("Intel(R) Wi-Fi 6 AX201 160MHz".to_string()));
+		assert_eq!(named.manufacturer, "Intel(R) Wi-Fi 6 AX201 160MHz");
+
+		// No usable adapter name: the OUI lookup, as for any other device
+		assert_eq!(build_own_device(ip, randomized, &oui, None).manufacturer, "Unknown");
+		let xerox = MacAddr::new(0x00, 0x00, 0x00, 0x12, 0x34, 0x56);
+		assert_eq!(build_own_device(ip, xerox, &oui, Some("  ".to_string())).manufacturer, "Xerox Corporation");
+	}
+
+	#[test]
+	fn test_recorded_scan_replays_identically() {
+		let mut recording = ScanRecording {
+			interface_name: "eth0".to_string(),
+			network: "192.168.1.0/24".to_string(),
+			source_ip: "192.168.1.10".to_string(),
+			source_mac: "02:00:00:00:00:01".to_string(),
+			promiscuous: false,
+			hosts_probed: 253,
+			frames: Vec::new(),
+		};
+		for host in [42u8, 7, 200] {
+			let mut reply = arp_frame([0x08, 0x06], 2);
+			reply[27] = host;
+			reply[31] = host;
+			recording.push_frame(&reply);
 		}
-	});
+		// Not a reply, and a sender from another subnet: both dropped as in a live scan
+		recording.push_frame(&arp_frame([0x08, 0x06], 1));
+		let mut off_subnet = arp_frame([0x08, 0x06], 2);
+		off_subnet[30] = 9;
+		recording.push_frame(&off_subnet);
 
-	let _ = timeout(Duration::from_secs(5), receiver_task).await;
-	let final_devices = found_devices.lock().unwrap();
-	let mut devices: Vec<Device> = final_devices.values().cloned().collect();
-	devices.sort_by(|a, b| a.ip_address.parse::<Ipv4Addr>().unwrap().cmp(&b.ip_address.parse::<Ipv4Addr>().unwrap()));
-	Ok(devices)
+		let options = ScanOptions { exclude_ips: vec!["192.168.1.200".to_string()], ..ScanOptions::default() };
+		let reported = Mutex::new(Vec::new());
+		let first = replay_recording(&recording, &options, |device| reported.lock().unwrap().push(device.ip_address.clone())).unwrap();
+		let ips: Vec<_> = first.devices.iter().map(|device| device.ip_address.as_str()).collect();
+		assert_eq!(ips, vec!["192.168.1.7", "192.168.1.10", "192.168.1.42"]);
+		assert_eq!(*reported.lock().unwrap(), vec!["192.168.1.10", "192.168.1.42", "192.168.1.7"]);
+		assert_eq!(first.devices[0].manufacturer, "Xerox Corporation");
+		assert_eq!(first.hosts_probed, 253);
+		assert_eq!(first.subnet_hosts, 254);
+
+		let path = std::env::temp_dir().join(format!("cutecatnet-replay-{}.json", std::process::id()));
+		recording.save(&path).unwrap();
+		let second = perform_scan_from_recording(&path, &options, |_| {}).unwrap();
+		assert_eq!(serde_json::to_value(&first).unwrap(), serde_json::to_value(&second).unwrap());
+		let _ = std::fs::remove_file(&path);
+	}
 }
@@ -0,0 +1,137 @@
+// src-tauri/src/self_test.rs
+
+use serde::Serialize;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crate::icmp;
+use crate::oui_db::{OuiDb, OuiParseSummary};
+use crate::privileges::{self, RawSocketCapability};
+use crate::stresser::{Intensity, StressTestConfig, StressTestEngine, TestStatus, TestType};
+
+const LOOPBACK_PING_TIMEOUT: Duration = Duration::from_secs(1);
+/// Longest the one-second loopback test may take to wind down before it's declared stuck.
+const STRESS_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SelfTestCheck {
+	pub name: String,
+	pub passed: bool,
+	pub detail: String,
+}
+
+impl SelfTestCheck {
+	fn new(name: &str, passed: bool, detail: impl Into<String>) -> Self {
+		Self { name: name.to_string(), passed, detail: detail.into() }
+	}
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SelfTestReport {
+	pub passed: bool,
+	pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+	fn new(checks: Vec<SelfTestCheck>) -> Self {
+		Self { passed: checks.iter().all(|check| check.passed), checks }
+	}
+}
+
+/// Smoke test that never leaves the machine: pings loopback, checks the OUI database and
+/// raw socket access, and runs a one-second low-intensity stress test against 127.0.0.1.
+pub async fn run_self_test() -> SelfTestReport {
+	let ping = icmp::ping(Ipv4Addr::LOCALHOST, LOOPBACK_PING_TIMEOUT).await;
+	let checks = vec![
+		check_loopback_ping(ping),
+		check_oui_database(OuiDb::new_embedded().summary()),
+		check_raw_sockets(privileges::check_raw_socket_capability()),
+		check_stress_engine(StressTestEngine::new()).await,
+	];
+	SelfTestReport::new(checks)
+}
+
+fn check_loopback_ping(ping: Result<Option<f64>, String>) -> SelfTestCheck {
+	match ping {
+		Ok(Some(latency)) => SelfTestCheck::new("loopback_ping", true, format!("127.0.0.1 replied in {:.2} ms", latency)),
+		Ok(None) => SelfTestCheck::new("loopback_ping", false, "127.0.0.1 did not reply"),
+		Err(e) => SelfTestCheck::new("loopback_ping", false, format!("Could not ping 127.0.0.1: {}", e)),
+	}
+}
+
+fn check_oui_database(summary: &OuiParseSummary) -> SelfTestCheck {
+	let detail = format!("{} vendors loaded, {} lines skipped", summary.entries_loaded, summary.lines_skipped);
+	SelfTestCheck::new("oui_database", !summary.looks_malformed(), detail)
+}
+
+fn check_raw_sockets(capability: RawSocketCapability) -> SelfTestCheck {
+	let detail = capability.guidance.unwrap_or_else(|| "Raw sockets available".to_string());
+	SelfTestCheck::new("raw_sockets", capability.available, detail)
+}
+
+/// Runs the minimal test on `engine`, which is let through to loopback for this run only.
+async fn check_stress_engine(engine: StressTestEngine) -> SelfTestCheck {
+	let mut engine = engine.allowing_loopback_target();
+	let config = StressTestConfig {
+		target_ip: Ipv4Addr::LOCALHOST.to_string(),
+		test_type: TestType::LatencyTest,
+		intensity: Intensity::Low,
+		duration_seconds: 1,
+		skip_reachability_check: true,
+		label: Some("self-test".to_string()),
+		warmup_packets: Some(0),
+		..Default::default()
+	};
+
+	if let Err(e) = engine.start_stress_test(config).await {
+		return SelfTestCheck::new("stress_engine", false, format!("Could not start: {}", e));
+	}
+	if !engine.wait_for_completion(STRESS_CHECK_TIMEOUT).await {
+		let _ = engine.stop_current_test().await;
+		return SelfTestCheck::new("stress_engine", false, "Test did not finish in time");
+	}
+
+	match engine.get_current_test().await {
+		Some(test) if test.status == TestStatus::Completed => {
+			let metrics = test.final_metrics.unwrap_or_default();
+			let passed = metrics.packets_received > 0;
+			let detail = format!("{} of {} packets answered", metrics.packets_received, metrics.packets_sent);
+			SelfTestCheck::new("stress_engine", passed, detail)
+		}
+		Some(test) => SelfTestCheck::new(
+			"stress_engine",
+			false,
+			test.error_message.unwrap_or_else(|| format!("Ended as {:?}", test.status)),
+		),
+		None => SelfTestCheck::new("stress_engine", false, "No test was recorded"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_healthy_system_passes_every_check() {
+		let checks = vec![
+			check_loopback_ping(Ok(Some(0.05))),
+			check_oui_database(OuiDb::new_embedded().summary()),
+			check_raw_sockets(RawSocketCapability { available: true, guidance: None }),
+			check_stress_engine(StressTestEngine::new()).await,
+		];
+		let report = SelfTestReport::new(checks);
+
+		let names: Vec<&str> = report.checks.iter().map(|check| check.name.as_str()).collect();
+		assert_eq!(names, vec!["loopback_ping", "oui_database", "raw_sockets", "stress_engine"]);
+		assert!(report.passed, "{:?}", report.checks);
+	}
+
+	#[tokio::test]
+	async fn test_loopback_stays_refused_outside_self_test() {
+		let engine = StressTestEngine::new();
+		assert!(engine.validate_target_ip("127.0.0.1").await.is_err());
+
+		let report = SelfTestReport::new(vec![check_loopback_ping(Ok(None))]);
+		assert!(!report.passed);
+	}
+}
@@ -0,0 +1,72 @@
+// src-tauri/src/source_ip.rs
+
+use pnet::datalink::{self, NetworkInterface};
+use socket2::SockRef;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+/// The local interface that owns `ip`, if any. Multi-homed hosts use this to check a
+/// requested source address before binding to it.
+pub fn interface_for(ip: Ipv4Addr) -> Option<NetworkInterface> {
+	find_interface(&datalink::interfaces(), ip).cloned()
+}
+
+fn find_interface(interfaces: &[NetworkInterface], ip: Ipv4Addr) -> Option<&NetworkInterface> {
+	interfaces
+		.iter()
+		.find(|iface| iface.ips.iter().any(|network| network.ip() == IpAddr::V4(ip)))
+}
+
+/// Binds `socket` to `ip` so everything sent on it leaves from that address, whatever the
+/// routing table would have picked. The port is left to the OS.
+pub fn bind(socket: SockRef<'_>, ip: Ipv4Addr) -> io::Result<()> {
+	socket.bind(&SocketAddr::from((ip, 0)).into())
+}
+
+/// Same as `bind` for the raw sockets pnet opens, which only expose their descriptor.
+#[cfg(unix)]
+pub fn bind_raw(fd: std::os::fd::RawFd, ip: Ipv4Addr) -> io::Result<()> {
+	// Safety: the channel owning `fd` is alive for the duration of this call
+	let fd = unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) };
+	bind(SockRef::from(&fd), ip)
+}
+
+#[cfg(windows)]
+pub fn bind_raw(socket: std::os::windows::io::RawSocket, ip: Ipv4Addr) -> io::Result<()> {
+	// Safety: the channel owning `socket` is alive for the duration of this call
+	let socket = unsafe { std::os::windows::io::BorrowedSocket::borrow_raw(socket) };
+	bind(SockRef::from(&socket), ip)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use socket2::{Domain, Socket, Type};
+
+	#[test]
+	fn test_socket_is_bound_to_source() {
+		let socket = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+		bind(SockRef::from(&socket), Ipv4Addr::LOCALHOST).unwrap();
+
+		let local = socket.local_addr().unwrap().as_socket().unwrap();
+		assert_eq!(local.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+		assert_ne!(local.port(), 0);
+	}
+
+	#[test]
+	fn test_find_interface_by_address() {
+		let interface = |name: &str, ip: &str| NetworkInterface {
+			name: name.to_string(),
+			description: String::new(),
+			index: 0,
+			mac: None,
+			ips: vec![ip.parse::<ipnetwork::IpNetwork>().unwrap()],
+			flags: 0,
+		};
+		let interfaces = [interface("eth0", "192.168.1.5/24"), interface("wlan0", "10.0.0.7/24")];
+
+		assert_eq!(find_interface(&interfaces, "10.0.0.7".parse().unwrap()).unwrap().name, "wlan0");
+		// On the subnet isn't enough, the address itself must be ours
+		assert!(find_interface(&interfaces, "10.0.0.8".parse().unwrap()).is_none());
+	}
+}
@@ -0,0 +1,101 @@
+// src-tauri/src/sparkline.rs
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often a running test adds a point to its series. Once a second keeps a 300 second
+/// test to 300 points in the history file.
+pub const SERIES_INTERVAL: Duration = Duration::from_secs(1);
+/// Most points a sparkline may be asked for.
+pub const MAX_SPARKLINE_BUCKETS: usize = 500;
+
+/// The headline metrics at one point of a test.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct MetricSample {
+	/// Time since the test started
+	pub elapsed_ms: u64,
+	pub latency_ms: f64,
+	pub jitter_ms: f64,
+	pub packet_loss_percentage: f64,
+	pub throughput_mbps: f64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SparklineMetric {
+	Latency,
+	Jitter,
+	PacketLoss,
+	Throughput,
+}
+
+impl SparklineMetric {
+	pub fn value(self, sample: &MetricSample) -> f64 {
+		match self {
+			SparklineMetric::Latency => sample.latency_ms,
+			SparklineMetric::Jitter => sample.jitter_ms,
+			SparklineMetric::PacketLoss => sample.packet_loss_percentage,
+			SparklineMetric::Throughput => sample.throughput_mbps,
+		}
+	}
+}
+
+/// `metric` over `series`, squeezed into exactly `buckets` points, each the average of the
+/// samples that fall in it. A series shorter than `buckets` is stretched, each sample
+/// filling the buckets it spans. An empty series has no points at all.
+pub fn sparkline(series: &[MetricSample], metric: SparklineMetric, buckets: usize) -> Vec<f64> {
+	let values: Vec<f64> = series.iter().map(|sample| metric.value(sample)).collect();
+	downsample(&values, buckets)
+}
+
+fn downsample(values: &[f64], buckets: usize) -> Vec<f64> {
+	if values.is_empty() {
+		return Vec::new();
+	}
+	let len = values.len();
+	(0..buckets)
+		.map(|bucket| {
+			let start = bucket * len / buckets;
+			let end = ((bucket + 1) * len / buckets).max(start + 1);
+			let slice = &values[start..end];
+			slice.iter().sum::<f64>() / slice.len() as f64
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn series(latencies: &[f64]) -> Vec<MetricSample> {
+		latencies
+			.iter()
+			.enumerate()
+			.map(|(i, latency)| MetricSample {
+				elapsed_ms: i as u64 * 1000,
+				latency_ms: *latency,
+				jitter_ms: 0.0,
+				packet_loss_percentage: 0.0,
+				throughput_mbps: 0.0,
+			})
+			.collect()
+	}
+
+	#[test]
+	fn test_series_is_bucketed() {
+		let long = series(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+		assert_eq!(sparkline(&long, SparklineMetric::Latency, 4), vec![1.5, 3.5, 5.5, 7.5]);
+
+		// Uneven splits still give exactly the requested number of points
+		let uneven = series(&[3.0, 3.0, 3.0, 6.0, 6.0, 6.0, 9.0, 9.0, 9.0, 9.0]);
+		assert_eq!(sparkline(&uneven, SparklineMetric::Latency, 3), vec![3.0, 6.0, 9.0]);
+	}
+
+	#[test]
+	fn test_short_series_is_stretched() {
+		let short = series(&[10.0, 20.0]);
+		assert_eq!(sparkline(&short, SparklineMetric::Latency, 4), vec![10.0, 10.0, 20.0, 20.0]);
+		assert_eq!(sparkline(&short, SparklineMetric::Jitter, 3), vec![0.0, 0.0, 0.0]);
+		assert!(sparkline(&[], SparklineMetric::Latency, 4).is_empty());
+	}
+}
@@ -0,0 +1,122 @@
+// src-tauri/src/status.rs
+
+use pnet::datalink::{self, Channel};
+use serde::Serialize;
+use std::io;
+
+use crate::oui_db::{OuiDb, OuiParseSummary};
+use crate::scanner;
+use crate::stresser::TestStatus;
+
+/// One-call readiness report. Problems are reported as fields, never as errors.
+#[derive(Serialize, Clone, Debug)]
+pub struct SystemStatus {
+	pub raw_sockets_available: bool,
+	pub sufficient_privileges: bool,
+	pub default_interface: Option<String>,
+	pub engine_status: TestStatus,
+	pub oui_entries: usize,
+	pub problems: Vec<String>,
+}
+
+pub fn collect_system_status(engine_status: TestStatus) -> SystemStatus {
+	let oui = OuiDb::new_embedded();
+	let interface = scanner::default_interface();
+	let channel = match &interface {
+		Ok(iface) => match datalink::channel(iface, Default::default()) {
+			Ok(Channel::Ethernet(_, _)) => Ok(()),
+			Ok(_) => Err(io::Error::other("Unsupported datalink channel type")),
+			Err(e) => Err(e),
+		},
+		Err(_) => Err(io::Error::other("No interface to open a channel on")),
+	};
+
+	build_status(
+		interface.map(|iface| iface.name).map_err(|e| e.to_string()),
+		channel,
+		engine_status,
+		oui.summary(),
+	)
+}
+
+fn build_status(
+	interface: Result<String, String>,
+	channel: Result<(), io::Error>,
+	engine_status: TestStatus,
+	oui: &OuiParseSummary,
+) -> SystemStatus {
+	let mut problems = Vec::new();
+
+	let default_interface = match interface {
+		Ok(name) => Some(name),
+		Err(e) => {
+			problems.push(e);
+			None
+		}
+	};
+
+	let (raw_sockets_available, sufficient_privileges) = match channel {
+		Ok(()) => (true, true),
+		Err(e) => {
+			let permission_denied = e.kind() == io::ErrorKind::PermissionDenied;
+			problems.push(format!("Cannot open a raw datalink channel: {}", e));
+			(false, !permission_denied)
+		}
+	};
+
+	if oui.entries_loaded == 0 {
+		problems.push("OUI database is empty; manufacturers will show as Unknown".to_string());
+	} else if oui.looks_malformed() {
+		problems.push(format!(
+			"OUI database looks malformed: {} of {} lines skipped",
+			oui.lines_skipped, oui.lines_processed
+		));
+	}
+
+	SystemStatus {
+		raw_sockets_available,
+		sufficient_privileges,
+		default_interface,
+		engine_status,
+		oui_entries: oui.entries_loaded,
+		problems,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn oui(entries_loaded: usize, lines_skipped: usize) -> OuiParseSummary {
+		OuiParseSummary { lines_processed: entries_loaded + lines_skipped, entries_loaded, lines_skipped }
+	}
+
+	#[test]
+	fn test_healthy_status_is_fully_populated() {
+		let status = build_status(Ok("eth0".to_string()), Ok(()), TestStatus::Idle, &oui(30_000, 0));
+
+		assert!(status.raw_sockets_available);
+		assert!(status.sufficient_privileges);
+		assert_eq!(status.default_interface.as_deref(), Some("eth0"));
+		assert_eq!(status.engine_status, TestStatus::Idle);
+		assert_eq!(status.oui_entries, 30_000);
+		assert!(status.problems.is_empty());
+	}
+
+	#[test]
+	fn test_permission_problems_are_reported() {
+		let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+		let status = build_status(Ok("eth0".to_string()), Err(denied), TestStatus::Running, &oui(30_000, 0));
+
+		assert!(!status.raw_sockets_available);
+		assert!(!status.sufficient_privileges);
+		assert_eq!(status.problems.len(), 1);
+	}
+
+	#[test]
+	fn test_malformed_oui_database_is_reported() {
+		let status = build_status(Ok("eth0".to_string()), Ok(()), TestStatus::Idle, &oui(2, 40));
+		assert_eq!(status.oui_entries, 2);
+		assert_eq!(status.problems, vec!["OUI database looks malformed: 40 of 42 lines skipped".to_string()]);
+	}
+}
@@ -1,12 +1,32 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::net::Ipv4Addr;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::{watch, RwLock};
-use tokio::time::{interval, timeout};
+use tokio::sync::{mpsc, watch, RwLock, Semaphore};
+use tokio::time::{interval, sleep, timeout};
 use thiserror::Error;
 use log::{debug, info, warn, error};
+use pnet::datalink::NetworkInterface;
+
+use crate::alerts::{AlertSample, AlertSink, AlertThresholds, AlertTracker, SustainedAbort, SustainedTracker};
+use crate::compare::{self, TestComparison};
+use crate::audit::{AuditEntry, AuditEvent, AuditLog};
+use crate::baseline::BaselineStore;
+use crate::history::{HistoryBackend, HistoryFilter, HistoryStore, DEFAULT_HISTORY_LIMIT};
+use crate::owd;
+use crate::privileges;
+use crate::qos;
+use crate::quality::{GradeCutoffs, QualityGrade, QualityThresholds};
+use crate::rate_limit::SharedRateLimiter;
+use crate::scan_gate::ScanGate;
+use crate::scanner;
+use crate::sparkline::{MetricSample, SERIES_INTERVAL};
+use crate::source_ip;
+use crate::syn;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum TestType {
@@ -14,6 +34,12 @@ pub enum TestType {
     BandwidthTest,
     LatencyTest,
     PacketLoss,
+    /// Raw TCP SYNs to `target_port`, counting SYN-ACKs as received. Disabled unless
+    /// listed in `SafetyLimits::allowed_test_types`.
+    SynFlood,
+    /// Latency while idle, then while the rate ramps up to the intensity's, reporting how
+    /// much the load adds as `TestMetrics::bufferbloat_ms`
+    Bufferbloat,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -38,10 +64,44 @@ pub enum TestStatus {
     Idle,
     Running,
     Paused,
+    /// Ran for its full duration, or until a byte cap ended it early
     Completed,
+    /// Stopped by the user before its duration was up
+    Cancelled,
     Failed,
 }
 
+/// What ended a test. `status` says whether it went well; this says which guard or limit
+/// stopped it, so the UI and history can tell a circuit breaker from a dead man's switch.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TerminationReason {
+    /// Ran for its configured duration
+    DurationReached,
+    /// Stopped cleanly before the next packet would exceed `max_total_bytes`
+    ByteCapReached,
+    UserCancelled,
+    DeadMansSwitch,
+    CircuitBreaker,
+    /// Loss or latency stayed over the configured `sustained_abort` limits
+    SustainedUnhealthy,
+    ResourceLimit,
+    /// Any other failure; `error_message` has the details
+    Error,
+}
+
+impl TerminationReason {
+    pub fn from_error(error: &StressError) -> Self {
+        match error {
+            StressError::UserCancelled => Self::UserCancelled,
+            StressError::DeadMansSwitchTriggered => Self::DeadMansSwitch,
+            StressError::CircuitBreakerTriggered => Self::CircuitBreaker,
+            StressError::SustainedUnhealthy(_) => Self::SustainedUnhealthy,
+            StressError::ResourceLimitExceeded(_) => Self::ResourceLimit,
+            _ => Self::Error,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TestMetrics {
     pub latency_ms: f64,
@@ -51,6 +111,69 @@ pub struct TestMetrics {
     pub packets_sent: u32,
     pub packets_received: u32,
     pub timestamp: u64,
+    #[serde(default)]
+    pub latency_p95_ms: f64,
+    /// Bytes put on the wire so far, IP headers included
+    #[serde(default)]
+    pub bytes_sent: u64,
+    #[serde(default)]
+    pub current_rate_pps: u32,
+    #[serde(default)]
+    pub in_flight: u32,
+    #[serde(default)]
+    pub per_target: Vec<TargetMetrics>,
+    /// De-jitter buffer depth that would keep late discards under 1% (latency/ping tests only)
+    #[serde(default)]
+    pub required_jitter_buffer_ms: f64,
+    /// Packets sent per second of wall time, idle burst phases included
+    #[serde(default)]
+    pub average_rate_pps: f64,
+    /// Replies came back with more than one TTL: shifting or asymmetric routes, or a load balancer
+    #[serde(default)]
+    pub ttl_varied: bool,
+    /// Every reply TTL seen so far, ascending. Empty when the probe can't see TTLs.
+    #[serde(default)]
+    pub distinct_ttls: Vec<u8>,
+    /// Replies that arrived after their reply window; their pings still count as lost
+    #[serde(default)]
+    pub late_replies: u32,
+    /// Pings sent to warm up the path, left out of every other figure
+    #[serde(default)]
+    pub warmup_packets: u32,
+    /// Replies carrying the ECN Congestion Experienced mark
+    #[serde(default)]
+    pub ecn_marked_count: u32,
+    /// Latency, jitter and loss boiled down to one grade; `None` until a packet is sent
+    #[serde(default)]
+    pub quality_grade: Option<QualityGrade>,
+    /// Extra replies to a ping that was already answered, as some Wi-Fi setups produce.
+    /// Not counted in `packets_received`.
+    #[serde(default)]
+    pub duplicate_replies: u32,
+    /// Average one-way (outbound) delay, only when probing a timestamping reflector
+    #[serde(default)]
+    pub owd_estimate_ms: Option<f64>,
+    /// Times the simulated playout buffer ran dry, a run of late packets counting once.
+    /// Zero unless `playout_buffer_ms` is set.
+    #[serde(default)]
+    pub playout_underruns: u32,
+    /// Replies the simulated playout buffer would have thrown away for arriving too late
+    #[serde(default)]
+    pub playout_discards: u32,
+    /// Bufferbloat tests: median latency at full load less the idle median
+    #[serde(default)]
+    pub bufferbloat_ms: Option<f64>,
+    #[serde(default)]
+    pub bufferbloat_grade: Option<QualityGrade>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TargetMetrics {
+    pub target_ip: String,
+    pub packets_sent: u32,
+    pub packets_received: u32,
+    pub packet_loss_percentage: f64,
+    pub latency_ms: f64,
 }
 
 impl Default for TestMetrics {
@@ -66,6 +189,25 @@ impl Default for TestMetrics {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64,
+            latency_p95_ms: 0.0,
+            bytes_sent: 0,
+            current_rate_pps: 0,
+            in_flight: 0,
+            per_target: Vec::new(),
+            required_jitter_buffer_ms: 0.0,
+            average_rate_pps: 0.0,
+            ttl_varied: false,
+            distinct_ttls: Vec::new(),
+            late_replies: 0,
+            warmup_packets: 0,
+            ecn_marked_count: 0,
+            quality_grade: None,
+            duplicate_replies: 0,
+            owd_estimate_ms: None,
+            playout_underruns: 0,
+            playout_discards: 0,
+            bufferbloat_ms: None,
+            bufferbloat_grade: None,
         }
     }
 }
@@ -74,6 +216,9 @@ impl Default for TestMetrics {
 pub struct TestResult {
     pub test_id: String,
     pub target_ip: String,
+    /// The name the test was started against, when `target_ip` was resolved from one
+    #[serde(default)]
+    pub target_hostname: Option<String>,
     pub test_type: TestType,
     pub intensity: Intensity,
     pub duration_seconds: u32,
@@ -82,41 +227,488 @@ pub struct TestResult {
     pub final_metrics: Option<TestMetrics>,
     pub status: TestStatus,
     pub error_message: Option<String>,
+    #[serde(default)]
+    pub source_interface: Option<String>,
+    #[serde(default)]
+    pub source_ip: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Why a test ended early without failing, e.g. a byte cap being reached
+    #[serde(default)]
+    pub stop_reason: Option<String>,
+    /// Which limit or guard ended the test; `None` while it runs and on older results
+    #[serde(default)]
+    pub termination_reason: Option<TerminationReason>,
+    /// DSCP value the test's packets were marked with
+    #[serde(default)]
+    pub dscp: Option<u8>,
+    /// Whether packets actually went out ECN-capable; false when requested but unsupported
+    #[serde(default)]
+    pub ecn_capable: bool,
+    /// Everything the test was started with, so it can be replayed exactly. Missing from
+    /// results recorded before configs were kept.
+    #[serde(default)]
+    pub config: Option<StressTestConfig>,
+    /// The test this one is a replay of
+    #[serde(default)]
+    pub replay_of: Option<String>,
+    /// Headline metrics about once a second, for charting the run afterwards
+    #[serde(default)]
+    pub metric_series: Vec<MetricSample>,
+    /// A network scan ran at some point during the test; its ARP traffic may have skewed the
+    /// measurements
+    #[serde(default)]
+    pub concurrent_scan_during_test: bool,
+    /// How this test did against its target's baseline run. `None` for the baseline itself,
+    /// for unsuccessful tests and for engines without a data dir.
+    #[serde(default)]
+    pub vs_baseline: Option<TestComparison>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl TestResult {
+    /// The config to run this test again with, marked as a replay of it. Results recorded
+    /// without their config are rebuilt from the fields they do keep.
+    pub fn replay_config(&self) -> StressTestConfig {
+        let mut config = self.config.clone().unwrap_or_else(|| StressTestConfig {
+            target_ip: self.target_hostname.clone().unwrap_or_else(|| self.target_ip.clone()),
+            test_type: self.test_type.clone(),
+            intensity: self.intensity.clone(),
+            duration_seconds: self.duration_seconds,
+            label: self.label.clone(),
+            notes: self.notes.clone(),
+            dscp: self.dscp,
+            ecn: self.ecn_capable,
+            ..Default::default()
+        });
+        config.replay_of = Some(self.test_id.clone());
+        config
+    }
+}
+
+#[cfg(test)]
+impl TestResult {
+    pub(crate) fn sample(test_id: &str, target_ip: &str) -> Self {
+        Self {
+            test_id: test_id.to_string(),
+            target_ip: target_ip.to_string(),
+            target_hostname: None,
+            test_type: TestType::LatencyTest,
+            intensity: Intensity::Low,
+            duration_seconds: 1,
+            start_time: 0,
+            end_time: None,
+            final_metrics: None,
+            status: TestStatus::Running,
+            error_message: None,
+            source_interface: None,
+            source_ip: None,
+            label: None,
+            notes: None,
+            stop_reason: None,
+            termination_reason: None,
+            dscp: None,
+            ecn_capable: false,
+            config: None,
+            replay_of: None,
+            metric_series: Vec::new(),
+            concurrent_scan_during_test: false,
+            vs_baseline: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct StressTestConfig {
     pub target_ip: String,
     pub test_type: TestType,
     pub intensity: Intensity,
     pub duration_seconds: u32,
+    /// Back off the send rate on loss instead of holding the intensity fixed
+    #[serde(default)]
+    pub adaptive: bool,
+    /// Start even if the target doesn't answer the reachability precheck
+    #[serde(default)]
+    pub skip_reachability_check: bool,
+    /// Echo payload size in bytes, defaults to the classic 56 bytes of `ping`
+    #[serde(default)]
+    pub payload_size: Option<u32>,
+    /// Reject payloads that would need fragmenting instead of warning about them
+    #[serde(default)]
+    pub dont_fragment: bool,
+    /// Multi-target mode: packets are shared between these targets by weight.
+    /// When empty, the test runs against `target_ip` alone.
+    #[serde(default)]
+    pub targets: Vec<WeightedTarget>,
+    /// TCP port SYN flood tests are aimed at, defaults to 80
+    #[serde(default)]
+    pub target_port: Option<u16>,
+    /// Explicit acknowledgement required before running sensitive test types
+    #[serde(default)]
+    pub confirmed_sensitive_target: bool,
+    /// Free-form tag for comparing runs, e.g. "before firmware update"
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// DSCP code point (0-63) to mark outgoing packets with, for checking QoS policies
+    #[serde(default)]
+    pub dscp: Option<u8>,
+    /// Burst pattern: send at the full rate for `burst_on_ms`, then idle for `burst_off_ms`.
+    /// Both must be set for the pattern to apply.
+    #[serde(default)]
+    pub burst_on_ms: Option<u32>,
+    #[serde(default)]
+    pub burst_off_ms: Option<u32>,
+    /// How long a ping may stay outstanding before it's declared lost, defaults to 1000 ms
+    #[serde(default)]
+    pub reply_window_ms: Option<u32>,
+    /// Leading pings excluded from the metrics, so ARP resolution and cold caches don't
+    /// skew the results. Defaults to 3.
+    #[serde(default)]
+    pub warmup_packets: Option<u32>,
+    /// Send packets ECN-capable and count replies marked Congestion Experienced
+    #[serde(default)]
+    pub ecn: bool,
+    /// Cutoffs for `TestMetrics::quality_grade`, defaults to typical home-network expectations
+    #[serde(default)]
+    pub quality_thresholds: Option<QualityThresholds>,
+    /// Raise alert events while the test runs when these are crossed
+    #[serde(default)]
+    pub alert_thresholds: Option<AlertThresholds>,
+    /// Fail the test when loss or latency stays over these limits for a sustained window
+    #[serde(default)]
+    pub sustained_abort: Option<SustainedAbort>,
+    /// Local address to send from on multi-homed hosts, instead of the default interface's
+    #[serde(default)]
+    pub source_ip: Option<String>,
+    /// Probe with timestamped UDP datagrams to a one-way delay reflector (`owd::run_reflector`)
+    /// listening on this port of the target, reporting `owd_estimate_ms`. Echo tests only.
+    #[serde(default)]
+    pub owd_reflector_port: Option<u16>,
+    /// Simulate a fixed playout buffer this deep (ms) over the replies and report how often
+    /// it would run dry. Latency and ping tests only.
+    #[serde(default)]
+    pub playout_buffer_ms: Option<u32>,
+    /// Seconds between the confirmations this test needs, in place of the global dead man's
+    /// switch interval. Kept between `MIN_DEAD_MANS_SWITCH_SECONDS` and the global interval.
+    #[serde(default)]
+    pub dead_mans_switch_seconds: Option<u32>,
+    /// Set by `replay_test` to the test being reproduced
+    #[serde(default)]
+    pub replay_of: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WeightedTarget {
+    pub ip: String,
+    pub weight: u32,
+}
+
+/// The gentlest test: a short, low-rate latency run. Has no target, so it can't be started
+/// until one is set.
+impl Default for StressTestConfig {
+    fn default() -> Self {
+        Self {
+            target_ip: String::new(),
+            test_type: TestType::LatencyTest,
+            intensity: Intensity::Low,
+            duration_seconds: 10,
+            adaptive: false,
+            skip_reachability_check: false,
+            payload_size: None,
+            dont_fragment: false,
+            targets: Vec::new(),
+            target_port: None,
+            confirmed_sensitive_target: false,
+            label: None,
+            notes: None,
+            dscp: None,
+            burst_on_ms: None,
+            burst_off_ms: None,
+            reply_window_ms: None,
+            warmup_packets: None,
+            ecn: false,
+            quality_thresholds: None,
+            alert_thresholds: None,
+            sustained_abort: None,
+            source_ip: None,
+            owd_reflector_port: None,
+            playout_buffer_ms: None,
+            dead_mans_switch_seconds: None,
+            replay_of: None,
+        }
+    }
+}
+
+impl StressTestConfig {
+    pub fn payload_size(&self) -> u32 {
+        self.payload_size.unwrap_or(DEFAULT_PAYLOAD_SIZE)
+    }
+
+    pub fn target_port(&self) -> u16 {
+        self.target_port.unwrap_or(DEFAULT_TARGET_PORT)
+    }
+
+    pub fn warmup_packets(&self) -> u32 {
+        self.warmup_packets.unwrap_or(DEFAULT_WARMUP_PACKETS)
+    }
+
+    /// How long this test runs without a confirmation before the dead man's switch stops it:
+    /// its own `dead_mans_switch_seconds`, clamped to what `limits` allow, or the global interval.
+    pub fn dead_mans_switch_interval(&self, limits: &SafetyLimits) -> Duration {
+        let global = limits.dead_mans_switch_interval_seconds;
+        let seconds = self
+            .dead_mans_switch_seconds
+            .map_or(global, |requested| requested.clamp(MIN_DEAD_MANS_SWITCH_SECONDS.min(global), global));
+        Duration::from_secs(seconds.into())
+    }
+
+    pub fn quality_thresholds(&self) -> QualityThresholds {
+        self.quality_thresholds.clone().unwrap_or_default()
+    }
+
+    /// The requested source address, parsed and checked to belong to a local interface.
+    pub fn source_ip(&self) -> Result<Option<Ipv4Addr>, StressError> {
+        let Some(source) = &self.source_ip else {
+            return Ok(None);
+        };
+        match source.parse::<Ipv4Addr>() {
+            Ok(ip) if source_ip::interface_for(ip).is_some() => Ok(Some(ip)),
+            _ => Err(StressError::InvalidSourceIp(source.clone())),
+        }
+    }
+
+    pub fn reply_window(&self) -> Duration {
+        Duration::from_millis(self.reply_window_ms.unwrap_or(DEFAULT_REPLY_WINDOW_MS).max(1) as u64)
+    }
+
+    /// On-the-wire size of one probe, IP header included.
+    pub fn packet_bytes(&self) -> u64 {
+        match self.test_type {
+            TestType::SynFlood => SYN_PACKET_BYTES,
+            _ => (self.payload_size() + PACKET_HEADER_OVERHEAD) as u64,
+        }
+    }
+
+    /// (on, off) phase lengths when the test sends in bursts rather than a steady stream.
+    pub fn burst_pattern(&self) -> Option<(Duration, Duration)> {
+        match (self.burst_on_ms, self.burst_off_ms) {
+            (Some(on_ms), Some(off_ms)) if on_ms > 0 && off_ms > 0 => {
+                Some((Duration::from_millis(on_ms as u64), Duration::from_millis(off_ms as u64)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Packets the test would send if it ran its full duration: the intensity's rate over
+    /// the time actually spent sending, which for a burst pattern is only the on phases.
+    pub fn projected_packets(&self) -> u64 {
+        let duration_ms = u64::from(self.duration_seconds) * 1000;
+        let sending_ms = match self.burst_pattern() {
+            Some((on, off)) => {
+                let (on_ms, cycle_ms) = (on.as_millis() as u64, (on + off).as_millis() as u64);
+                duration_ms / cycle_ms * on_ms + (duration_ms % cycle_ms).min(on_ms)
+            }
+            None => duration_ms,
+        };
+        (u64::from(self.intensity.to_packets_per_second()) * sending_ms).div_ceil(1000)
+    }
+
+    /// Every target this test sends to, with its share of the packets.
+    pub fn weighted_targets(&self) -> Vec<WeightedTarget> {
+        if self.targets.is_empty() {
+            vec![WeightedTarget { ip: self.target_ip.clone(), weight: 1 }]
+        } else {
+            self.targets.clone()
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum StressError {
     #[error("Target IP is not in a private network range")]
     InvalidTargetIp,
+    #[error("Target {0} is an IPv6 address; only IPv4 targets are supported")]
+    Ipv6NotSupported(String),
+    #[error("Target {0} must have a weight greater than zero")]
+    InvalidTargetWeight(String),
     #[error("Test already running. Only one test allowed at a time")]
     TestAlreadyRunning,
-    #[error("Rate limit exceeded. Maximum 1000 pps allowed")]
-    RateLimitExceeded,
-    #[error("Test duration too long. Maximum 300 seconds allowed")]
-    DurationTooLong,
+    #[error("Rate limit exceeded. Maximum {max} pps allowed")]
+    RateLimitExceeded { requested: u32, max: u32 },
+    #[error("Test duration too long. Maximum {max} seconds allowed")]
+    DurationTooLong { requested: u32, max: u32 },
+    #[error("Test would send about {projected} packets, over the budget of {max}")]
+    PacketBudgetExceeded { projected: u64, max: u64 },
     #[error("Cooldown period active. Wait {0} seconds before testing this target again")]
     CooldownActive(u64),
     #[error("System resource limit exceeded: {0}")]
     ResourceLimitExceeded(String),
     #[error("Network error: {0}")]
     NetworkError(String),
+    #[error("Target {0} did not respond to the reachability check")]
+    TargetUnreachable(String),
+    #[error("Payload of {payload} bytes exceeds the {max} bytes that fit in the interface MTU without fragmenting")]
+    PayloadExceedsMtu { payload: u32, max: u32 },
+    #[error("Payload of {0} bytes is larger than an IPv4 packet can carry")]
+    PayloadTooLarge(u32),
     #[error("Dead man's switch triggered - UI confirmation required")]
     DeadMansSwitchTriggered,
+    #[error("Circuit breaker triggered - packet loss too high to continue")]
+    CircuitBreakerTriggered,
+    #[error("Target stayed unhealthy: {0}")]
+    SustainedUnhealthy(String),
     #[error("Test cancelled by user")]
     UserCancelled,
+    #[error("No previous test configuration to re-run")]
+    NoPreviousTest,
+    #[error("Test type {0} is not enabled in the safety limits")]
+    TestTypeNotAllowed(String),
+    #[error("This test type requires confirming the target is yours to test")]
+    ConfirmationRequired,
+    #[error("No test {0} in the history")]
+    TestNotFound(String),
+    #[error("Insufficient privileges: {0}")]
+    InsufficientPrivileges(String),
+    #[error("DSCP value {0} is out of range, it must be between 0 and 63")]
+    InvalidDscp(u8),
+    #[error("Source IP {0} is not an address of this host")]
+    InvalidSourceIp(String),
+    #[error("Could not resolve {host}: {reason}")]
+    HostnameUnresolved { host: String, reason: String },
     #[error("Internal error: {0}")]
     InternalError(String),
 }
 
-#[derive(Clone)]
+impl StressError {
+    /// Stable machine-readable code, independent of the message wording.
+    pub fn code(&self) -> &'static str {
+        match self {
+            StressError::InvalidTargetIp => "INVALID_TARGET_IP",
+            StressError::Ipv6NotSupported(_) => "IPV6_NOT_SUPPORTED",
+            StressError::InvalidTargetWeight(_) => "INVALID_TARGET_WEIGHT",
+            StressError::TestAlreadyRunning => "TEST_ALREADY_RUNNING",
+            StressError::RateLimitExceeded { .. } => "RATE_LIMIT",
+            StressError::DurationTooLong { .. } => "DURATION_TOO_LONG",
+            StressError::PacketBudgetExceeded { .. } => "PACKET_BUDGET_EXCEEDED",
+            StressError::CooldownActive(_) => "COOLDOWN_ACTIVE",
+            StressError::ResourceLimitExceeded(_) => "RESOURCE_LIMIT",
+            StressError::NetworkError(_) => "NETWORK_ERROR",
+            StressError::TargetUnreachable(_) => "TARGET_UNREACHABLE",
+            StressError::PayloadExceedsMtu { .. } => "PAYLOAD_EXCEEDS_MTU",
+            StressError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            StressError::DeadMansSwitchTriggered => "DEAD_MANS_SWITCH",
+            StressError::CircuitBreakerTriggered => "CIRCUIT_BREAKER",
+            StressError::SustainedUnhealthy(_) => "SUSTAINED_UNHEALTHY",
+            StressError::UserCancelled => "USER_CANCELLED",
+            StressError::NoPreviousTest => "NO_PREVIOUS_TEST",
+            StressError::TestTypeNotAllowed(_) => "TEST_TYPE_NOT_ALLOWED",
+            StressError::ConfirmationRequired => "CONFIRMATION_REQUIRED",
+            StressError::TestNotFound(_) => "TEST_NOT_FOUND",
+            StressError::InsufficientPrivileges(_) => "INSUFFICIENT_PRIVILEGES",
+            StressError::InvalidDscp(_) => "INVALID_DSCP",
+            StressError::InvalidSourceIp(_) => "INVALID_SOURCE_IP",
+            StressError::HostnameUnresolved { .. } => "HOSTNAME_UNRESOLVED",
+            StressError::InternalError(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// Variant-specific data the UI can act on (e.g. the cooldown countdown).
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            StressError::CooldownActive(remaining) => {
+                Some(serde_json::json!({ "remaining_seconds": remaining }))
+            }
+            StressError::TargetUnreachable(target)
+            | StressError::InvalidTargetWeight(target)
+            | StressError::Ipv6NotSupported(target) => {
+                Some(serde_json::json!({ "target_ip": target }))
+            }
+            StressError::PayloadExceedsMtu { payload, max } => {
+                Some(serde_json::json!({ "payload": payload, "max_payload": max }))
+            }
+            StressError::PayloadTooLarge(payload) => {
+                Some(serde_json::json!({ "payload": payload }))
+            }
+            StressError::PacketBudgetExceeded { projected, max } => {
+                Some(serde_json::json!({ "projected_packets": projected, "max_total_packets": max }))
+            }
+            StressError::RateLimitExceeded { requested, max } => {
+                Some(serde_json::json!({ "packets_per_second": requested, "max_packets_per_second": max }))
+            }
+            StressError::DurationTooLong { requested, max } => {
+                Some(serde_json::json!({ "duration_seconds": requested, "max_duration_seconds": max }))
+            }
+            StressError::TestTypeNotAllowed(test_type) => {
+                Some(serde_json::json!({ "test_type": test_type }))
+            }
+            StressError::TestNotFound(test_id) => {
+                Some(serde_json::json!({ "test_id": test_id }))
+            }
+            StressError::InsufficientPrivileges(guidance) => {
+                Some(serde_json::json!({ "guidance": guidance }))
+            }
+            StressError::InvalidDscp(dscp) => {
+                Some(serde_json::json!({ "dscp": dscp }))
+            }
+            StressError::InvalidSourceIp(source) => {
+                Some(serde_json::json!({ "source_ip": source }))
+            }
+            StressError::HostnameUnresolved { host, reason } => {
+                Some(serde_json::json!({ "hostname": host, "reason": reason }))
+            }
+            StressError::ResourceLimitExceeded(reason)
+            | StressError::SustainedUnhealthy(reason)
+            | StressError::NetworkError(reason)
+            | StressError::InternalError(reason) => {
+                Some(serde_json::json!({ "reason": reason }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Error shape returned by the stress test commands.
+#[derive(Serialize, Clone, Debug)]
+pub struct ErrorResponse {
+    pub code: String,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl From<StressError> for ErrorResponse {
+    fn from(error: StressError) -> Self {
+        Self {
+            code: error.code().to_string(),
+            message: error.to_string(),
+            details: error.details(),
+        }
+    }
+}
+
+/// A config that passed validation but comes close to a hard limit, for the UI to confirm
+/// before starting.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SafetyWarning {
+    NearRateLimit { packets_per_second: u32, max: u32 },
+    NearDurationLimit { seconds: u32, max: u32 },
+    NearPacketBudget { projected: u64, max: u64 },
+    /// A network scan is running and may skew the test's latency measurements
+    ConcurrentScan,
+}
+
+/// Whether one address of a bulk validation may be tested, and why not.
+#[derive(Serialize, Clone, Debug)]
+pub struct TargetValidation {
+    pub ip: String,
+    pub valid: bool,
+    pub error: Option<ErrorResponse>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
 pub struct SafetyLimits {
     pub max_packets_per_second: u32,
     pub max_duration_seconds: u32,
@@ -124,6 +716,19 @@ pub struct SafetyLimits {
     pub max_cpu_percent: f32,
     pub max_memory_percent: f32,
     pub dead_mans_switch_interval_seconds: u32,
+    pub max_in_flight_pings: u32,
+    /// Test types the engine will run. SYN floods are left out unless explicitly opted into.
+    pub allowed_test_types: Vec<TestType>,
+    /// Hard ceiling on bytes sent per test, for metered or shared links
+    pub max_total_bytes: Option<u64>,
+    /// Ceiling on the packets a test may plan to send (rate × duration), checked before it
+    /// starts, so limits that each pass on their own can't add up to more than intended
+    pub max_total_packets: Option<u64>,
+    /// Ignore dead man's switch confirmations that arrive faster than a person could give
+    /// them, so a runaway UI timer can't keep a test alive. They're logged either way.
+    pub reject_automated_confirmations: bool,
+    /// Fraction of a hard limit past which a config still validates, but with a warning
+    pub soft_limit_ratio: f64,
 }
 
 impl Default for SafetyLimits {
@@ -135,8 +740,349 @@ impl Default for SafetyLimits {
             max_cpu_percent: 80.0,
             max_memory_percent: 70.0,
             dead_mans_switch_interval_seconds: 30,
+            max_in_flight_pings: 64,
+            allowed_test_types: vec![
+                TestType::PingFlood,
+                TestType::BandwidthTest,
+                TestType::LatencyTest,
+                TestType::PacketLoss,
+                TestType::Bufferbloat,
+            ],
+            max_total_bytes: None,
+            max_total_packets: None,
+            reject_automated_confirmations: false,
+            soft_limit_ratio: 0.8,
+        }
+    }
+}
+
+impl SafetyLimits {
+    /// Tight limits for embedding where network impact must stay minimal: latency tests
+    /// only, at most 50 pps for 30 s, a minute apart per target.
+    pub fn conservative() -> Self {
+        Self {
+            max_packets_per_second: 50,
+            max_duration_seconds: 30,
+            min_cooldown_seconds: 60,
+            max_in_flight_pings: 16,
+            allowed_test_types: vec![TestType::LatencyTest],
+            reject_automated_confirmations: true,
+            ..Self::default()
+        }
+    }
+}
+
+// Packet sizing: IPv4 header + ICMP/UDP header in front of the payload
+const DEFAULT_PAYLOAD_SIZE: u32 = 56;
+const DEFAULT_TARGET_PORT: u16 = 80;
+const DEFAULT_REPLY_WINDOW_MS: u32 = 1_000;
+const DEFAULT_WARMUP_PACKETS: u32 = 3;
+/// Shortest dead man's switch interval a test may ask for
+pub const MIN_DEAD_MANS_SWITCH_SECONDS: u32 = 5;
+// IPv4 header + option-less TCP header
+const SYN_PACKET_BYTES: u64 = 40;
+const PACKET_HEADER_OVERHEAD: u32 = 28;
+const DEFAULT_MTU: u32 = 1500;
+const MAX_IPV4_PAYLOAD: u32 = 65_535 - PACKET_HEADER_OVERHEAD;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MtuInfo {
+    pub interface: Option<String>,
+    pub mtu: u32,
+    pub max_payload: u32,
+}
+
+impl MtuInfo {
+    fn from_mtu(interface: Option<String>, mtu: u32) -> Self {
+        Self {
+            interface,
+            mtu,
+            max_payload: mtu.saturating_sub(PACKET_HEADER_OVERHEAD),
+        }
+    }
+}
+
+// Reachability precheck done before committing to a full run
+const REACHABILITY_PROBES: u32 = 3;
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What one answered probe measured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProbeReply {
+    pub latency_ms: f64,
+    /// IP TTL of the reply, when the probe's socket exposes it
+    pub ttl: Option<u8>,
+    /// The reply came back with the ECN Congestion Experienced mark
+    pub congestion_experienced: bool,
+    /// Outbound delay, when the target stamped the probe's payload on arrival
+    pub one_way_delay_ms: Option<f64>,
+    /// Further copies of the reply that reached the probe while it was listening
+    pub duplicates: u32,
+}
+
+impl From<f64> for ProbeReply {
+    fn from(latency_ms: f64) -> Self {
+        Self { latency_ms, ttl: None, congestion_experienced: false, one_way_delay_ms: None, duplicates: 0 }
+    }
+}
+
+/// A single echo probe against a target, resolving to the round-trip latency in ms.
+pub type ProbeFuture = Pin<Box<dyn Future<Output = Result<ProbeReply, StressError>> + Send>>;
+pub type ProbeFn = Arc<dyn Fn(Ipv4Addr) -> ProbeFuture + Send + Sync>;
+
+pub type ResolveFuture = Pin<Box<dyn Future<Output = std::io::Result<Vec<IpAddr>>> + Send>>;
+pub type ResolveFn = Arc<dyn Fn(String) -> ResolveFuture + Send + Sync>;
+
+/// Longest a hostname lookup may take; mDNS in particular can stall for seconds.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn default_resolver() -> ResolveFn {
+    Arc::new(|host: String| -> ResolveFuture {
+        Box::pin(async move {
+            let addresses = tokio::net::lookup_host((host.as_str(), 0)).await?;
+            Ok(addresses.map(|address| address.ip()).collect())
+        })
+    })
+}
+
+/// Looks like a DNS name rather than a mistyped address: letters, digits, hyphens and dots,
+/// with at least one letter so "192.168.1" isn't sent to the resolver.
+fn is_hostname(target: &str) -> bool {
+    !target.is_empty()
+        && target.len() <= 253
+        && target.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        && target.chars().any(|c| c.is_ascii_alphabetic())
+}
+
+fn default_probe() -> ProbeFn {
+    Arc::new(|target: Ipv4Addr| -> ProbeFuture {
+        Box::pin(async move { send_ping(&target).await })
+    })
+}
+
+/// How a probe spawned by the test loop ended.
+enum ProbeOutcome {
+    /// Resolved, successfully or not, within the reply window
+    InWindow(Result<ProbeReply, StressError>),
+    /// Answered after the window had already written the ping off as lost
+    Late,
+}
+
+/// Matches replies to the sequence number of the ping they answer, so a duplicated reply
+/// isn't counted as a second received packet.
+#[derive(Default)]
+struct ReplyMatcher {
+    answered: HashSet<u32>,
+    duplicates: u32,
+}
+
+impl ReplyMatcher {
+    /// True for the first reply to `sequence`; later ones are counted as duplicates.
+    fn is_first(&mut self, sequence: u32) -> bool {
+        let first = self.answered.insert(sequence);
+        if !first {
+            self.duplicates += 1;
+        }
+        first
+    }
+}
+
+// How long outstanding probes get to report back once the run ends
+const REPLY_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+// How long a probe past its reply window is still awaited, to count a late reply, before
+// it's dropped
+const LATE_REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+// Replies the alert latency and jitter are computed over
+const ALERT_SAMPLE_REPLIES: usize = 10;
+// Sent packets the sustained-abort loss is computed over
+const SUSTAINED_LOSS_PACKETS: u32 = 10;
+// How long stop_current_test waits for the loop to acknowledge; covers the drain above
+const STOP_ACK_TIMEOUT: Duration = Duration::from_secs(3);
+// Confirmations closer together than this can't be a person clicking
+const MIN_HUMAN_CONFIRMATION_INTERVAL: Duration = Duration::from_millis(100);
+// Back-to-back too-fast confirmations before the stream counts as automated
+const AUTOMATED_CONFIRMATION_RUN: u32 = 5;
+
+/// Watches the rhythm of dead man's switch confirmations. A UI timer stuck in a tight loop
+/// confirms many times a second, which would keep the switch from ever tripping.
+#[derive(Default)]
+struct ConfirmationMonitor {
+    last: Option<Instant>,
+    fast_run: u32,
+}
+
+impl ConfirmationMonitor {
+    /// Records a confirmation at `now`; true while it's part of an automated-looking stream.
+    fn record(&mut self, now: Instant) -> bool {
+        let fast = self
+            .last
+            .is_some_and(|last| now.duration_since(last) < MIN_HUMAN_CONFIRMATION_INTERVAL);
+        self.last = Some(now);
+        self.fast_run = if fast { self.fast_run + 1 } else { 0 };
+        if self.fast_run == AUTOMATED_CONFIRMATION_RUN {
+            warn!(
+                "Dead man's switch confirmed {} times in a row less than {:?} apart; this looks automated",
+                AUTOMATED_CONFIRMATION_RUN + 1,
+                MIN_HUMAN_CONFIRMATION_INTERVAL
+            );
+        }
+        self.fast_run >= AUTOMATED_CONFIRMATION_RUN
+    }
+}
+
+// AIMD tuning for adaptive tests
+const ADAPTIVE_WINDOW_PACKETS: u32 = 20;
+const ADAPTIVE_LOSS_THRESHOLD_PERCENT: f64 = 5.0;
+const ADAPTIVE_DECREASE_FACTOR: f64 = 0.5;
+
+/// Additive-increase/multiplicative-decrease controller for adaptive tests.
+/// The rate never exceeds the configured intensity, it only backs off from it.
+#[derive(Clone, Debug)]
+pub struct AdaptiveRateController {
+    current_pps: f64,
+    max_pps: u32,
+    increase_step: f64,
+    loss_threshold_percent: f64,
+}
+
+impl AdaptiveRateController {
+    pub fn new(max_pps: u32) -> Self {
+        Self {
+            current_pps: max_pps as f64,
+            max_pps,
+            increase_step: (max_pps as f64 / 10.0).max(1.0),
+            loss_threshold_percent: ADAPTIVE_LOSS_THRESHOLD_PERCENT,
+        }
+    }
+
+    pub fn current_pps(&self) -> u32 {
+        (self.current_pps.round() as u32).max(1)
+    }
+
+    /// Feeds the loss observed over the last window and returns the new rate.
+    pub fn update(&mut self, window_loss_percent: f64) -> u32 {
+        if window_loss_percent > self.loss_threshold_percent {
+            self.current_pps = (self.current_pps * ADAPTIVE_DECREASE_FACTOR).max(1.0);
+        } else {
+            self.current_pps = (self.current_pps + self.increase_step).min(self.max_pps as f64);
+        }
+        self.current_pps()
+    }
+}
+
+/// Smooth weighted round-robin: over any run of `total weight` picks each target is
+/// chosen exactly `weight` times, interleaved rather than in blocks.
+pub struct WeightedScheduler {
+    weights: Vec<i64>,
+    current: Vec<i64>,
+    total: i64,
+}
+
+impl WeightedScheduler {
+    pub fn new(weights: &[u32]) -> Self {
+        Self {
+            weights: weights.iter().map(|&w| w as i64).collect(),
+            current: vec![0; weights.len()],
+            total: weights.iter().map(|&w| w as i64).sum(),
+        }
+    }
+
+    /// Index of the target the next packet goes to.
+    pub fn next_index(&mut self) -> usize {
+        for (current, weight) in self.current.iter_mut().zip(&self.weights) {
+            *current += weight;
+        }
+        let best = self
+            .current
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, &current)| if current > self.current[best] { i } else { best });
+        self.current[best] -= self.total;
+        best
+    }
+}
+
+#[derive(Default)]
+struct TargetCounters {
+    sent: u32,
+    received: u32,
+    latency_sum: f64,
+}
+
+fn target_metrics(targets: &[Ipv4Addr], counters: &[TargetCounters]) -> Vec<TargetMetrics> {
+    targets
+        .iter()
+        .zip(counters)
+        .map(|(ip, c)| TargetMetrics {
+            target_ip: ip.to_string(),
+            packets_sent: c.sent,
+            packets_received: c.received,
+            packet_loss_percentage: if c.sent > 0 {
+                (c.sent.saturating_sub(c.received) as f64 / c.sent as f64) * 100.0
+            } else {
+                0.0
+            },
+            latency_ms: if c.received > 0 { c.latency_sum / c.received as f64 } else { 0.0 },
+        })
+        .collect()
+}
+
+/// Payload of the `stress_test_update` event the UI listens for.
+#[derive(Serialize, Clone, Debug)]
+pub struct StressTestUpdate {
+    pub metrics: TestMetrics,
+    pub status: TestStatus,
+}
+
+pub type UpdateSink = Arc<dyn Fn(StressTestUpdate) + Send + Sync>;
+
+/// Default minimum gap between emitted updates, i.e. at most 10 per second.
+pub const DEFAULT_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Coalesces metric updates so the UI refresh rate doesn't follow the internal sampling rate.
+struct UpdateThrottle {
+    min_interval: Duration,
+    last_emit: Option<Instant>,
+}
+
+impl UpdateThrottle {
+    fn new(min_interval: Duration) -> Self {
+        Self { min_interval, last_emit: None }
+    }
+
+    fn should_emit(&mut self, now: Instant) -> bool {
+        match self.last_emit {
+            Some(last) if now.duration_since(last) < self.min_interval => false,
+            _ => {
+                self.last_emit = Some(now);
+                true
+            }
+        }
+    }
+}
+
+struct UpdateEmitter {
+    sink: UpdateSink,
+    throttle: UpdateThrottle,
+}
+
+/// Where the test loop reports live metrics and alerts, when anyone is listening.
+struct LoopReporting {
+    updates: Option<UpdateEmitter>,
+    alerts: Option<(AlertTracker, AlertSink)>,
+}
+
+impl UpdateEmitter {
+    fn running(&mut self, metrics: &TestMetrics) {
+        if self.throttle.should_emit(Instant::now()) {
+            (self.sink)(StressTestUpdate { metrics: metrics.clone(), status: TestStatus::Running });
         }
     }
+
+    /// The final snapshot always goes out, regardless of the throttle.
+    fn finished(&self, metrics: &TestMetrics, status: TestStatus) {
+        (self.sink)(StressTestUpdate { metrics: metrics.clone(), status });
+    }
 }
 
 pub struct TestState {
@@ -145,6 +1091,39 @@ pub struct TestState {
     pub last_update: Instant,
     pub last_confirmation: Instant,
     pub cooldown_targets: HashMap<String, Instant>,
+    /// Latest metrics of the current test, for `subscribe_metrics`
+    metrics_feed: Option<(String, watch::Sender<TestMetrics>)>,
+    confirmations: ConfirmationMonitor,
+}
+
+/// A target still cooling down after a test.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TargetCooldown {
+    pub target_ip: String,
+    pub remaining_seconds: u64,
+}
+
+/// Serializable copy of `TestState`, with each `Instant` given as an age in seconds.
+#[derive(Serialize, Clone, Debug)]
+pub struct EngineStateSnapshot {
+    pub current_test: Option<TestResult>,
+    pub metrics: TestMetrics,
+    pub seconds_since_update: f64,
+    pub seconds_since_confirmation: f64,
+    /// Until the dead man's switch stops the running test; `None` when none is running
+    pub dead_mans_switch_remaining_seconds: Option<f64>,
+    /// Targets still cooling down, by address
+    pub cooldowns: Vec<TargetCooldown>,
+}
+
+/// Whole seconds left of a `cooldown_duration` cooldown that began at `last_test_time`.
+fn cooldown_remaining(last_test_time: Instant, cooldown_duration: Duration) -> u64 {
+    let elapsed = last_test_time.elapsed();
+    if elapsed < cooldown_duration {
+        cooldown_duration.as_secs() - elapsed.as_secs()
+    } else {
+        0
+    }
 }
 
 impl Default for TestState {
@@ -155,6 +1134,38 @@ impl Default for TestState {
             last_update: Instant::now(),
             last_confirmation: Instant::now(),
             cooldown_targets: HashMap::new(),
+            metrics_feed: None,
+            confirmations: ConfirmationMonitor::default(),
+        }
+    }
+}
+
+impl TestState {
+    fn publish_metrics(&self) {
+        if let Some((_, feed)) = &self.metrics_feed {
+            feed.send_replace(self.metrics.clone());
+        }
+    }
+
+    /// Adds the current metrics to the test's series, at most once per `SERIES_INTERVAL`
+    /// unless it's the `last` point of the run.
+    fn record_series_sample(&mut self, elapsed: Duration, last: bool) {
+        let Some(test) = self.current_test.as_mut() else {
+            return;
+        };
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let due = match test.metric_series.last() {
+            Some(previous) => last || elapsed_ms >= previous.elapsed_ms + SERIES_INTERVAL.as_millis() as u64,
+            None => true,
+        };
+        if due {
+            test.metric_series.push(MetricSample {
+                elapsed_ms,
+                latency_ms: self.metrics.latency_ms,
+                jitter_ms: self.metrics.jitter_ms,
+                packet_loss_percentage: self.metrics.packet_loss_percentage,
+                throughput_mbps: self.metrics.throughput_mbps,
+            });
         }
     }
 }
@@ -163,7 +1174,26 @@ impl Default for TestState {
 pub struct StressTestEngine {
     state: Arc<RwLock<TestState>>,
     cancel_tx: Option<watch::Sender<bool>>,
+    /// Flips to true once the current test's loop has finalized its state and history
+    finished_rx: Option<watch::Receiver<bool>>,
     safety_limits: SafetyLimits,
+    probe: ProbeFn,
+    resolver: ResolveFn,
+    last_config: Option<StressTestConfig>,
+    last_config_path: Option<PathBuf>,
+    update_sink: Option<UpdateSink>,
+    update_interval: Duration,
+    alert_sink: Option<AlertSink>,
+    /// Packet budget shared with the scanner; unset, the test is only held to its intensity
+    rate_limiter: Option<SharedRateLimiter>,
+    history_dir: Option<PathBuf>,
+    history_limit: usize,
+    history_backend: HistoryBackend,
+    audit_log: Option<AuditLog>,
+    /// Accept 127.0.0.0/8 targets; only the built-in self-test turns this on
+    allow_loopback_target: bool,
+    /// The scanner's gate, to flag tests that overlapped a scan
+    scan_gate: Option<Arc<ScanGate>>,
 }
 
 impl Default for StressTestEngine {
@@ -171,78 +1201,449 @@ impl Default for StressTestEngine {
         Self {
             state: Arc::new(RwLock::new(TestState::default())),
             cancel_tx: None,
+            finished_rx: None,
             safety_limits: SafetyLimits::default(),
+            probe: default_probe(),
+            resolver: default_resolver(),
+            last_config: None,
+            last_config_path: None,
+            update_sink: None,
+            update_interval: DEFAULT_UPDATE_INTERVAL,
+            alert_sink: None,
+            rate_limiter: None,
+            history_dir: None,
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            history_backend: HistoryBackend::default(),
+            audit_log: None,
+            allow_loopback_target: false,
+            scan_gate: None,
         }
     }
 }
 
+const LAST_CONFIG_FILE: &str = "last_config.json";
+
 impl StressTestEngine {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub async fn validate_target_ip(&self, ip: &str) -> Result<(), StressError> {
-        let parsed_ip: Ipv4Addr = ip.parse()
-            .map_err(|_| StressError::InvalidTargetIp)?;
+    /// An engine with `SafetyLimits::conservative`, safe to embed without tuning limits.
+    pub fn new_conservative() -> Self {
+        Self::new().with_safety_limits(SafetyLimits::conservative())
+    }
 
-        // Only allow private network ranges for safety
-        if !is_private_ip(&parsed_ip) {
-            return Err(StressError::InvalidTargetIp);
-        }
+    pub fn with_safety_limits(mut self, safety_limits: SafetyLimits) -> Self {
+        self.safety_limits = safety_limits;
+        self
+    }
 
-        Ok(())
+    pub fn safety_limits(&self) -> &SafetyLimits {
+        &self.safety_limits
     }
 
-    pub async fn validate_test_config(&self, config: &StressTestConfig) -> Result<(), StressError> {
-        // Validate target IP
-        self.validate_target_ip(&config.target_ip).await?;
+    /// Persists engine data (the last-used config and test history) under `dir`,
+    /// loading what's already there.
+    pub fn with_data_dir(mut self, dir: PathBuf) -> Self {
+        self.history_dir = Some(dir.clone());
+        let path = dir.join(LAST_CONFIG_FILE);
+        self.last_config = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok());
+        self.last_config_path = Some(path);
+        self
+    }
 
-        // Check rate limits
-        if config.intensity.to_packets_per_second() > self.safety_limits.max_packets_per_second {
-            return Err(StressError::RateLimitExceeded);
-        }
+    /// How many finished tests the history keeps before rotating out the oldest.
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        self
+    }
 
-        // Check duration
-        if config.duration_seconds > self.safety_limits.max_duration_seconds {
-            return Err(StressError::DurationTooLong);
-        }
+    /// Where the history is kept under the data dir. Defaults to the JSON-lines file.
+    pub fn with_history_backend(mut self, history_backend: HistoryBackend) -> Self {
+        self.history_backend = history_backend;
+        self
+    }
 
-        // Check if test is already running
-        {
-            let state = self.state.read().await;
-            if let Some(test) = &state.current_test {
-                if test.status == TestStatus::Running {
-                    return Err(StressError::TestAlreadyRunning);
-                }
-            }
-        }
+    /// Records every start, stop, completion, failure and rejected start to `path`.
+    pub fn set_audit_log(&mut self, path: PathBuf) {
+        self.audit_log = Some(AuditLog::new(path));
+    }
 
-        // Check cooldown
-        {
-            let state = self.state.read().await;
-            if let Some(last_test_time) = state.cooldown_targets.get(&config.target_ip) {
-                let cooldown_duration = Duration::from_secs(self.safety_limits.min_cooldown_seconds);
-                if last_test_time.elapsed() < cooldown_duration {
-                    let remaining = cooldown_duration.as_secs() - last_test_time.elapsed().as_secs();
-                    return Err(StressError::CooldownActive(remaining));
-                }
-            }
+    fn audit(&self, entry: AuditEntry) {
+        if let Some(audit_log) = &self.audit_log {
+            audit_log.record(&entry);
+        }
+    }
+
+    fn history_store(&self) -> Option<HistoryStore> {
+        self.history_dir
+            .as_ref()
+            .map(|dir| HistoryStore::in_dir(dir, self.history_backend, self.history_limit))
+    }
+
+    /// Finished tests, newest first. Empty when the engine has no data dir.
+    pub fn get_test_history(&self) -> Vec<TestResult> {
+        self.history_store().map(|store| store.load()).unwrap_or_default()
+    }
+
+    /// Finished tests matching `filter`, newest first.
+    pub fn query_test_history(&self, filter: &HistoryFilter) -> Vec<TestResult> {
+        self.history_store().map(|store| store.query(filter)).unwrap_or_default()
+    }
+
+    fn find_in_history(&self, test_id: &str) -> Result<TestResult, StressError> {
+        self.history_store()
+            .and_then(|store| store.find(test_id))
+            .ok_or_else(|| StressError::TestNotFound(test_id.to_string()))
+    }
+
+    fn baseline_store(&self) -> Option<BaselineStore> {
+        self.history_dir.as_deref().map(BaselineStore::in_dir)
+    }
+
+    /// Pins `test_id` from the history as the baseline later tests of its target and type
+    /// are compared against, replacing the automatically captured one.
+    pub fn set_baseline(&self, test_id: &str) -> Result<(), StressError> {
+        let result = self.find_in_history(test_id)?;
+        let store = self.baseline_store().ok_or_else(|| StressError::TestNotFound(test_id.to_string()))?;
+        store
+            .set(&result.target_ip, &result.test_type, test_id)
+            .map_err(|e| StressError::InternalError(format!("Could not save the baseline: {}", e)))
+    }
+
+    /// Diffs the key metrics of two tests from the history.
+    pub fn compare_tests(&self, id_a: &str, id_b: &str) -> Result<TestComparison, StressError> {
+        Ok(compare::compare_results(&self.find_in_history(id_a)?, &self.find_in_history(id_b)?))
+    }
+
+    pub fn last_config(&self) -> Option<&StressTestConfig> {
+        self.last_config.as_ref()
+    }
+
+    fn remember_config(&mut self, config: &StressTestConfig) {
+        self.last_config = Some(config.clone());
+
+        let Some(path) = &self.last_config_path else {
+            return;
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(config).unwrap_or_default()));
+        if let Err(e) = written {
+            warn!("Failed to persist last test config to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Runs a test from the history again with the same parameters, subject to the current
+    /// limits and cooldowns. The new result links back to it through `replay_of`.
+    pub async fn replay_test(&mut self, test_id: &str) -> Result<String, StressError> {
+        let original = self.find_in_history(test_id)?;
+        self.start_stress_test(original.replay_config()).await
+    }
+
+    /// Starts a new test with the last-used configuration, re-validated against current limits.
+    pub async fn rerun_last_test(&mut self) -> Result<String, StressError> {
+        let config = self.last_config.clone().ok_or(StressError::NoPreviousTest)?;
+        self.start_stress_test(config).await
+    }
+
+    /// Replaces the echo probe used for the precheck and the test loop.
+    pub fn with_probe(mut self, probe: ProbeFn) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Replaces the DNS/mDNS lookup used for hostname targets.
+    pub fn with_resolver(mut self, resolver: ResolveFn) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Receives live metric updates while a test runs, at most one per `update_interval`.
+    pub fn with_update_sink(mut self, sink: UpdateSink) -> Self {
+        self.update_sink = Some(sink);
+        self
+    }
+
+    pub fn with_update_interval(mut self, update_interval: Duration) -> Self {
+        self.update_interval = update_interval;
+        self
+    }
+
+    /// Lets loopback addresses through the private-range check, for the self-test's run
+    /// against 127.0.0.1. Not exposed outside the crate.
+    pub(crate) fn allowing_loopback_target(mut self) -> Self {
+        self.allow_loopback_target = true;
+        self
+    }
+
+    /// Draws every packet from `limiter` as well, so the test and concurrent scans stay under
+    /// one combined rate.
+    pub fn with_rate_limiter(mut self, limiter: SharedRateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Flags results of tests that ran while a scan went through `gate`, and warns about
+    /// starting one mid-scan.
+    pub fn with_scan_gate(mut self, gate: Arc<ScanGate>) -> Self {
+        self.scan_gate = Some(gate);
+        self
+    }
+
+    /// Receives alert and clear events for tests configured with `alert_thresholds`.
+    pub fn with_alert_sink(mut self, sink: AlertSink) -> Self {
+        self.alert_sink = Some(sink);
+        self
+    }
+
+    /// The IPv4 address `target` names: itself when it's an address, otherwise the first IPv4
+    /// address a DNS (or, where the OS supports it, mDNS) lookup returns.
+    pub async fn resolve_target(&self, target: &str) -> Result<Ipv4Addr, StressError> {
+        // Tell "wrong address family" apart from input that isn't an address at all
+        match target.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => return Ok(ip),
+            Ok(IpAddr::V6(_)) => return Err(StressError::Ipv6NotSupported(target.to_string())),
+            Err(_) if !is_hostname(target) => return Err(StressError::InvalidTargetIp),
+            Err(_) => {}
+        }
+
+        let unresolved = |reason: String| StressError::HostnameUnresolved { host: target.to_string(), reason };
+        let addresses = timeout(RESOLVE_TIMEOUT, (self.resolver)(target.to_string()))
+            .await
+            .map_err(|_| unresolved(format!("no answer within {:?}", RESOLVE_TIMEOUT)))?
+            .map_err(|e| unresolved(e.to_string()))?;
+        let ipv4 = addresses.iter().find_map(|address| match address {
+            IpAddr::V4(ip) => Some(*ip),
+            IpAddr::V6(_) => None,
+        });
+        match ipv4 {
+            Some(ip) => {
+                info!("Resolved {} to {}", target, ip);
+                Ok(ip)
+            }
+            None if addresses.is_empty() => Err(unresolved("no addresses found".to_string())),
+            None => Err(StressError::Ipv6NotSupported(target.to_string())),
+        }
+    }
+
+    /// `config` with its hostname targets replaced by the addresses they resolve to, so the
+    /// guards, cooldowns and the loop all work on the real target.
+    async fn resolve_hostnames(&self, config: &StressTestConfig) -> Result<StressTestConfig, StressError> {
+        let mut resolved = config.clone();
+        resolved.target_ip = self.resolve_target(&config.target_ip).await?.to_string();
+        for target in &mut resolved.targets {
+            target.ip = self.resolve_target(&target.ip).await?.to_string();
+        }
+        Ok(resolved)
+    }
+
+    /// Accepts an IPv4 address or a hostname; a hostname is checked by the address it resolves to.
+    pub async fn validate_target_ip(&self, ip: &str) -> Result<(), StressError> {
+        let parsed_ip = self.resolve_target(ip).await?;
+
+        // Only allow private network ranges for safety
+        if !is_private_ip(&parsed_ip) && !(self.allow_loopback_target && parsed_ip.is_loopback()) {
+            return Err(StressError::InvalidTargetIp);
         }
 
         Ok(())
     }
 
-    pub async fn start_stress_test(&mut self, config: StressTestConfig) -> Result<String, StressError> {
+    /// `validate_target_ip` over each of `ips`, in order, keeping each one's error.
+    pub async fn validate_targets(&self, ips: &[String]) -> Vec<TargetValidation> {
+        let mut validations = Vec::with_capacity(ips.len());
+        for ip in ips {
+            let error = self.validate_target_ip(ip).await.err().map(ErrorResponse::from);
+            validations.push(TargetValidation { ip: ip.clone(), valid: error.is_none(), error });
+        }
+        validations
+    }
+
+    /// Seconds before `ip` may be tested again, 0 when it isn't cooling down.
+    pub async fn target_cooldown_remaining(&self, ip: &str) -> u64 {
+        let state = self.state.read().await;
+        let cooldown_duration = Duration::from_secs(self.safety_limits.min_cooldown_seconds);
+        state.cooldown_targets.get(ip).map_or(0, |last_test_time| cooldown_remaining(*last_test_time, cooldown_duration))
+    }
+
+    /// The engine's state as it stands, for diagnostics.
+    pub async fn snapshot(&self) -> EngineStateSnapshot {
+        let state = self.state.read().await;
+        let cooldown_duration = Duration::from_secs(self.safety_limits.min_cooldown_seconds);
+        let running = state.current_test.as_ref().is_some_and(|test| test.status == TestStatus::Running);
+        let switch_interval = self.switch_interval(&state);
+        let mut cooldowns: Vec<TargetCooldown> = state
+            .cooldown_targets
+            .iter()
+            .map(|(target_ip, last_test_time)| TargetCooldown {
+                target_ip: target_ip.clone(),
+                remaining_seconds: cooldown_remaining(*last_test_time, cooldown_duration),
+            })
+            .filter(|cooldown| cooldown.remaining_seconds > 0)
+            .collect();
+        cooldowns.sort_by(|a, b| a.target_ip.cmp(&b.target_ip));
+        EngineStateSnapshot {
+            current_test: state.current_test.clone(),
+            metrics: state.metrics.clone(),
+            seconds_since_update: state.last_update.elapsed().as_secs_f64(),
+            seconds_since_confirmation: state.last_confirmation.elapsed().as_secs_f64(),
+            dead_mans_switch_remaining_seconds: running
+                .then(|| switch_interval.saturating_sub(state.last_confirmation.elapsed()).as_secs_f64()),
+            cooldowns,
+        }
+    }
+
+    /// Rejects configs that break a safety limit; ones that pass come back with a warning for
+    /// each limit they're within `soft_limit_ratio` of.
+    pub async fn validate_test_config(&self, config: &StressTestConfig) -> Result<Vec<SafetyWarning>, StressError> {
+        // Validate target IP
+        self.validate_target_ip(&config.target_ip).await?;
+        for target in &config.targets {
+            self.validate_target_ip(&target.ip).await?;
+            if target.weight == 0 {
+                return Err(StressError::InvalidTargetWeight(target.ip.clone()));
+            }
+        }
+
+        // Check the test type is enabled, and that sensitive ones were confirmed
+        if !self.safety_limits.allowed_test_types.contains(&config.test_type) {
+            return Err(StressError::TestTypeNotAllowed(format!("{:?}", config.test_type)));
+        }
+        if config.test_type == TestType::SynFlood && !config.confirmed_sensitive_target {
+            return Err(StressError::ConfirmationRequired);
+        }
+
+        // Check rate limits
+        let requested = config.intensity.to_packets_per_second();
+        if requested > self.safety_limits.max_packets_per_second {
+            return Err(StressError::RateLimitExceeded { requested, max: self.safety_limits.max_packets_per_second });
+        }
+
+        // Check duration
+        if config.duration_seconds > self.safety_limits.max_duration_seconds {
+            return Err(StressError::DurationTooLong {
+                requested: config.duration_seconds,
+                max: self.safety_limits.max_duration_seconds,
+            });
+        }
+
+        // Check rate and duration together stay within the packet budget
+        if let Some(max) = self.safety_limits.max_total_packets {
+            let projected = config.projected_packets();
+            if projected > max {
+                return Err(StressError::PacketBudgetExceeded { projected, max });
+            }
+        }
+
+        // Check the DSCP fits the six bits it's carried in
+        if let Some(dscp) = config.dscp {
+            if dscp > qos::MAX_DSCP {
+                return Err(StressError::InvalidDscp(dscp));
+            }
+        }
+
+        // Check the source address is one we can actually send from
+        config.source_ip()?;
+
+        // Check the payload fits the interface MTU
+        validate_payload_size(config.payload_size(), config.dont_fragment, &detect_mtu())?;
+
+        // Check if test is already running
+        {
+            let state = self.state.read().await;
+            if let Some(test) = &state.current_test {
+                if test.status == TestStatus::Running {
+                    return Err(StressError::TestAlreadyRunning);
+                }
+            }
+        }
+
+        // Check cooldown
+        for target in config.weighted_targets() {
+            let remaining = self.target_cooldown_remaining(&target.ip).await;
+            if remaining > 0 {
+                return Err(StressError::CooldownActive(remaining));
+            }
+        }
+
+        let mut warnings = self.soft_limit_warnings(config);
+        if self.scan_gate.as_ref().is_some_and(|gate| gate.scans_running() > 0) {
+            warnings.push(SafetyWarning::ConcurrentScan);
+        }
+        Ok(warnings)
+    }
+
+    fn soft_limit_warnings(&self, config: &StressTestConfig) -> Vec<SafetyWarning> {
+        let limits = &self.safety_limits;
+        let near = |value: u64, max: u64| value as f64 >= max as f64 * limits.soft_limit_ratio;
+        let mut warnings = Vec::new();
+        let packets_per_second = config.intensity.to_packets_per_second();
+        if near(packets_per_second.into(), limits.max_packets_per_second.into()) {
+            warnings.push(SafetyWarning::NearRateLimit { packets_per_second, max: limits.max_packets_per_second });
+        }
+        if near(config.duration_seconds.into(), limits.max_duration_seconds.into()) {
+            warnings.push(SafetyWarning::NearDurationLimit { seconds: config.duration_seconds, max: limits.max_duration_seconds });
+        }
+        if let Some(max) = limits.max_total_packets {
+            let projected = config.projected_packets();
+            if near(projected, max) {
+                warnings.push(SafetyWarning::NearPacketBudget { projected, max });
+            }
+        }
+        warnings
+    }
+
+    pub async fn start_stress_test(&mut self, requested: StressTestConfig) -> Result<String, StressError> {
+        // Hostnames are resolved once, up front; everything after works on the addresses
+        let config = match self.resolve_hostnames(&requested).await {
+            Ok(config) => config,
+            Err(e) => {
+                self.audit(AuditEntry::new(AuditEvent::Rejected, None, &requested.target_ip, e.code()).with_config(&requested));
+                return Err(e);
+            }
+        };
+
         // Validate configuration
-        self.validate_test_config(&config).await?;
+        match self.validate_test_config(&config).await {
+            Ok(warnings) => {
+                for warning in warnings {
+                    info!("Starting a test near a safety limit: {:?}", warning);
+                }
+            }
+            Err(e) => {
+                self.audit(AuditEntry::new(AuditEvent::Rejected, None, &config.target_ip, e.code()).with_config(&config));
+                return Err(e);
+            }
+        }
 
         // Check system resources
         self.check_system_resources().await?;
 
+        // ECN is best effort: without support the test runs unmarked rather than failing
+        let ecn_capable = config.ecn && qos::ecn_marking_supported();
+        if config.ecn && !ecn_capable {
+            warn!("ECN marking isn't supported on this system, sending without ECT");
+        }
+        let probe = self.probe_for(&config, ecn_capable)?;
+
+        // Don't commit to a full run against a host that isn't there
+        if !config.skip_reachability_check {
+            for target in config.weighted_targets() {
+                let target: Ipv4Addr = target.ip.parse()
+                    .map_err(|_| StressError::InvalidTargetIp)?;
+                self.check_target_reachable(target, &probe).await?;
+            }
+        }
+
         let test_id = generate_test_id();
         let test_result = TestResult {
             test_id: test_id.clone(),
             target_ip: config.target_ip.clone(),
+            target_hostname: (requested.target_ip != config.target_ip).then(|| requested.target_ip.clone()),
             test_type: config.test_type.clone(),
             intensity: config.intensity.clone(),
             duration_seconds: config.duration_seconds,
@@ -254,35 +1655,110 @@ impl StressTestEngine {
             final_metrics: None,
             status: TestStatus::Running,
             error_message: None,
+            source_interface: None,
+            source_ip: None,
+            label: config.label.clone(),
+            notes: config.notes.clone(),
+            stop_reason: None,
+            termination_reason: None,
+            dscp: config.dscp,
+            ecn_capable,
+            // Kept as requested, so a replay or rerun resolves hostnames afresh
+            config: Some(requested.clone()),
+            replay_of: config.replay_of.clone(),
+            metric_series: Vec::new(),
+            concurrent_scan_during_test: false,
+            vs_baseline: None,
+        };
+        let source_interface = match config.source_ip()? {
+            Some(ip) => source_ip::interface_for(ip),
+            None => scanner::default_interface().ok(),
         };
+        let mut test_result = with_source_details(test_result, source_interface.as_ref());
+        if let Some(source) = &config.source_ip {
+            test_result.source_ip = Some(source.clone());
+        }
+
+        self.remember_config(&requested);
 
         // Create cancellation channel
         let (cancel_tx, cancel_rx) = watch::channel(false);
         self.cancel_tx = Some(cancel_tx);
+        let (finished_tx, finished_rx) = watch::channel(false);
+        self.finished_rx = Some(finished_rx);
 
         // Initialize test state
         {
             let mut state = self.state.write().await;
             state.current_test = Some(test_result);
             state.metrics = TestMetrics::default();
+            state.metrics_feed = Some((test_id.clone(), watch::channel(TestMetrics::default()).0));
             state.last_update = Instant::now();
             state.last_confirmation = Instant::now();
-            state.cooldown_targets.insert(config.target_ip.clone(), Instant::now());
+            for target in config.weighted_targets() {
+                state.cooldown_targets.insert(target.ip, Instant::now());
+            }
         }
 
         // Start the stress test engine
         let state_clone = Arc::clone(&self.state);
         let config_clone = config.clone();
         let safety_limits = self.safety_limits.clone();
+        let updates = self.update_sink.clone().map(|sink| UpdateEmitter {
+            sink,
+            throttle: UpdateThrottle::new(self.update_interval),
+        });
+        let alerts = match (config.alert_thresholds.clone(), self.alert_sink.clone()) {
+            (Some(thresholds), Some(sink)) => Some((AlertTracker::new(thresholds), sink)),
+            _ => None,
+        };
+        let rate_limiter = self.rate_limiter.clone();
+        let history = self.history_store();
+        let baselines = self.baseline_store();
+        let history_test_id = test_id.clone();
+        let audit_log = self.audit_log.clone();
+        let audit_test_id = test_id.clone();
+        let audit_target = config.target_ip.clone();
+        let scan_watch = self.scan_gate.clone().map(|gate| {
+            let scans_before = gate.scans_started();
+            let scanning_at_start = gate.scans_running() > 0;
+            move || scanning_at_start || gate.scans_running() > 0 || gate.scans_started() != scans_before
+        });
 
         tokio::spawn(async move {
             let state_for_error = Arc::clone(&state_clone);
-            if let Err(e) = run_stress_test_loop(state_clone, config_clone, cancel_rx, safety_limits).await {
-                error!("Stress test failed: {}", e);
-                // Update state with error
-                if let Ok(mut state) = state_for_error.try_write() {
-                    if let Some(ref mut test) = state.current_test {
+            let state_for_history = Arc::clone(&state_clone);
+            let reporting = LoopReporting { updates, alerts };
+            let outcome = run_stress_test_loop(state_clone, config_clone, cancel_rx, safety_limits, probe, reporting, rate_limiter).await;
+            if let Some(audit_log) = &audit_log {
+                let entry = match &outcome {
+                    Ok(()) => Some(AuditEntry::new(AuditEvent::Complete, Some(&audit_test_id), &audit_target, "completed")),
+                    // Stops are audited by stop_current_test itself
+                    Err(StressError::UserCancelled) => None,
+                    Err(e @ StressError::DeadMansSwitchTriggered) => {
+                        Some(AuditEntry::new(AuditEvent::DeadMansSwitch, Some(&audit_test_id), &audit_target, e.code()))
+                    }
+                    Err(e) => Some(AuditEntry::new(AuditEvent::Fail, Some(&audit_test_id), &audit_target, e.code())),
+                };
+                if let Some(entry) = entry {
+                    audit_log.record(&entry);
+                }
+            }
+
+            if let Err(e) = outcome {
+                // A newer test may already have replaced this one in the state
+                let mut state = state_for_error.write().await;
+                let partial_metrics = state.metrics.clone();
+                match state.current_test.as_mut().filter(|test| test.test_id == history_test_id) {
+                    // stop_current_test already settled the status; keep the drained metrics
+                    Some(test) if matches!(e, StressError::UserCancelled) => {
+                        test.final_metrics = Some(partial_metrics);
+                    }
+                    Some(test) => {
+                        error!("Stress test failed: {}", e);
+                        // Update state with error, keeping the metrics gathered up to the failure
                         test.status = TestStatus::Failed;
+                        test.termination_reason = Some(TerminationReason::from_error(&e));
                         test.error_message = Some(e.to_string());
                         test.end_time = Some(
                             SystemTime::now()
@@ -290,15 +1766,95 @@ impl StressTestEngine {
                                 .unwrap()
                                 .as_millis() as u64
                         );
+                        test.final_metrics = Some(partial_metrics);
+                    }
+                    None => {}
+                }
+            }
+
+            if scan_watch.is_some_and(|overlapped| overlapped()) {
+                let mut state = state_for_history.write().await;
+                if let Some(test) = state.current_test.as_mut().filter(|test| test.test_id == history_test_id) {
+                    test.concurrent_scan_during_test = true;
+                }
+            }
+
+            // Both stores are read from disk, so the comparison is made off the state lock
+            if let (Some(history), Some(baselines)) = (&history, &baselines) {
+                let finished = state_for_history
+                    .read()
+                    .await
+                    .current_test
+                    .clone()
+                    .filter(|test| test.test_id == history_test_id);
+                if let Some(comparison) = finished.and_then(|test| compare_to_baseline(&test, history, baselines)) {
+                    let mut state = state_for_history.write().await;
+                    if let Some(test) = state.current_test.as_mut().filter(|test| test.test_id == history_test_id) {
+                        test.vs_baseline = Some(comparison);
                     }
                 }
             }
+
+            if let Some(history) = history {
+                // A newer test may already have replaced this one in the state
+                let finished = state_for_history
+                    .read()
+                    .await
+                    .current_test
+                    .clone()
+                    .filter(|test| test.test_id == history_test_id);
+                if let Some(result) = finished {
+                    if let Err(e) = history.append(&result) {
+                        warn!("Failed to record test {} in history: {}", result.test_id, e);
+                    }
+                }
+            }
+            let _ = finished_tx.send(true);
         });
 
+        self.audit(AuditEntry::new(AuditEvent::Start, Some(&test_id), &config.target_ip, "started").with_config(&config));
         info!("Started stress test {} for target {}", test_id, config.target_ip);
         Ok(test_id)
     }
 
+    /// SYN floods probe with raw TCP SYNs from the configured source IP, or the scanner's
+    /// interface when unset; every other type echoes.
+    fn probe_for(&self, config: &StressTestConfig, ecn_capable: bool) -> Result<ProbeFn, StressError> {
+        if config.test_type != TestType::SynFlood {
+            return Ok(match config.owd_reflector_port {
+                Some(port) => owd::reflector_probe(port, config.payload_size()),
+                None => Arc::clone(&self.probe),
+            });
+        }
+
+        privileges::require_raw_sockets().map_err(StressError::InsufficientPrivileges)?;
+        let bind_source = config.source_ip()?;
+        let source = bind_source
+            .or_else(|| scanner::default_interface().ok().as_ref().and_then(scanner::interface_ipv4))
+            .ok_or_else(|| StressError::NetworkError("No IPv4 interface to send SYNs from".to_string()))?;
+        Ok(syn::syn_probe(source, bind_source.is_some(), config.target_port(), config.dscp, ecn_capable))
+    }
+
+    async fn check_target_reachable(&self, target: Ipv4Addr, probe: &ProbeFn) -> Result<(), StressError> {
+        let probe = Arc::clone(probe);
+        let attempts = async move {
+            for _ in 0..REACHABILITY_PROBES {
+                if probe(target).await.is_ok() {
+                    return true;
+                }
+            }
+            false
+        };
+
+        match timeout(REACHABILITY_TIMEOUT, attempts).await {
+            Ok(true) => Ok(()),
+            _ => {
+                warn!("Target {} failed the reachability precheck", target);
+                Err(StressError::TargetUnreachable(target.to_string()))
+            }
+        }
+    }
+
     async fn check_system_resources(&self) -> Result<(), StressError> {
         // Simple resource check - in production this would use actual system monitoring
         // For now, we'll just check if we have reasonable limits
@@ -326,9 +1882,36 @@ impl StressTestEngine {
         state.current_test.clone()
     }
 
+    /// The metric series of `test_id`, whether it's the current test or one from the history.
+    pub async fn metric_series(&self, test_id: &str) -> Result<Vec<MetricSample>, StressError> {
+        if let Some(test) = self.get_current_test().await.filter(|test| test.test_id == test_id) {
+            return Ok(test.metric_series);
+        }
+        self.find_in_history(test_id).map(|result| result.metric_series)
+    }
+
+    /// Push-based metrics for `test_id`, for embedding the engine without polling. Any number
+    /// of subscribers may watch the same test; the feed stays readable after the test ends
+    /// and closes when the next one starts.
+    pub async fn subscribe_metrics(&self, test_id: &str) -> Result<watch::Receiver<TestMetrics>, StressError> {
+        let state = self.state.read().await;
+        match &state.metrics_feed {
+            Some((feed_test_id, feed)) if feed_test_id == test_id => Ok(feed.subscribe()),
+            _ => Err(StressError::TestNotFound(test_id.to_string())),
+        }
+    }
+
+    /// The current test's dead man's switch interval, or the global one without a test.
+    fn switch_interval(&self, state: &TestState) -> Duration {
+        match state.current_test.as_ref().and_then(|test| test.config.as_ref()) {
+            Some(config) => config.dead_mans_switch_interval(&self.safety_limits),
+            None => Duration::from_secs(self.safety_limits.dead_mans_switch_interval_seconds.into()),
+        }
+    }
+
     pub async fn check_dead_mans_switch(&self) -> Result<(), StressError> {
         let state = self.state.read().await;
-        let switch_interval = Duration::from_secs(self.safety_limits.dead_mans_switch_interval_seconds as u64);
+        let switch_interval = self.switch_interval(&state);
 
         if state.last_confirmation.elapsed() > switch_interval {
             return Err(StressError::DeadMansSwitchTriggered);
@@ -337,21 +1920,77 @@ impl StressTestEngine {
         Ok(())
     }
 
-    pub async fn confirm_alive(&self) {
+    /// Refreshes the dead man's switch. Returns false when the confirmation was part of an
+    /// automated-looking stream and `reject_automated_confirmations` had it ignored.
+    pub async fn confirm_alive(&self) -> bool {
         let mut state = self.state.write().await;
-        state.last_confirmation = Instant::now();
+        let now = Instant::now();
+        if state.confirmations.record(now) && self.safety_limits.reject_automated_confirmations {
+            return false;
+        }
+        state.last_confirmation = now;
         debug!("Dead man's switch confirmed");
+        true
+    }
+
+    /// Returns the engine to a pristine state between scripted scenarios: no current test,
+    /// no metrics and no cooldowns. Refused while a test is running.
+    pub async fn reset_state(&mut self) -> Result<(), StressError> {
+        let mut state = self.state.write().await;
+        if state.current_test.as_ref().is_some_and(|test| test.status == TestStatus::Running) {
+            return Err(StressError::TestAlreadyRunning);
+        }
+
+        state.current_test = None;
+        state.metrics = TestMetrics::default();
+        state.metrics_feed = None;
+        state.cooldown_targets.clear();
+        state.last_update = Instant::now();
+        state.last_confirmation = Instant::now();
+        self.cancel_tx = None;
+        self.finished_rx = None;
+        info!("Stress test engine state reset");
+        Ok(())
     }
 
+    /// Cancels the current test and waits (up to `STOP_ACK_TIMEOUT`) for its loop to wind
+    /// down, so a test started right after doesn't race the old one's finalization.
     pub async fn stop_current_test(&self) -> Result<(), StressError> {
+        self.mark_stopped().await;
+
         if let Some(tx) = &self.cancel_tx {
             let _ = tx.send(true);
         }
+        if let Some(mut finished_rx) = self.finished_rx.clone() {
+            if timeout(STOP_ACK_TIMEOUT, finished_rx.wait_for(|finished| *finished)).await.is_err() {
+                warn!("Stress test loop didn't acknowledge the stop within {:?}", STOP_ACK_TIMEOUT);
+            }
+        }
+
+        info!("Stress test stopped by user");
+        Ok(())
+    }
+
+    /// Waits up to `limit` for the current test's loop to finish on its own. False on timeout
+    /// or when no test was started.
+    pub(crate) async fn wait_for_completion(&self, limit: Duration) -> bool {
+        let Some(mut finished_rx) = self.finished_rx.clone() else {
+            return false;
+        };
+        matches!(timeout(limit, finished_rx.wait_for(|finished| *finished)).await, Ok(Ok(_)))
+    }
 
+    async fn mark_stopped(&self) {
         let mut state = self.state.write().await;
         let final_metrics = state.metrics.clone();
         if let Some(ref mut test) = state.current_test {
-            test.status = TestStatus::Completed;
+            // A test that already completed or failed keeps its real outcome
+            if test.status != TestStatus::Running {
+                return;
+            }
+            self.audit(AuditEntry::new(AuditEvent::Stop, Some(&test.test_id), &test.target_ip, "stopped by user"));
+            test.status = TestStatus::Cancelled;
+            test.termination_reason = Some(TerminationReason::UserCancelled);
             test.end_time = Some(
                 SystemTime::now()
                     .duration_since(UNIX_EPOCH)
@@ -360,9 +1999,23 @@ impl StressTestEngine {
             );
             test.final_metrics = Some(final_metrics);
         }
+    }
+}
 
-        info!("Stress test stopped by user");
-        Ok(())
+/// `result` against the baseline test of its target and type. The first successful test of
+/// each becomes its baseline, as does the next one if the baseline has left the history.
+fn compare_to_baseline(result: &TestResult, history: &HistoryStore, baselines: &BaselineStore) -> Option<TestComparison> {
+    if result.status != TestStatus::Completed {
+        return None;
+    }
+    match baselines.get(&result.target_ip, &result.test_type).and_then(|test_id| history.find(&test_id)) {
+        Some(baseline) => Some(compare::compare_results(&baseline, result)),
+        None => {
+            if let Err(e) = baselines.set(&result.target_ip, &result.test_type, &result.test_id) {
+                warn!("Failed to record test {} as the baseline for {}: {}", result.test_id, result.target_ip, e);
+            }
+            None
+        }
     }
 }
 
@@ -371,111 +2024,356 @@ async fn run_stress_test_loop(
     config: StressTestConfig,
     mut cancel_rx: watch::Receiver<bool>,
     safety_limits: SafetyLimits,
+    probe: ProbeFn,
+    reporting: LoopReporting,
+    rate_limiter: Option<SharedRateLimiter>,
 ) -> Result<(), StressError> {
-    let target_ip: Ipv4Addr = config.target_ip.parse()
+    let LoopReporting { mut updates, mut alerts } = reporting;
+    let weighted_targets = config.weighted_targets();
+    let targets = weighted_targets
+        .iter()
+        .map(|target| target.ip.parse::<Ipv4Addr>())
+        .collect::<Result<Vec<_>, _>>()
         .map_err(|_| StressError::InvalidTargetIp)?;
+    let weights: Vec<u32> = weighted_targets.iter().map(|target| target.weight).collect();
+    let mut scheduler = WeightedScheduler::new(&weights);
+    let mut target_counters: Vec<TargetCounters> = targets.iter().map(|_| TargetCounters::default()).collect();
+
+    let mut packets_per_second = config.intensity.to_packets_per_second();
+    let mut send_interval = Duration::from_millis(1000 / packets_per_second as u64);
+    let mut interval_timer = interval(send_interval);
+    let mut rate_controller = config
+        .adaptive
+        .then(|| AdaptiveRateController::new(packets_per_second));
 
-    let packets_per_second = config.intensity.to_packets_per_second();
-    let mut interval_timer = interval(Duration::from_millis(1000 / packets_per_second as u64));
+    let tracks_jitter_buffer = matches!(config.test_type, TestType::LatencyTest | TestType::PingFlood);
+    let playout_buffer_ms = config.playout_buffer_ms.filter(|_| tracks_jitter_buffer);
+    let burst_pattern = config.burst_pattern();
+    let peak_rate_pps = packets_per_second;
+    let measures_bufferbloat = config.test_type == TestType::Bufferbloat;
 
     let start_time = Instant::now();
     let test_duration = Duration::from_secs(config.duration_seconds as u64);
 
+    // Probes run concurrently; the semaphore bounds how many await a reply at once
+    let max_in_flight = safety_limits.max_in_flight_pings.max(1) as usize;
+    let in_flight = Arc::new(Semaphore::new(max_in_flight));
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<(usize, u32, ProbeOutcome)>();
+    let mut reply_matcher = ReplyMatcher::default();
+    let reply_window = config.reply_window();
+    let mut late_replies = 0u32;
+    let warmup_packets = config.warmup_packets();
+    let mut warmup_sent = 0u32;
+    let quality_thresholds = config.quality_thresholds();
+    let mut sustained_abort = config.sustained_abort.clone().map(SustainedTracker::new);
+    let mut loss_marks = VecDeque::from([(0u32, 0u32)]);
+
     let mut packets_sent = 0u32;
+    let packet_bytes = config.packet_bytes();
+    let mut bytes_sent = 0u64;
+    let mut stop_reason = None;
     let mut packets_received = 0u32;
     let mut latencies = Vec::new();
+    let mut reply_ttls = BTreeSet::new();
+    let mut ecn_marked_count = 0u32;
+    let mut one_way_delays = Vec::new();
+    let mut arrivals = Vec::new();
+    let mut window_sent = 0u32;
+    let mut window_received = 0u32;
 
-    info!("Starting stress test loop for {} with {} pps", target_ip, packets_per_second);
+    info!("Starting stress test loop for {:?} with {} pps", targets, packets_per_second);
 
-    loop {
+    let outcome = loop {
         tokio::select! {
             _ = interval_timer.tick() => {
                 // Check if test should continue
                 if start_time.elapsed() >= test_duration {
-                    break;
+                    break Ok(());
                 }
 
                 // Check for cancellation
                 if *cancel_rx.borrow() {
                     info!("Stress test cancelled by user");
-                    return Err(StressError::UserCancelled);
+                    break Err(StressError::UserCancelled);
+                }
+
+                // Stop cleanly once the next packet would go over the byte ceiling
+                if let Some(max_total_bytes) = safety_limits.max_total_bytes {
+                    if bytes_sent + packet_bytes > max_total_bytes {
+                        info!("Byte limit of {} reached after {} bytes", max_total_bytes, bytes_sent);
+                        stop_reason = Some(format!("Byte limit of {} bytes reached", max_total_bytes));
+                        break Ok(());
+                    }
                 }
 
                 // Check dead man's switch
                 {
                     let state_read = state.read().await;
-                    let switch_interval = Duration::from_secs(safety_limits.dead_mans_switch_interval_seconds as u64);
+                    let switch_interval = config.dead_mans_switch_interval(&safety_limits);
                     if state_read.last_confirmation.elapsed() > switch_interval {
                         warn!("Dead man's switch triggered");
-                        return Err(StressError::DeadMansSwitchTriggered);
+                        break Err(StressError::DeadMansSwitchTriggered);
                     }
                 }
 
-                // Perform stress test operation based on type
-                match config.test_type {
-                    TestType::PingFlood | TestType::LatencyTest => {
-                        if let Ok(latency) = send_ping(&target_ip).await {
-                            packets_received += 1;
-                            latencies.push(latency);
-                        }
-                        packets_sent += 1;
-                    },
-                    TestType::BandwidthTest => {
-                        // For bandwidth test, we'd send larger packets
-                        if let Ok(latency) = send_ping(&target_ip).await {
-                            packets_received += 1;
-                            latencies.push(latency);
+                // Bufferbloat: hold a light rate for the baseline, then step up to the full one
+                if measures_bufferbloat {
+                    let rate = bufferbloat_rate(start_time.elapsed(), test_duration, peak_rate_pps);
+                    if rate != packets_per_second {
+                        debug!("Bufferbloat load stepped from {} to {} pps", packets_per_second, rate);
+                        packets_per_second = rate;
+                        send_interval = Duration::from_millis(1000 / packets_per_second as u64);
+                        interval_timer = interval(send_interval);
+                    }
+                }
+
+                // Burst mode: ticks during the off-phase send nothing
+                if let Some((on, off)) = burst_pattern {
+                    if !in_burst_on_phase(start_time.elapsed(), on, off) {
+                        continue;
+                    }
+                }
+
+                // Scans running alongside share the same packet budget. A starved bucket
+                // mustn't hold up a stop or run the test past its duration.
+                if let Some(limiter) = &rate_limiter {
+                    tokio::select! {
+                        _ = limiter.acquire() => {}
+                        _ = sleep(test_duration.saturating_sub(start_time.elapsed())) => break Ok(()),
+                        _ = cancel_rx.changed() => {
+                            if *cancel_rx.borrow() {
+                                info!("Stress test cancelled while waiting for the rate limiter");
+                                break Err(StressError::UserCancelled);
+                            }
+                            continue;
                         }
-                        packets_sent += 1;
-                    },
-                    TestType::PacketLoss => {
-                        // Similar to ping but focused on loss measurement
-                        if let Ok(latency) = send_ping(&target_ip).await {
-                            packets_received += 1;
-                            latencies.push(latency);
+                    }
+                }
+
+                // Every test type is currently driven by echo probes. A probe that can't get
+                // an in-flight slot within one send interval is counted as lost.
+                let target_index = scheduler.next_index();
+                let target_ip = targets[target_index];
+                let warmup = warmup_sent < warmup_packets;
+                match timeout(send_interval, Arc::clone(&in_flight).acquire_owned()).await {
+                    Ok(Ok(permit)) => {
+                        let probe = Arc::clone(&probe);
+                        let reply_tx = reply_tx.clone();
+                        let sequence = packets_sent;
+                        tokio::spawn(async move {
+                            // The permit is held until the probe is done with, late wait included,
+                            // so lost pings still count against the in-flight limit
+                            let _permit = permit;
+                            let mut reply = probe(target_ip);
+                            let in_window = timeout(reply_window, &mut reply).await;
+                            if warmup {
+                                return;
+                            }
+                            match in_window {
+                                Ok(result) => {
+                                    // Each copy goes to the matcher under the same sequence number
+                                    let answered = result.as_ref().ok().copied();
+                                    let _ = reply_tx.send((target_index, sequence, ProbeOutcome::InWindow(result)));
+                                    if let Some(reply) = answered {
+                                        for _ in 0..reply.duplicates {
+                                            let _ = reply_tx.send((target_index, sequence, ProbeOutcome::InWindow(Ok(reply))));
+                                        }
+                                    }
+                                }
+                                // A probe that never resolves is dropped once the late wait ends
+                                Err(_) => {
+                                    if let Ok(Ok(_)) = timeout(LATE_REPLY_TIMEOUT, reply).await {
+                                        let _ = reply_tx.send((target_index, sequence, ProbeOutcome::Late));
+                                    }
+                                }
+                            }
+                        });
+                    }
+                    _ => debug!("In-flight limit reached, counting ping as lost"),
+                }
+                bytes_sent += packet_bytes;
+                // Warmup pings pace the test like any other but stay out of the metrics
+                if warmup {
+                    warmup_sent += 1;
+                    continue;
+                }
+                packets_sent += 1;
+                target_counters[target_index].sent += 1;
+
+                // Adaptive mode: re-evaluate the rate once per window of packets
+                if let Some(controller) = rate_controller.as_mut() {
+                    window_sent += 1;
+                    if window_sent >= ADAPTIVE_WINDOW_PACKETS {
+                        let window_loss = (window_sent.saturating_sub(window_received) as f64 / window_sent as f64) * 100.0;
+                        let new_rate = controller.update(window_loss);
+                        if new_rate != packets_per_second {
+                            debug!("Adaptive rate changed from {} to {} pps", packets_per_second, new_rate);
+                            packets_per_second = new_rate;
+                            send_interval = Duration::from_millis(1000 / packets_per_second as u64);
+                            interval_timer = interval(send_interval);
                         }
-                        packets_sent += 1;
-                    },
+                        window_sent = 0;
+                        window_received = 0;
+                    }
                 }
 
                 // Update metrics every 100ms
                 if packets_sent % (packets_per_second / 10).max(1) == 0 {
                     let mut state_write = state.write().await;
                     update_metrics(&mut state_write.metrics, packets_sent, packets_received, &latencies);
+                    state_write.metrics.quality_grade = quality_thresholds.grade(&state_write.metrics);
+                    if tracks_jitter_buffer {
+                        state_write.metrics.required_jitter_buffer_ms = required_jitter_buffer_ms(&latencies, JITTER_BUFFER_MAX_DISCARD);
+                    }
+                    if let Some(depth_ms) = playout_buffer_ms {
+                        record_playout(&mut state_write.metrics, &arrivals, depth_ms);
+                    }
+                    state_write.metrics.current_rate_pps = packets_per_second;
+                    state_write.metrics.average_rate_pps = average_rate(packets_sent + warmup_sent, start_time.elapsed());
+                    state_write.metrics.warmup_packets = warmup_sent;
+                    state_write.metrics.bytes_sent = bytes_sent;
+                    state_write.metrics.in_flight = (max_in_flight - in_flight.available_permits()) as u32;
+                    state_write.metrics.per_target = target_metrics(&targets, &target_counters);
+                    record_ttls(&mut state_write.metrics, &reply_ttls);
+                    state_write.metrics.late_replies = late_replies;
+                    state_write.metrics.ecn_marked_count = ecn_marked_count;
+                    state_write.metrics.duplicate_replies = reply_matcher.duplicates;
+                    state_write.metrics.owd_estimate_ms = average_delay(&one_way_delays);
                     state_write.last_update = Instant::now();
+                    state_write.publish_metrics();
+                    state_write.record_series_sample(start_time.elapsed(), false);
+                    if let Some(updates) = updates.as_mut() {
+                        updates.running(&state_write.metrics);
+                    }
+                    let sample = alert_sample(&latencies, state_write.metrics.packet_loss_percentage);
+                    if let Some((tracker, sink)) = alerts.as_mut() {
+                        for event in tracker.check(sample) {
+                            sink(event);
+                        }
+                    }
+                    // A past burst would keep the cumulative loss high long after the target recovered
+                    let sample = AlertSample { packet_loss_percentage: recent_loss(&mut loss_marks, packets_sent, packets_received), ..sample };
+                    if let Some(reason) = sustained_abort.as_mut().and_then(|tracker| tracker.check(sample, start_time.elapsed())) {
+                        warn!("Stopping test, target stayed unhealthy: {}", reason);
+                        break Err(StressError::SustainedUnhealthy(reason));
+                    }
                 }
 
                 // Circuit breaker - stop if packet loss is too high
                 if packets_sent > 100 && (packets_received as f64 / packets_sent as f64) < 0.1 {
                     warn!("Circuit breaker triggered - high packet loss detected");
-                    break;
+                    break Err(StressError::CircuitBreakerTriggered);
+                }
+            }
+
+            Some((target_index, sequence, outcome)) = reply_rx.recv() => {
+                if let ProbeOutcome::Late = outcome {
+                    late_replies += 1;
+                } else if let ProbeOutcome::InWindow(Ok(reply)) = outcome {
+                    if reply_matcher.is_first(sequence) {
+                        let latency = reply.latency_ms;
+                        reply_ttls.extend(reply.ttl);
+                        ecn_marked_count += u32::from(reply.congestion_experienced);
+                        one_way_delays.extend(reply.one_way_delay_ms);
+                        arrivals.push(Arrival::now(start_time.elapsed(), latency));
+                        packets_received += 1;
+                        latencies.push(latency);
+                        window_received += 1;
+                        target_counters[target_index].received += 1;
+                        target_counters[target_index].latency_sum += latency;
+                    }
                 }
             }
 
             _ = cancel_rx.changed() => {
                 if *cancel_rx.borrow() {
                     info!("Stress test cancelled");
-                    return Err(StressError::UserCancelled);
+                    break Err(StressError::UserCancelled);
                 }
             }
         }
-    }
+    };
+
+    // Give outstanding probes a moment to report back
+    drop(reply_tx);
+    let _ = timeout(REPLY_DRAIN_TIMEOUT, async {
+        while let Some((target_index, sequence, outcome)) = reply_rx.recv().await {
+            if let ProbeOutcome::Late = outcome {
+                late_replies += 1;
+            } else if let ProbeOutcome::InWindow(Ok(reply)) = outcome {
+                if reply_matcher.is_first(sequence) {
+                    let latency = reply.latency_ms;
+                    reply_ttls.extend(reply.ttl);
+                    ecn_marked_count += u32::from(reply.congestion_experienced);
+                    one_way_delays.extend(reply.one_way_delay_ms);
+                    arrivals.push(Arrival::now(start_time.elapsed(), latency));
+                    packets_received += 1;
+                    latencies.push(latency);
+                    target_counters[target_index].received += 1;
+                    target_counters[target_index].latency_sum += latency;
+                }
+            }
+        }
+    })
+    .await;
 
-    // Finalize test
+    // Finalize test. Metrics are flushed even when the run failed so partial results survive.
     {
         let mut state_write = state.write().await;
         update_metrics(&mut state_write.metrics, packets_sent, packets_received, &latencies);
+        state_write.metrics.quality_grade = quality_thresholds.grade(&state_write.metrics);
+        // Replies drained after the loop count toward the final depth
+        if tracks_jitter_buffer {
+            state_write.metrics.required_jitter_buffer_ms = required_jitter_buffer_ms(&latencies, JITTER_BUFFER_MAX_DISCARD);
+        }
+        state_write.metrics.current_rate_pps = packets_per_second;
+        state_write.metrics.average_rate_pps = average_rate(packets_sent + warmup_sent, start_time.elapsed());
+        state_write.metrics.warmup_packets = warmup_sent;
+        state_write.metrics.bytes_sent = bytes_sent;
+        state_write.metrics.in_flight = 0;
+        state_write.metrics.per_target = target_metrics(&targets, &target_counters);
+        record_ttls(&mut state_write.metrics, &reply_ttls);
+        state_write.metrics.late_replies = late_replies;
+        state_write.metrics.ecn_marked_count = ecn_marked_count;
+        state_write.metrics.duplicate_replies = reply_matcher.duplicates;
+        state_write.metrics.owd_estimate_ms = average_delay(&one_way_delays);
+        if let Some(depth_ms) = playout_buffer_ms {
+            record_playout(&mut state_write.metrics, &arrivals, depth_ms);
+        }
+        if measures_bufferbloat {
+            record_bufferbloat(&mut state_write.metrics, &arrivals, test_duration);
+        }
+        state_write.publish_metrics();
+        state_write.record_series_sample(start_time.elapsed(), true);
+        if let Some(updates) = &updates {
+            let status = match &outcome {
+                Ok(()) => TestStatus::Completed,
+                Err(StressError::UserCancelled) => TestStatus::Cancelled,
+                Err(_) => TestStatus::Failed,
+            };
+            updates.finished(&state_write.metrics, status);
+        }
+        outcome?;
 
         let final_metrics = state_write.metrics.clone();
         if let Some(ref mut test) = state_write.current_test {
-            test.status = TestStatus::Completed;
-            test.end_time = Some(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64
-            );
+            // A stop that landed as the duration ran out has already marked the test Cancelled
+            if test.status == TestStatus::Running {
+                test.status = TestStatus::Completed;
+                test.termination_reason = Some(if stop_reason.is_some() {
+                    TerminationReason::ByteCapReached
+                } else {
+                    TerminationReason::DurationReached
+                });
+                test.end_time = Some(
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis() as u64
+                );
+            }
             test.final_metrics = Some(final_metrics);
+            test.stop_reason = stop_reason;
         }
     }
 
@@ -483,7 +2381,7 @@ async fn run_stress_test_loop(
     Ok(())
 }
 
-async fn send_ping(_target: &Ipv4Addr) -> Result<f64, StressError> {
+async fn send_ping(_target: &Ipv4Addr) -> Result<ProbeReply, StressError> {
     // Simplified ping implementation using ICMP
     // In a real implementation, this would use raw sockets or system ping
     let start = Instant::now();
@@ -492,29 +2390,252 @@ async fn send_ping(_target: &Ipv4Addr) -> Result<f64, StressError> {
     match timeout(Duration::from_millis(100), std::future::ready(())).await {
         Ok(_) => {
             let latency = start.elapsed().as_secs_f64() * 1000.0;
-            Ok(latency)
+            Ok(latency.into())
         },
         Err(_) => Err(StressError::NetworkError("Ping timeout".to_string())),
     }
 }
 
-fn update_metrics(metrics: &mut TestMetrics, sent: u32, received: u32, latencies: &[f64]) {
-    metrics.packets_sent = sent;
-    metrics.packets_received = received;
+/// MTU of the interface tests are sent from, falling back to Ethernet's 1500.
+pub fn detect_mtu() -> MtuInfo {
+    let name = scanner::default_interface().ok().map(|interface| interface.name);
+    let mtu = name.as_deref().and_then(read_interface_mtu).unwrap_or(DEFAULT_MTU);
+    MtuInfo::from_mtu(name, mtu)
+}
 
-    if sent > 0 {
-        metrics.packet_loss_percentage = ((sent - received) as f64 / sent as f64) * 100.0;
-    }
+#[cfg(target_os = "linux")]
+fn read_interface_mtu(name: &str) -> Option<u32> {
+    std::fs::read_to_string(format!("/sys/class/net/{}/mtu", name))
+        .ok()
+        .and_then(|mtu| mtu.trim().parse().ok())
+}
 
-    if !latencies.is_empty() {
-        metrics.latency_ms = latencies.iter().sum::<f64>() / latencies.len() as f64;
+#[cfg(not(target_os = "linux"))]
+fn read_interface_mtu(_name: &str) -> Option<u32> {
+    None
+}
 
-        // Calculate jitter (standard deviation of latencies)
-        let mean = metrics.latency_ms;
+fn validate_payload_size(payload: u32, dont_fragment: bool, mtu: &MtuInfo) -> Result<(), StressError> {
+    if payload > MAX_IPV4_PAYLOAD {
+        return Err(StressError::PayloadTooLarge(payload));
+    }
+
+    if payload > mtu.max_payload {
+        if dont_fragment {
+            return Err(StressError::PayloadExceedsMtu { payload, max: mtu.max_payload });
+        }
+        warn!(
+            "Payload of {} bytes exceeds the {} byte MTU and will be fragmented, skewing results",
+            payload, mtu.mtu
+        );
+    }
+
+    Ok(())
+}
+
+/// Records which local interface/IP a test runs from, picked the same way the scanner does.
+fn with_source_details(mut result: TestResult, interface: Option<&NetworkInterface>) -> TestResult {
+    if let Some(interface) = interface {
+        result.source_interface = Some(interface.name.clone());
+        result.source_ip = scanner::interface_ipv4(interface).map(|ip| ip.to_string());
+    }
+    result
+}
+
+/// Share of packets a de-jitter buffer may discard for arriving too late.
+const JITTER_BUFFER_MAX_DISCARD: f64 = 0.01;
+/// Bufferbloat tests measure the idle baseline over this share of the duration, ramp up
+/// over the next share and hold the full rate for the rest
+const BUFFERBLOAT_BASELINE_SHARE: f64 = 0.2;
+const BUFFERBLOAT_RAMP_SHARE: f64 = 0.4;
+const BUFFERBLOAT_RAMP_STEPS: u32 = 4;
+/// Latency added under load, graded as for real-time traffic: a few ms goes unnoticed,
+/// past 100 ms calls and games suffer
+const BUFFERBLOAT_CUTOFFS: GradeCutoffs = GradeCutoffs { excellent: 5.0, good: 30.0, fair: 100.0 };
+
+/// Buffer depth (ms beyond the fastest packet) needed so at most `max_discard` of the
+/// samples would arrive too late to be played out.
+fn required_jitter_buffer_ms(latencies: &[f64], max_discard: f64) -> f64 {
+    if latencies.is_empty() {
+        return 0.0;
+    }
+
+    let mut sorted = latencies.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    percentile(&sorted, 1.0 - max_discard) - sorted[0]
+}
+
+/// When a reply's ping went out and when the reply came back, in ms since the test started.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Arrival {
+    sent_ms: f64,
+    arrived_ms: f64,
+}
+
+impl Arrival {
+    /// A reply arriving `elapsed` into the test after a `latency_ms` round trip.
+    fn now(elapsed: Duration, latency_ms: f64) -> Self {
+        let arrived_ms = elapsed.as_secs_f64() * 1000.0;
+        Self { sent_ms: arrived_ms - latency_ms, arrived_ms }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct PlayoutStats {
+    underruns: u32,
+    discards: u32,
+}
+
+/// Plays `arrivals` out through a fixed buffer `depth_ms` deep, as a media receiver would.
+/// Playback starts `depth_ms` after the first packet arrives and then keeps the senders'
+/// pacing, so each packet is due `depth_ms` plus the first packet's delay after it was sent.
+/// A packet arriving after it was due is discarded, and the buffer runs dry until the next
+/// one in time.
+fn simulate_playout(arrivals: &[Arrival], depth_ms: u32) -> PlayoutStats {
+    let mut by_send_time = arrivals.to_vec();
+    by_send_time.sort_by(|a, b| a.sent_ms.total_cmp(&b.sent_ms));
+    let Some(first) = by_send_time.first() else {
+        return PlayoutStats::default();
+    };
+    let playout_delay = first.arrived_ms - first.sent_ms + f64::from(depth_ms);
+
+    let mut stats = PlayoutStats::default();
+    let mut starved = false;
+    for arrival in &by_send_time {
+        let late = arrival.arrived_ms > arrival.sent_ms + playout_delay;
+        if late {
+            stats.discards += 1;
+            stats.underruns += u32::from(!starved);
+        }
+        starved = late;
+    }
+    stats
+}
+
+fn record_playout(metrics: &mut TestMetrics, arrivals: &[Arrival], depth_ms: u32) {
+    let stats = simulate_playout(arrivals, depth_ms);
+    metrics.playout_underruns = stats.underruns;
+    metrics.playout_discards = stats.discards;
+}
+
+/// Send rate `elapsed` into a bufferbloat test: a tenth of `peak_pps` during the baseline,
+/// then up in even steps to `peak_pps`.
+fn bufferbloat_rate(elapsed: Duration, duration: Duration, peak_pps: u32) -> u32 {
+    let baseline = (peak_pps / 10).max(1);
+    let progress = elapsed.as_secs_f64() / duration.as_secs_f64().max(f64::EPSILON);
+    if progress < BUFFERBLOAT_BASELINE_SHARE {
+        return baseline;
+    }
+    let ramp = ((progress - BUFFERBLOAT_BASELINE_SHARE) / BUFFERBLOAT_RAMP_SHARE).min(1.0);
+    let step = ((ramp * f64::from(BUFFERBLOAT_RAMP_STEPS)) as u32 + 1).min(BUFFERBLOAT_RAMP_STEPS);
+    baseline + (peak_pps.saturating_sub(baseline)) * step / BUFFERBLOAT_RAMP_STEPS
+}
+
+/// Median latency of probes sent once the rate peaked, less the median of those sent during
+/// the baseline, never below zero. `None` without replies from both phases.
+fn bufferbloat_ms(arrivals: &[Arrival], duration: Duration) -> Option<f64> {
+    let duration_ms = duration.as_secs_f64() * 1000.0;
+    let median_between = |from: f64, to: f64| {
+        let mut latencies: Vec<f64> = arrivals
+            .iter()
+            .filter(|arrival| arrival.sent_ms >= from && arrival.sent_ms < to)
+            .map(|arrival| arrival.arrived_ms - arrival.sent_ms)
+            .collect();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+        (!latencies.is_empty()).then(|| percentile(&latencies, 0.5))
+    };
+    let baseline = median_between(0.0, duration_ms * BUFFERBLOAT_BASELINE_SHARE)?;
+    let loaded = median_between(duration_ms * (BUFFERBLOAT_BASELINE_SHARE + BUFFERBLOAT_RAMP_SHARE), f64::INFINITY)?;
+    Some((loaded - baseline).max(0.0))
+}
+
+fn record_bufferbloat(metrics: &mut TestMetrics, arrivals: &[Arrival], duration: Duration) {
+    metrics.bufferbloat_ms = bufferbloat_ms(arrivals, duration);
+    metrics.bufferbloat_grade = metrics.bufferbloat_ms.map(|added_ms| BUFFERBLOAT_CUTOFFS.grade(added_ms));
+}
+
+/// Nearest-rank percentile (`fraction` in 0..=1) of already sorted, non-empty samples.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = (fraction * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Latency and jitter over the last `ALERT_SAMPLE_REPLIES` replies, so a spike shows up right
+/// away instead of being averaged over the whole run.
+fn alert_sample(latencies: &[f64], packet_loss_percentage: f64) -> AlertSample {
+    let recent = &latencies[latencies.len().saturating_sub(ALERT_SAMPLE_REPLIES)..];
+    if recent.is_empty() {
+        return AlertSample { packet_loss_percentage, ..AlertSample::default() };
+    }
+
+    let latency_ms = recent.iter().sum::<f64>() / recent.len() as f64;
+    let variance = recent.iter().map(|&x| (x - latency_ms).powi(2)).sum::<f64>() / recent.len() as f64;
+    AlertSample { latency_ms, packet_loss_percentage, jitter_ms: variance.sqrt() }
+}
+
+/// Loss over roughly the last `SUSTAINED_LOSS_PACKETS` sent, from the (sent, received)
+/// counts recorded at each metrics update. Drops marks that fall out of the window.
+fn recent_loss(marks: &mut VecDeque<(u32, u32)>, sent: u32, received: u32) -> f64 {
+    while marks.len() > 1 && sent - marks[1].0 >= SUSTAINED_LOSS_PACKETS {
+        marks.pop_front();
+    }
+    let (sent_before, received_before) = marks.front().copied().unwrap_or_default();
+    marks.push_back((sent, received));
+
+    let window_sent = sent - sent_before;
+    let window_received = (received - received_before).min(window_sent);
+    if window_sent == 0 {
+        return 0.0;
+    }
+    (window_sent - window_received) as f64 / window_sent as f64 * 100.0
+}
+
+fn record_ttls(metrics: &mut TestMetrics, ttls: &BTreeSet<u8>) {
+    metrics.distinct_ttls = ttls.iter().copied().collect();
+    metrics.ttl_varied = ttls.len() > 1;
+}
+
+/// Whether `elapsed` into a burst test falls in a sending phase. Cycles start with the on-phase.
+fn in_burst_on_phase(elapsed: Duration, on: Duration, off: Duration) -> bool {
+    let cycle = (on + off).as_millis();
+    elapsed.as_millis() % cycle < on.as_millis()
+}
+
+fn average_rate(packets_sent: u32, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 {
+        packets_sent as f64 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// Mean of the one-way delays measured so far; `None` when no reply carried one.
+fn average_delay(delays: &[f64]) -> Option<f64> {
+    (!delays.is_empty()).then(|| delays.iter().sum::<f64>() / delays.len() as f64)
+}
+
+fn update_metrics(metrics: &mut TestMetrics, sent: u32, received: u32, latencies: &[f64]) {
+    metrics.packets_sent = sent;
+    metrics.packets_received = received;
+
+    if sent > 0 {
+        metrics.packet_loss_percentage = ((sent - received) as f64 / sent as f64) * 100.0;
+    }
+
+    if !latencies.is_empty() {
+        metrics.latency_ms = latencies.iter().sum::<f64>() / latencies.len() as f64;
+
+        // Calculate jitter (standard deviation of latencies)
+        let mean = metrics.latency_ms;
         let variance = latencies.iter()
             .map(|&x| (x - mean).powi(2))
             .sum::<f64>() / latencies.len() as f64;
         metrics.jitter_ms = variance.sqrt();
+
+        let mut sorted = latencies.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        metrics.latency_p95_ms = percentile(&sorted, 0.95);
     }
 
     // Simple throughput calculation (packets/sec converted to approximate Mbps)
@@ -526,7 +2647,7 @@ fn update_metrics(metrics: &mut TestMetrics, sent: u32, received: u32, latencies
         .as_millis() as u64;
 }
 
-fn is_private_ip(ip: &Ipv4Addr) -> bool {
+pub(crate) fn is_private_ip(ip: &Ipv4Addr) -> bool {
     let octets = ip.octets();
 
     // 10.0.0.0/8
@@ -560,6 +2681,18 @@ fn generate_test_id() -> String {
 mod tests {
     use super::*;
 
+    fn test_config(target_ip: &str) -> StressTestConfig {
+        StressTestConfig {
+            target_ip: target_ip.to_string(),
+            duration_seconds: 1,
+            ..Default::default()
+        }
+    }
+
+    fn test_result(test_id: &str, target_ip: &str) -> TestResult {
+        TestResult::sample(test_id, target_ip)
+    }
+
     #[test]
     fn test_private_ip_validation() {
         assert!(is_private_ip(&"10.0.0.1".parse().unwrap()));
@@ -589,7 +2722,1471 @@ mod tests {
         // Invalid public IP
         assert!(engine.validate_target_ip("8.8.8.8").await.is_err());
 
-        // Invalid IP format
-        assert!(engine.validate_target_ip("invalid").await.is_err());
+        // Invalid IP format, and not a hostname either
+        assert!(matches!(engine.validate_target_ip("not an ip").await, Err(StressError::InvalidTargetIp)));
+        assert!(matches!(engine.validate_target_ip("192.168.1").await, Err(StressError::InvalidTargetIp)));
+
+        // IPv6 literals get their own error, even private ones
+        assert!(matches!(engine.validate_target_ip("fd00::1").await, Err(StressError::Ipv6NotSupported(_))));
+        assert!(matches!(engine.validate_target_ip("::ffff:192.168.1.1").await, Err(StressError::Ipv6NotSupported(_))));
+    }
+
+    #[tokio::test]
+    async fn test_default_config_is_gentle_and_needs_a_target() {
+        let config = StressTestConfig::default();
+        assert_eq!((config.test_type.clone(), config.intensity.clone()), (TestType::LatencyTest, Intensity::Low));
+        assert!(!config.confirmed_sensitive_target);
+
+        let mut engine = StressTestEngine::new();
+        assert!(engine.start_stress_test(config).await.is_err());
+    }
+
+    #[test]
+    fn test_payload_size_against_mtu() {
+        let mtu = MtuInfo::from_mtu(Some("eth0".to_string()), 1500);
+        assert_eq!(mtu.max_payload, 1472);
+
+        // Under the MTU is fine either way
+        assert!(validate_payload_size(1400, true, &mtu).is_ok());
+        assert!(validate_payload_size(1472, true, &mtu).is_ok());
+
+        // Over the MTU only fails when fragmenting isn't allowed
+        assert!(validate_payload_size(1473, false, &mtu).is_ok());
+        assert!(matches!(
+            validate_payload_size(1473, true, &mtu),
+            Err(StressError::PayloadExceedsMtu { payload: 1473, max: 1472 })
+        ));
+
+        assert!(matches!(
+            validate_payload_size(70_000, false, &mtu),
+            Err(StressError::PayloadTooLarge(70_000))
+        ));
+    }
+
+    #[test]
+    fn test_safety_limits_reported() {
+        assert_eq!(StressTestEngine::new().safety_limits(), &SafetyLimits::default());
+
+        let limits = SafetyLimits {
+            max_packets_per_second: 200,
+            max_duration_seconds: 60,
+            ..SafetyLimits::default()
+        };
+        let engine = StressTestEngine::new().with_safety_limits(limits.clone());
+        assert_eq!(engine.safety_limits(), &limits);
+    }
+
+    #[test]
+    fn test_source_details_from_interface() {
+        let result = test_result("test_1", "192.168.1.50");
+        let interface = NetworkInterface {
+            name: "eth0".to_string(),
+            description: String::new(),
+            index: 2,
+            mac: None,
+            ips: vec!["fe80::1/64".parse().unwrap(), "192.168.1.10/24".parse().unwrap()],
+            flags: 0,
+        };
+
+        let result = with_source_details(result, Some(&interface));
+        assert_eq!(result.source_interface.as_deref(), Some("eth0"));
+        assert_eq!(result.source_ip.as_deref(), Some("192.168.1.10"));
+
+        let result = with_source_details(TestResult { source_interface: None, source_ip: None, ..result }, None);
+        assert!(result.source_interface.is_none());
+        assert!(result.source_ip.is_none());
+    }
+
+    #[test]
+    fn test_weighted_scheduler_distribution() {
+        let mut scheduler = WeightedScheduler::new(&[3, 1, 6]);
+        let mut counts = [0u32; 3];
+        for _ in 0..1000 {
+            counts[scheduler.next_index()] += 1;
+        }
+        assert_eq!(counts, [300, 100, 600]);
+
+        // Picks are interleaved, not sent in blocks
+        let mut scheduler = WeightedScheduler::new(&[1, 1]);
+        let picks: Vec<usize> = (0..4).map(|_| scheduler.next_index()).collect();
+        assert_eq!(picks, vec![0, 1, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_validation_keeps_each_reason() {
+        let engine = StressTestEngine::new();
+        let ips: Vec<String> = ["192.168.1.20", "8.8.8.8", "192.168.1", "fd00::1", "10.0.0.5"]
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect();
+
+        let validations = engine.validate_targets(&ips).await;
+        let outcomes: Vec<(&str, bool, Option<&str>)> = validations
+            .iter()
+            .map(|v| (v.ip.as_str(), v.valid, v.error.as_ref().map(|e| e.code.as_str())))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("192.168.1.20", true, None),
+                ("8.8.8.8", false, Some("INVALID_TARGET_IP")),
+                ("192.168.1", false, Some("INVALID_TARGET_IP")),
+                ("fd00::1", false, Some("IPV6_NOT_SUPPORTED")),
+                ("10.0.0.5", true, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multi_target_validation() {
+        let engine = StressTestEngine::new();
+        let targets = |weights: [u32; 2]| StressTestConfig {
+            targets: vec![
+                WeightedTarget { ip: "192.168.1.10".to_string(), weight: weights[0] },
+                WeightedTarget { ip: "192.168.1.11".to_string(), weight: weights[1] },
+            ],
+            ..test_config("192.168.1.10")
+        };
+
+        assert!(engine.validate_test_config(&targets([3, 1])).await.is_ok());
+        assert!(matches!(
+            engine.validate_test_config(&targets([3, 0])).await,
+            Err(StressError::InvalidTargetWeight(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dscp_range_is_validated() {
+        let engine = StressTestEngine::new();
+        let marked = |dscp| StressTestConfig { dscp: Some(dscp), ..test_config("192.168.1.10") };
+
+        assert!(engine.validate_test_config(&marked(0)).await.is_ok());
+        assert!(engine.validate_test_config(&marked(46)).await.is_ok());
+        assert!(engine.validate_test_config(&marked(63)).await.is_ok());
+        assert!(matches!(
+            engine.validate_test_config(&marked(64)).await,
+            Err(StressError::InvalidDscp(64))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_source_ip_must_be_local() {
+        let engine = StressTestEngine::new();
+        let from = |source: &str| StressTestConfig { source_ip: Some(source.to_string()), ..test_config("192.168.1.10") };
+
+        assert!(engine.validate_test_config(&from("127.0.0.1")).await.is_ok());
+        assert!(matches!(
+            engine.validate_test_config(&from("192.0.2.123")).await,
+            Err(StressError::InvalidSourceIp(source)) if source == "192.0.2.123"
+        ));
+        assert!(matches!(
+            engine.validate_test_config(&from("not-an-ip")).await,
+            Err(StressError::InvalidSourceIp(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_syn_flood_requires_opt_in() {
+        let config = StressTestConfig {
+            test_type: TestType::SynFlood,
+            target_port: Some(443),
+            ..test_config("192.168.1.50")
+        };
+
+        // Off by default, even when confirmed
+        let engine = StressTestEngine::new();
+        let confirmed = StressTestConfig { confirmed_sensitive_target: true, ..config.clone() };
+        assert!(matches!(
+            engine.validate_test_config(&confirmed).await,
+            Err(StressError::TestTypeNotAllowed(_))
+        ));
+
+        // Allowlisted, but still needs the confirmation
+        let mut limits = SafetyLimits::default();
+        limits.allowed_test_types.push(TestType::SynFlood);
+        let engine = StressTestEngine::new().with_safety_limits(limits);
+        assert!(matches!(
+            engine.validate_test_config(&config).await,
+            Err(StressError::ConfirmationRequired)
+        ));
+        assert!(engine.validate_test_config(&confirmed).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rerun_uses_last_config() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-rerun-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new()
+            .with_safety_limits(limits.clone())
+            .with_data_dir(dir.clone());
+        assert!(matches!(engine.rerun_last_test().await, Err(StressError::NoPreviousTest)));
+
+        let config = StressTestConfig {
+            intensity: Intensity::Medium,
+            duration_seconds: 7,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.60")
+        };
+        engine.start_stress_test(config.clone()).await.unwrap();
+        engine.stop_current_test().await.unwrap();
+
+        engine.rerun_last_test().await.unwrap();
+        let rerun = engine.get_current_test().await.unwrap();
+        assert_eq!(rerun.target_ip, "192.168.1.60");
+        assert_eq!(rerun.intensity, Intensity::Medium);
+        assert_eq!(rerun.duration_seconds, 7);
+        assert_eq!(engine.last_config(), Some(&config));
+        engine.stop_current_test().await.unwrap();
+
+        // The config survives an app restart
+        let restarted = StressTestEngine::new().with_data_dir(dir.clone());
+        assert_eq!(restarted.last_config(), Some(&config));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_stop_then_start_does_not_race() {
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits);
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.64")
+        };
+
+        let first = engine.start_stress_test(config.clone()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        engine.stop_current_test().await.unwrap();
+        let second = engine.start_stress_test(config).await.unwrap();
+        assert_ne!(first, second);
+
+        // The first loop has finished, so it can't mark the new test as failed
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let current = engine.get_current_test().await.unwrap();
+        assert_eq!(current.test_id, second);
+        assert_eq!(current.status, TestStatus::Running);
+        engine.stop_current_test().await.unwrap();
+    }
+
+    fn fixed_resolver() -> ResolveFn {
+        Arc::new(|host: String| -> ResolveFuture {
+            Box::pin(async move {
+                match host.as_str() {
+                    "nas.local" => Ok(vec!["fe80::1".parse().unwrap(), "192.168.1.40".parse().unwrap()]),
+                    "public.example.com" => Ok(vec!["8.8.8.8".parse().unwrap()]),
+                    _ => Err(std::io::Error::new(std::io::ErrorKind::NotFound, "name not known")),
+                }
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn test_hostname_target_resolves_to_private_ip() {
+        let mut engine = StressTestEngine::new().with_resolver(fixed_resolver());
+
+        assert_eq!(engine.resolve_target("nas.local").await.unwrap(), Ipv4Addr::new(192, 168, 1, 40));
+        assert!(engine.validate_target_ip("nas.local").await.is_ok());
+        // The guard applies to where the name points, not to the name
+        assert!(matches!(engine.validate_target_ip("public.example.com").await, Err(StressError::InvalidTargetIp)));
+        assert!(matches!(
+            engine.validate_target_ip("printer.local").await,
+            Err(StressError::HostnameUnresolved { host, .. }) if host == "printer.local"
+        ));
+        // Truncated addresses aren't handed to the resolver
+        assert!(matches!(engine.validate_target_ip("192.168.1").await, Err(StressError::InvalidTargetIp)));
+
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("nas.local") };
+        engine.start_stress_test(config).await.unwrap();
+        let current = engine.get_current_test().await.unwrap();
+        assert_eq!(current.target_ip, "192.168.1.40");
+        assert_eq!(current.target_hostname.as_deref(), Some("nas.local"));
+        assert_eq!(current.config.unwrap().target_ip, "nas.local");
+        engine.stop_current_test().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_is_not_held_up_by_the_rate_limiter() {
+        let limiter = SharedRateLimiter::new(1);
+        // Take the one token in the bucket, so the test's first ping waits a full second
+        limiter.acquire().await;
+        let mut engine = StressTestEngine::new().with_rate_limiter(limiter);
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.73")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let stopping = Instant::now();
+        engine.stop_current_test().await.unwrap();
+        assert!(stopping.elapsed() < Duration::from_millis(500), "stop took {:?}", stopping.elapsed());
+        assert!(engine.wait_for_completion(Duration::ZERO).await);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_scan_and_test_share_rate() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let limiter = SharedRateLimiter::new(40);
+        let probes = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&probes);
+        let counting: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok(1.0.into()) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(counting).with_rate_limiter(limiter.clone());
+        let config = StressTestConfig {
+            intensity: Intensity::High,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.72")
+        };
+
+        // Stands in for an ARP sweep drawing from the same budget
+        let start = Instant::now();
+        let sweep_limiter = limiter.clone();
+        let arp_sent = Arc::new(AtomicU32::new(0));
+        let sweep_sent = Arc::clone(&arp_sent);
+        let sweep = tokio::spawn(async move {
+            while start.elapsed() < Duration::from_millis(1000) {
+                sweep_limiter.acquire().await;
+                sweep_sent.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        engine.start_stress_test(config).await.unwrap();
+        sweep.await.unwrap();
+        engine.wait_for_completion(Duration::from_secs(3)).await;
+
+        // High intensity alone would send 100 pps; together they're held to ~40 plus a burst
+        let elapsed = start.elapsed().as_secs_f64();
+        let total = probes.load(Ordering::SeqCst) + arp_sent.load(Ordering::SeqCst);
+        assert!(f64::from(total) <= 40.0 * elapsed + 6.0, "{} packets in {:.2}s", total, elapsed);
+        assert!(probes.load(Ordering::SeqCst) > 0 && arp_sent.load(Ordering::SeqCst) > 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_duration_records_termination_reason() {
+        let mut engine = StressTestEngine::new();
+        let config = StressTestConfig { skip_reachability_check: true, ..test_config("192.168.1.50") };
+        let test_id = engine.start_stress_test(config).await.unwrap();
+        assert!(engine.wait_for_completion(Duration::from_secs(3)).await);
+
+        let result = engine.get_current_test().await.unwrap();
+        assert_eq!(result.status, TestStatus::Completed);
+        assert_eq!(result.termination_reason, Some(TerminationReason::DurationReached));
+        assert!(result.stop_reason.is_none());
+
+        // The series ends with the final metrics, for sparklines drawn after the run
+        let series = engine.metric_series(&test_id).await.unwrap();
+        assert!(!series.is_empty());
+        assert_eq!(series.last().unwrap().latency_ms, result.final_metrics.unwrap().latency_ms);
+        assert!(engine.metric_series("test_0").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dead_mans_switch_records_termination_reason() {
+        let limits = SafetyLimits { dead_mans_switch_interval_seconds: 1, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits);
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("192.168.1.50") };
+        engine.start_stress_test(config).await.unwrap();
+
+        // No confirm_alive calls, so the switch trips a little after one second
+        assert!(engine.wait_for_completion(Duration::from_secs(4)).await);
+        let result = engine.get_current_test().await.unwrap();
+        assert_eq!(result.status, TestStatus::Failed);
+        assert_eq!(result.termination_reason, Some(TerminationReason::DeadMansSwitch));
+    }
+
+    #[tokio::test]
+    async fn test_dead_mans_switch_override_is_clamped() {
+        let limits = SafetyLimits::default();
+        let interval = |seconds: Option<u32>| {
+            StressTestConfig { dead_mans_switch_seconds: seconds, ..test_config("192.168.1.50") }.dead_mans_switch_interval(&limits)
+        };
+        assert_eq!(interval(None), Duration::from_secs(30));
+        assert_eq!(interval(Some(10)), Duration::from_secs(10));
+        // Can't be turned off, or stretched past the global interval
+        assert_eq!(interval(Some(0)), Duration::from_secs(5));
+        assert_eq!(interval(Some(3_600)), Duration::from_secs(30));
+
+        // The running test is held to its own interval
+        let mut engine = StressTestEngine::new().with_safety_limits(limits.clone());
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            dead_mans_switch_seconds: Some(10),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        let switch_left = engine.snapshot().await.dead_mans_switch_remaining_seconds.unwrap();
+        assert!(switch_left > 9.0 && switch_left <= 10.0, "{}", switch_left);
+        engine.stop_current_test().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dead_mans_switch_override_trips_the_test() {
+        let limits = SafetyLimits { dead_mans_switch_interval_seconds: 1, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits);
+        // Asking for longer than the global second is clamped back to it
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            dead_mans_switch_seconds: Some(60),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        assert!(engine.wait_for_completion(Duration::from_secs(4)).await);
+        assert_eq!(engine.get_current_test().await.unwrap().termination_reason, Some(TerminationReason::DeadMansSwitch));
+    }
+
+    #[test]
+    fn test_confirmation_flood_is_flagged() {
+        let start = Instant::now();
+        let mut monitor = ConfirmationMonitor::default();
+        let flagged: Vec<bool> = (0..8).map(|i| monitor.record(start + Duration::from_millis(i * 20))).collect();
+        assert_eq!(flagged, vec![false, false, false, false, false, true, true, true]);
+
+        // A pause a person could plausibly take resets the run
+        assert!(!monitor.record(start + Duration::from_millis(2_000)));
+        let mut human = ConfirmationMonitor::default();
+        assert!((0..20).all(|i| !human.record(start + Duration::from_millis(i * 1_500))));
+    }
+
+    #[tokio::test]
+    async fn test_automated_confirmations_can_be_rejected() {
+        let limits = SafetyLimits { reject_automated_confirmations: true, ..SafetyLimits::default() };
+        let engine = StressTestEngine::new().with_safety_limits(limits);
+        let mut accepted = Vec::new();
+        for _ in 0..8 {
+            accepted.push(engine.confirm_alive().await);
+        }
+        assert!(accepted[..5].iter().all(|ok| *ok));
+        assert!(accepted[5..].iter().all(|ok| !*ok));
+
+        // Only logged by default
+        let lenient = StressTestEngine::new();
+        for _ in 0..8 {
+            assert!(lenient.confirm_alive().await);
+        }
+    }
+
+    #[test]
+    fn test_termination_reason_from_error() {
+        let cases = [
+            (StressError::UserCancelled, TerminationReason::UserCancelled),
+            (StressError::DeadMansSwitchTriggered, TerminationReason::DeadMansSwitch),
+            (StressError::CircuitBreakerTriggered, TerminationReason::CircuitBreaker),
+            (StressError::SustainedUnhealthy("latency".to_string()), TerminationReason::SustainedUnhealthy),
+            (StressError::ResourceLimitExceeded("memory".to_string()), TerminationReason::ResourceLimit),
+            (StressError::NetworkError("down".to_string()), TerminationReason::Error),
+        ];
+        for (error, reason) in cases {
+            assert_eq!(TerminationReason::from_error(&error), reason);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_user_stop_ends_as_cancelled() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-engine-cancel-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_data_dir(dir.clone());
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("192.168.1.71") };
+
+        let test_id = engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        engine.stop_current_test().await.unwrap();
+
+        let current = engine.get_current_test().await.unwrap();
+        assert_eq!(current.status, TestStatus::Cancelled);
+        assert_eq!(current.termination_reason, Some(TerminationReason::UserCancelled));
+        assert!(current.error_message.is_none());
+        assert!(current.final_metrics.unwrap().packets_sent > 0);
+
+        let recorded = engine.get_test_history().into_iter().find(|r| r.test_id == test_id).unwrap();
+        assert_eq!(recorded.status, TestStatus::Cancelled);
+
+        // Stopping again doesn't rewrite the outcome
+        engine.stop_current_test().await.unwrap();
+        assert_eq!(engine.get_current_status().await, TestStatus::Cancelled);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_replay_reconstructs_config() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-engine-replay-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_data_dir(dir.clone());
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            payload_size: Some(512),
+            dscp: Some(46),
+            reply_window_ms: Some(400),
+            warmup_packets: Some(1),
+            label: Some("before firmware update".to_string()),
+            notes: Some("wired".to_string()),
+            ..test_config("192.168.1.70")
+        };
+
+        let original = engine.start_stress_test(config.clone()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let recorded = engine.get_test_history().into_iter().find(|r| r.test_id == original).unwrap();
+        let replayed = recorded.replay_config();
+        assert_eq!(replayed, StressTestConfig { replay_of: Some(original.clone()), ..config });
+
+        let replay = engine.replay_test(&original).await.unwrap();
+        let current = engine.get_current_test().await.unwrap();
+        assert_eq!(current.test_id, replay);
+        assert_eq!(current.replay_of.as_deref(), Some(original.as_str()));
+        assert_eq!(current.label.as_deref(), Some("before firmware update"));
+        engine.stop_current_test().await.unwrap();
+
+        assert!(matches!(engine.replay_test("test_missing").await, Err(StressError::TestNotFound(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_first_test_becomes_the_baseline() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-engine-baseline-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let probe: ProbeFn = Arc::new(|_: Ipv4Addr| -> ProbeFuture { Box::pin(async { Ok(20.0.into()) }) });
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_probe(probe).with_data_dir(dir.clone());
+        let config = StressTestConfig { skip_reachability_check: true, ..test_config("192.168.1.71") };
+        async fn run(engine: &mut StressTestEngine, config: &StressTestConfig) -> TestResult {
+            let test_id = engine.start_stress_test(config.clone()).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            engine.get_test_history().into_iter().find(|r| r.test_id == test_id).unwrap()
+        }
+
+        let first = run(&mut engine, &config).await;
+        assert_eq!(first.status, TestStatus::Completed);
+        assert!(first.vs_baseline.is_none());
+
+        let second = run(&mut engine, &config).await;
+        let delta = second.vs_baseline.expect("the second test should be compared to the first");
+        assert_eq!(delta.test_a, first.test_id);
+        assert_eq!(delta.test_b, second.test_id);
+        assert!(delta.metrics.iter().any(|m| m.metric == "latency_ms"));
+
+        // Pinning the second run makes it the reference from then on
+        engine.set_baseline(&second.test_id).unwrap();
+        let third = run(&mut engine, &config).await;
+        assert_eq!(third.vs_baseline.unwrap().test_a, second.test_id);
+
+        // Another test type of the same target gets a baseline of its own
+        let flood = StressTestConfig { test_type: TestType::PingFlood, ..config.clone() };
+        let first_flood = run(&mut engine, &flood).await;
+        assert!(first_flood.vs_baseline.is_none());
+        let second_flood = run(&mut engine, &flood).await;
+        assert_eq!(second_flood.vs_baseline.unwrap().test_a, first_flood.test_id);
+        assert!(matches!(engine.set_baseline("test_missing"), Err(StressError::TestNotFound(_))));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tests_recorded_in_history() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-engine-history-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new()
+            .with_safety_limits(limits)
+            .with_data_dir(dir.clone())
+            .with_history_limit(2);
+
+        for ip in ["192.168.1.60", "192.168.1.61", "192.168.1.62"] {
+            let config = StressTestConfig { skip_reachability_check: true, ..test_config(ip) };
+            engine.start_stress_test(config).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+        }
+
+        let targets: Vec<String> = engine.get_test_history().into_iter().map(|r| r.target_ip).collect();
+        assert_eq!(targets, vec!["192.168.1.62", "192.168.1.61"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_history_serves_compare_and_filters() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-engine-sqlite-{}", std::process::id()));
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new()
+            .with_safety_limits(limits)
+            .with_data_dir(dir.clone())
+            .with_history_backend(HistoryBackend::Sqlite);
+
+        let mut ids = Vec::new();
+        for ip in ["192.168.1.64", "192.168.1.65"] {
+            let config = StressTestConfig { skip_reachability_check: true, ..test_config(ip) };
+            ids.push(engine.start_stress_test(config).await.unwrap());
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+        }
+
+        assert!(dir.join(crate::history::HISTORY_DB_FILE).exists());
+        assert!(engine.compare_tests(&ids[0], &ids[1]).is_ok());
+        let filter = HistoryFilter { target_ip: Some("192.168.1.65".to_string()), ..HistoryFilter::default() };
+        let filtered: Vec<String> = engine.query_test_history(&filter).into_iter().map(|r| r.test_id).collect();
+        assert_eq!(filtered, vec![ids[1].clone()]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_compare_requires_both_tests() {
+        let engine = StressTestEngine::new();
+        assert!(matches!(
+            engine.compare_tests("test_a", "test_b"),
+            Err(StressError::TestNotFound(id)) if id == "test_a"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_labels_round_trip_through_history() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-labels-{}", std::process::id()));
+        let mut engine = StressTestEngine::new().with_data_dir(dir.clone());
+
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            label: Some("after QoS change".to_string()),
+            notes: Some("VoIP priority queue enabled".to_string()),
+            ..test_config("192.168.1.63")
+        };
+        let test_id = engine.start_stress_test(config).await.unwrap();
+        assert_eq!(engine.get_current_test().await.unwrap().label.as_deref(), Some("after QoS change"));
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        // Read back through a fresh engine, as after a restart
+        let history = StressTestEngine::new().with_data_dir(dir.clone()).get_test_history();
+        let result = history.iter().find(|r| r.test_id == test_id).unwrap();
+        assert_eq!(result.label.as_deref(), Some("after QoS change"));
+        assert_eq!(result.notes.as_deref(), Some("VoIP priority queue enabled"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_playout_buffer_underruns() {
+        // 20 ms packets, first one 10 ms in flight, so each is due 10 + 30 ms after sending
+        let latencies = [10.0, 12.0, 45.0, 50.0, 15.0, 41.0, 10.0, 39.0];
+        let arrivals: Vec<Arrival> = latencies
+            .iter()
+            .enumerate()
+            .map(|(i, latency)| Arrival { sent_ms: i as f64 * 20.0, arrived_ms: i as f64 * 20.0 + latency })
+            .collect();
+
+        // 45 and 50 miss their slot back to back: one underrun, two discards. 41 is a second.
+        let stats = simulate_playout(&arrivals, 30);
+        assert_eq!(stats, PlayoutStats { underruns: 2, discards: 3 });
+        // Deep enough for the slowest packet, nothing is lost
+        assert_eq!(simulate_playout(&arrivals, 40), PlayoutStats::default());
+        assert_eq!(simulate_playout(&[], 30), PlayoutStats::default());
+
+        // Arrival order doesn't matter, only send order
+        let mut shuffled = arrivals.clone();
+        shuffled.reverse();
+        assert_eq!(simulate_playout(&shuffled, 30), stats);
+        assert_eq!(Arrival::now(Duration::from_millis(100), 30.0), Arrival { sent_ms: 70.0, arrived_ms: 100.0 });
+    }
+
+    #[test]
+    fn test_required_jitter_buffer() {
+        assert_eq!(required_jitter_buffer_ms(&[], 0.01), 0.0);
+
+        // 1..=100ms: covering 99% of packets takes the 99ms sample, 98ms above the fastest
+        let uniform: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(required_jitter_buffer_ms(&uniform, 0.01), 98.0);
+
+        // A single 200ms outlier in 100 packets fits in the 1% discard budget
+        let mut steady = vec![10.0; 99];
+        steady.push(200.0);
+        assert_eq!(required_jitter_buffer_ms(&steady, 0.01), 0.0);
+
+        // Two outliers don't
+        steady[0] = 200.0;
+        assert_eq!(required_jitter_buffer_ms(&steady, 0.01), 190.0);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_shows_running_test_and_cooldowns() {
+        let mut engine = StressTestEngine::new();
+        let idle = engine.snapshot().await;
+        assert!(idle.current_test.is_none());
+        assert_eq!(idle.dead_mans_switch_remaining_seconds, None);
+        assert!(idle.cooldowns.is_empty());
+
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("192.168.1.74") };
+        let test_id = engine.start_stress_test(config).await.unwrap();
+        let running = engine.snapshot().await;
+        let test = running.current_test.as_ref().unwrap();
+        assert_eq!(test.test_id, test_id);
+        assert_eq!(test.status, TestStatus::Running);
+        assert!(running.seconds_since_confirmation < 1.0);
+        let switch_left = running.dead_mans_switch_remaining_seconds.unwrap();
+        assert!(switch_left > 29.0 && switch_left <= 30.0, "{}", switch_left);
+        assert_eq!(running.cooldowns, vec![TargetCooldown { target_ip: "192.168.1.74".to_string(), remaining_seconds: 5 }]);
+
+        // Serializes, unlike the state it's taken from
+        let json = serde_json::to_value(&running).unwrap();
+        assert_eq!(json["cooldowns"][0]["remaining_seconds"], 5);
+        engine.stop_current_test().await.unwrap();
+        assert_eq!(engine.snapshot().await.dead_mans_switch_remaining_seconds, None);
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_remaining_decays_to_zero() {
+        let limits = SafetyLimits { min_cooldown_seconds: 1, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits);
+        assert_eq!(engine.target_cooldown_remaining("192.168.1.71").await, 0);
+
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("192.168.1.71") };
+        engine.start_stress_test(config).await.unwrap();
+        engine.stop_current_test().await.unwrap();
+        assert_eq!(engine.target_cooldown_remaining("192.168.1.71").await, 1);
+        assert_eq!(engine.target_cooldown_remaining("192.168.1.72").await, 0);
+
+        tokio::time::sleep(Duration::from_millis(1_100)).await;
+        assert_eq!(engine.target_cooldown_remaining("192.168.1.71").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_scan_overlapping_a_test_is_flagged() {
+        let gate = Arc::new(ScanGate::default());
+        let limits = SafetyLimits { min_cooldown_seconds: 0, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_scan_gate(Arc::clone(&gate));
+        let config = StressTestConfig { duration_seconds: 10, skip_reachability_check: true, ..test_config("192.168.1.73") };
+
+        engine.start_stress_test(config.clone()).await.unwrap();
+        engine.stop_current_test().await.unwrap();
+        assert!(!engine.get_current_test().await.unwrap().concurrent_scan_during_test);
+
+        engine.start_stress_test(config.clone()).await.unwrap();
+        gate.run(|| async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        engine.stop_current_test().await.unwrap();
+        assert!(engine.get_current_test().await.unwrap().concurrent_scan_during_test);
+
+        // Starting a test mid-scan is allowed, with a warning
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let scan = tokio::spawn({
+            let gate = Arc::clone(&gate);
+            async move { gate.run(|| async { release_rx.await.map_err(|_| scanner::ScanError::ScanInProgress) }).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(engine.validate_test_config(&config).await.unwrap(), vec![SafetyWarning::ConcurrentScan]);
+        release_tx.send(()).unwrap();
+        scan.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_start_stop_cycle_is_audited() {
+        let dir = std::env::temp_dir().join(format!("cutecatnet-audit-{}", std::process::id()));
+        let audit_path = dir.join("audit.jsonl");
+        let mut engine = StressTestEngine::new();
+        engine.set_audit_log(audit_path.clone());
+
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.70")
+        };
+        let test_id = engine.start_stress_test(config.clone()).await.unwrap();
+        engine.stop_current_test().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        // Restarting during the cooldown is refused, and that's audited too
+        assert!(engine.start_stress_test(config.clone()).await.is_err());
+
+        let entries = AuditLog::new(audit_path).entries().unwrap();
+        let events: Vec<AuditEvent> = entries.iter().map(|entry| entry.event.clone()).collect();
+        assert_eq!(events, vec![AuditEvent::Start, AuditEvent::Stop, AuditEvent::Rejected]);
+        assert_eq!(entries[0].test_id.as_deref(), Some(test_id.as_str()));
+        assert_eq!(entries[0].config.as_ref(), Some(&config));
+        assert_eq!(entries[1].test_id.as_deref(), Some(test_id.as_str()));
+        assert_eq!(entries[2].outcome, "COOLDOWN_ACTIVE");
+        assert!(entries.iter().all(|entry| entry.target == "192.168.1.70"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_byte_cap_stops_test() {
+        // Default 56 byte payload: 84 bytes per packet, so the cap allows exactly 10 packets
+        let limits = SafetyLimits { max_total_bytes: Some(900), ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits);
+        let config = StressTestConfig {
+            intensity: Intensity::High,
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            warmup_packets: Some(0),
+            ..test_config("192.168.1.50")
+        };
+        assert_eq!(config.packet_bytes(), 84);
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let result = engine.get_current_test().await.unwrap();
+        assert_eq!(result.status, TestStatus::Completed);
+        assert_eq!(result.termination_reason, Some(TerminationReason::ByteCapReached));
+        assert!(result.stop_reason.unwrap().contains("900"));
+        let metrics = result.final_metrics.unwrap();
+        assert_eq!(metrics.packets_sent, 10);
+        assert_eq!(metrics.bytes_sent, 840);
+    }
+
+    #[tokio::test]
+    async fn test_packet_budget_catches_rate_and_duration_combined() {
+        let limits = SafetyLimits { max_total_packets: Some(20_000), ..SafetyLimits::default() };
+        let engine = StressTestEngine::new().with_safety_limits(limits);
+
+        // 100 pps and 300 s each pass their own limits, but add up to 30,000 packets
+        let config = StressTestConfig { intensity: Intensity::High, duration_seconds: 300, ..test_config("192.168.1.50") };
+        assert_eq!(config.projected_packets(), 30_000);
+        match engine.validate_test_config(&config).await {
+            Err(e @ StressError::PacketBudgetExceeded { projected: 30_000, max: 20_000 }) => {
+                assert_eq!(e.details().unwrap()["projected_packets"], 30_000);
+            }
+            other => panic!("expected PacketBudgetExceeded, got {:?}", other),
+        }
+
+        // Sending in half-second bursts every second halves the projection
+        let bursty = StressTestConfig { burst_on_ms: Some(500), burst_off_ms: Some(500), ..config.clone() };
+        assert_eq!(bursty.projected_packets(), 15_000);
+        assert!(engine.validate_test_config(&bursty).await.is_ok());
+        let shorter = StressTestConfig { duration_seconds: 200, ..config };
+        assert!(engine.validate_test_config(&shorter).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_near_limit_config_validates_with_warnings() {
+        let engine = StressTestEngine::new();
+        assert_eq!(engine.validate_test_config(&test_config("192.168.1.50")).await.unwrap(), vec![]);
+
+        // 280 s is under the 300 s cap but past 80% of it
+        let near = StressTestConfig { duration_seconds: 280, ..test_config("192.168.1.50") };
+        assert_eq!(
+            engine.validate_test_config(&near).await.unwrap(),
+            vec![SafetyWarning::NearDurationLimit { seconds: 280, max: 300 }]
+        );
+        let over = StressTestConfig { duration_seconds: 301, ..near };
+        assert!(matches!(engine.validate_test_config(&over).await, Err(StressError::DurationTooLong { requested: 301, max: 300 })));
+    }
+
+    #[tokio::test]
+    async fn test_conservative_engine_is_stricter() {
+        let default_engine = StressTestEngine::new();
+        let conservative = StressTestEngine::new_conservative();
+
+        let high_rate = StressTestConfig { intensity: Intensity::High, ..test_config("192.168.1.50") };
+        assert!(default_engine.validate_test_config(&high_rate).await.is_ok());
+        let too_fast = conservative.validate_test_config(&high_rate).await.unwrap_err();
+        assert!(matches!(too_fast, StressError::RateLimitExceeded { requested: 100, max: 50 }));
+        assert_eq!(too_fast.to_string(), "Rate limit exceeded. Maximum 50 pps allowed");
+
+        let long = StressTestConfig { duration_seconds: 60, ..test_config("192.168.1.50") };
+        assert!(default_engine.validate_test_config(&long).await.is_ok());
+        let too_long = conservative.validate_test_config(&long).await.unwrap_err();
+        assert!(matches!(too_long, StressError::DurationTooLong { requested: 60, max: 30 }));
+        assert_eq!(too_long.details().unwrap()["max_duration_seconds"], 30);
+
+        let flood = StressTestConfig { test_type: TestType::PingFlood, ..test_config("192.168.1.50") };
+        assert!(default_engine.validate_test_config(&flood).await.is_ok());
+        assert!(matches!(conservative.validate_test_config(&flood).await, Err(StressError::TestTypeNotAllowed(_))));
+
+        assert!(conservative.validate_test_config(&test_config("192.168.1.50")).await.is_ok());
+    }
+
+    #[test]
+    fn test_burst_phases() {
+        let (on, off) = (Duration::from_millis(100), Duration::from_millis(300));
+        assert!(in_burst_on_phase(Duration::ZERO, on, off));
+        assert!(in_burst_on_phase(Duration::from_millis(99), on, off));
+        assert!(!in_burst_on_phase(Duration::from_millis(100), on, off));
+        assert!(!in_burst_on_phase(Duration::from_millis(399), on, off));
+        assert!(in_burst_on_phase(Duration::from_millis(450), on, off));
+
+        // Half a pattern isn't a pattern
+        let config = StressTestConfig { burst_on_ms: Some(100), ..test_config("192.168.1.50") };
+        assert_eq!(config.burst_pattern(), None);
+    }
+
+    #[test]
+    fn test_duplicate_replies_counted_once() {
+        let mut matcher = ReplyMatcher::default();
+        assert!(matcher.is_first(7));
+        assert!(!matcher.is_first(7));
+        assert_eq!(matcher.duplicates, 1);
+        assert_eq!(matcher.answered.len(), 1);
+
+        assert!(matcher.is_first(8));
+        assert_eq!(matcher.duplicates, 1);
+    }
+
+    #[tokio::test]
+    async fn test_duplicated_replies_are_reported_by_the_loop() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Every other ping is answered twice, as on a network that duplicates packets
+        let calls = Arc::new(AtomicUsize::new(0));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let duplicates = (calls.fetch_add(1, Ordering::SeqCst) % 2) as u32;
+            Box::pin(async move { Ok(ProbeReply { duplicates, ..ProbeReply::from(1.0) }) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            warmup_packets: Some(0),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        assert!(engine.wait_for_completion(Duration::from_secs(3)).await);
+
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert_eq!(metrics.packets_received, metrics.packets_sent);
+        assert_eq!(metrics.duplicate_replies, metrics.packets_sent / 2);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_subscribers_receive_updates() {
+        let mut engine = StressTestEngine::new();
+        assert!(matches!(engine.subscribe_metrics("test_0").await, Err(StressError::TestNotFound(_))));
+
+        let config = StressTestConfig { intensity: Intensity::High, ..test_config("192.168.1.50") };
+        let test_id = engine.start_stress_test(config).await.unwrap();
+        let mut first = engine.subscribe_metrics(&test_id).await.unwrap();
+        let mut second = engine.subscribe_metrics(&test_id).await.unwrap();
+
+        let mut seen = Vec::new();
+        while timeout(Duration::from_secs(3), first.changed()).await.is_ok_and(|changed| changed.is_ok()) {
+            seen.push(first.borrow_and_update().packets_sent);
+            if engine.get_current_status().await != TestStatus::Running {
+                break;
+            }
+        }
+        assert!(seen.len() > 1);
+        assert!(seen.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Every subscriber ends up at the final metrics
+        let final_metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert_eq!(first.borrow().packets_sent, final_metrics.packets_sent);
+        assert_eq!(second.borrow_and_update().packets_sent, final_metrics.packets_sent);
+    }
+
+    #[tokio::test]
+    async fn test_varying_reply_ttls_are_flagged() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let run = |ttls: &'static [u8]| async move {
+            let calls = Arc::new(AtomicUsize::new(0));
+            let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+                let ttl = ttls[calls.fetch_add(1, Ordering::SeqCst) % ttls.len()];
+                Box::pin(async move { Ok(ProbeReply { ttl: Some(ttl), ..ProbeReply::from(1.0) }) })
+            });
+            let mut engine = StressTestEngine::new().with_probe(probe);
+            let config = StressTestConfig { skip_reachability_check: true, ..test_config("192.168.1.50") };
+            engine.start_stress_test(config).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            engine.get_current_test().await.unwrap().final_metrics.unwrap()
+        };
+
+        let steady = run(&[64]).await;
+        assert!(!steady.ttl_varied);
+        assert_eq!(steady.distinct_ttls, vec![64]);
+
+        let shifting = run(&[64, 64, 63, 64, 62]).await;
+        assert!(shifting.ttl_varied);
+        assert_eq!(shifting.distinct_ttls, vec![62, 63, 64]);
+    }
+
+    #[tokio::test]
+    async fn test_reset_state() {
+        let mut engine = StressTestEngine::new();
+        let config = StressTestConfig { skip_reachability_check: true, ..test_config("192.168.1.50") };
+        engine.start_stress_test(config.clone()).await.unwrap();
+
+        // Not while the test runs
+        assert!(matches!(engine.reset_state().await, Err(StressError::TestAlreadyRunning)));
+        assert_eq!(engine.get_current_status().await, TestStatus::Running);
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(matches!(engine.start_stress_test(config.clone()).await, Err(StressError::CooldownActive(_))));
+
+        engine.reset_state().await.unwrap();
+        assert_eq!(engine.get_current_status().await, TestStatus::Idle);
+        assert!(engine.get_current_test().await.is_none());
+        assert_eq!(engine.get_current_metrics().await.packets_sent, 0);
+
+        // The cooldown went with the rest of the state
+        engine.start_stress_test(config).await.unwrap();
+        engine.stop_current_test().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_congestion_marks_are_counted() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Every third reply comes back marked Congestion Experienced
+        let calls = Arc::new(AtomicUsize::new(0));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let congestion_experienced = calls.fetch_add(1, Ordering::SeqCst) % 3 == 0;
+            Box::pin(async move { Ok(ProbeReply { congestion_experienced, ..ProbeReply::from(1.0) }) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            warmup_packets: Some(0),
+            ecn: true,
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let result = engine.get_current_test().await.unwrap();
+        assert_eq!(result.ecn_capable, qos::ecn_marking_supported());
+        let metrics = result.final_metrics.unwrap();
+        assert_eq!(metrics.ecn_marked_count, metrics.packets_received.div_ceil(3));
+    }
+
+    #[tokio::test]
+    async fn test_one_way_delay_is_reported_when_replies_carry_it() {
+        let probe: ProbeFn = Arc::new(|_: Ipv4Addr| -> ProbeFuture {
+            Box::pin(async { Ok(ProbeReply { one_way_delay_ms: Some(2.0), ..ProbeReply::from(5.0) }) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig { skip_reachability_check: true, warmup_packets: Some(0), ..test_config("192.168.1.50") };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert_eq!(metrics.owd_estimate_ms, Some(2.0));
+        assert_eq!(average_delay(&[]), None);
+    }
+
+    #[test]
+    fn test_bufferbloat_rate_ramps_to_peak() {
+        let duration = Duration::from_secs(10);
+        let rate_at = |secs: f64| bufferbloat_rate(Duration::from_secs_f64(secs), duration, 100);
+        assert_eq!(rate_at(0.0), 10);
+        assert_eq!(rate_at(1.9), 10);
+        assert_eq!(rate_at(2.1), 32);
+        assert_eq!(rate_at(3.5), 55);
+        assert_eq!(rate_at(5.5), 100);
+        assert_eq!(rate_at(9.9), 100);
+        assert_eq!(bufferbloat_rate(Duration::ZERO, duration, 5), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bufferbloat_measures_latency_added_by_load() {
+        use std::collections::VecDeque;
+
+        // Latency grows with the number of probes sent in the last 200 ms, like a queue
+        // filling up as the link saturates
+        let recent = Arc::new(std::sync::Mutex::new(VecDeque::new()));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let mut recent = recent.lock().unwrap();
+            recent.push_back(Instant::now());
+            while recent.front().is_some_and(|sent: &Instant| sent.elapsed() > Duration::from_millis(200)) {
+                recent.pop_front();
+            }
+            let latency = 1.0 + recent.len() as f64 * 0.5;
+            Box::pin(async move { Ok(latency.into()) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig {
+            test_type: TestType::Bufferbloat,
+            intensity: Intensity::High,
+            duration_seconds: 2,
+            skip_reachability_check: true,
+            warmup_packets: Some(0),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        assert!(engine.wait_for_completion(Duration::from_secs(5)).await);
+
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        // About 2 probes in the window while idle, about 20 at 100 pps
+        let added_ms = metrics.bufferbloat_ms.unwrap();
+        assert!((5.0..=12.0).contains(&added_ms), "{}", added_ms);
+        assert_eq!(metrics.bufferbloat_grade, Some(QualityGrade::Good));
+        assert_eq!(metrics.current_rate_pps, 100);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_is_excluded_from_latency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // The first pings are slow, as if waiting on ARP resolution
+        let calls = Arc::new(AtomicUsize::new(0));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let latency = if calls.fetch_add(1, Ordering::SeqCst) < 3 { 50.0 } else { 1.0 };
+            Box::pin(async move { Ok(latency.into()) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig { skip_reachability_check: true, ..test_config("192.168.1.50") };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert_eq!(metrics.warmup_packets, 3);
+        assert_eq!(metrics.latency_ms, 1.0);
+        assert_eq!(metrics.jitter_ms, 0.0);
+        assert_eq!(metrics.packets_received, metrics.packets_sent);
+        // Warmup still paces the run: 10 pps for one second, warmup included
+        assert!(metrics.packets_sent + metrics.warmup_packets >= 10);
+    }
+
+    #[tokio::test]
+    async fn test_replies_past_window_are_late() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Every other ping answers well after the 100 ms window
+        let calls = Arc::new(AtomicUsize::new(0));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let slow = calls.fetch_add(1, Ordering::SeqCst) % 2 == 1;
+            Box::pin(async move {
+                if slow {
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                    Ok(300.0.into())
+                } else {
+                    Ok(1.0.into())
+                }
+            })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            reply_window_ms: Some(100),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert!(metrics.late_replies > 0);
+        assert_eq!(metrics.packets_received + metrics.late_replies, metrics.packets_sent);
+        // Late replies count as loss and stay out of the latency figures
+        assert!(metrics.packet_loss_percentage > 0.0);
+        assert_eq!(metrics.latency_ms, 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_probes_are_dropped() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Dropped(Arc<AtomicUsize>);
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        // No probe ever resolves; each one counts itself when its future is dropped
+        let (started, dropped) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (started_probe, dropped_probe) = (Arc::clone(&started), Arc::clone(&dropped));
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            started_probe.fetch_add(1, Ordering::SeqCst);
+            let guard = Dropped(Arc::clone(&dropped_probe));
+            Box::pin(async move {
+                let _guard = guard;
+                std::future::pending::<Result<ProbeReply, StressError>>().await
+            })
+        });
+        let limits = SafetyLimits { max_in_flight_pings: 4, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_probe(probe);
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            reply_window_ms: Some(100),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+
+        // Slots stay taken through the late wait, so the in-flight limit still holds
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(started.load(Ordering::SeqCst) <= 4);
+
+        tokio::time::sleep(Duration::from_millis(3000)).await;
+        assert!(started.load(Ordering::SeqCst) > 0);
+        assert_eq!(dropped.load(Ordering::SeqCst), started.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_burst_sends_only_in_on_phases() {
+        let sends = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorder = Arc::clone(&sends);
+        let probe: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            recorder.lock().unwrap().push(Instant::now());
+            Box::pin(async { Ok(1.0.into()) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(probe);
+        let config = StressTestConfig {
+            intensity: Intensity::High,
+            skip_reachability_check: true,
+            burst_on_ms: Some(100),
+            burst_off_ms: Some(300),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        // Offsets from the first packet land in the first 100ms of each 400ms cycle,
+        // give or take timer slack
+        let sends = sends.lock().unwrap();
+        let first = sends[0];
+        for send in sends.iter() {
+            let offset = send.duration_since(first).as_millis() % 400;
+            assert!(offset < 120, "packet sent {}ms into a cycle", offset);
+        }
+
+        // A steady 100 pps would have sent ~100 packets in the second
+        let metrics = engine.get_current_test().await.unwrap().final_metrics.unwrap();
+        assert!(metrics.packets_sent > 0 && metrics.packets_sent < 50);
+        assert!(metrics.average_rate_pps < 50.0);
+    }
+
+    #[test]
+    fn test_adaptive_rate_backs_off_on_loss() {
+        let mut controller = AdaptiveRateController::new(100);
+        assert_eq!(controller.current_pps(), 100);
+
+        // Sustained loss halves the rate each window
+        assert_eq!(controller.update(20.0), 50);
+        assert_eq!(controller.update(20.0), 25);
+
+        // Recovery increases additively, never above the configured rate
+        assert_eq!(controller.update(0.0), 35);
+        for _ in 0..20 {
+            controller.update(0.0);
+        }
+        assert_eq!(controller.current_pps(), 100);
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let cases = [
+            (StressError::InvalidTargetIp, "INVALID_TARGET_IP"),
+            (StressError::Ipv6NotSupported("fd00::1".to_string()), "IPV6_NOT_SUPPORTED"),
+            (StressError::InvalidTargetWeight("10.0.0.1".to_string()), "INVALID_TARGET_WEIGHT"),
+            (StressError::TestAlreadyRunning, "TEST_ALREADY_RUNNING"),
+            (StressError::RateLimitExceeded { requested: 100, max: 50 }, "RATE_LIMIT"),
+            (StressError::DurationTooLong { requested: 60, max: 30 }, "DURATION_TOO_LONG"),
+            (StressError::PacketBudgetExceeded { projected: 30_000, max: 10_000 }, "PACKET_BUDGET_EXCEEDED"),
+            (StressError::CooldownActive(3), "COOLDOWN_ACTIVE"),
+            (StressError::ResourceLimitExceeded("cpu".to_string()), "RESOURCE_LIMIT"),
+            (StressError::NetworkError("down".to_string()), "NETWORK_ERROR"),
+            (StressError::TargetUnreachable("10.0.0.1".to_string()), "TARGET_UNREACHABLE"),
+            (StressError::PayloadExceedsMtu { payload: 2000, max: 1472 }, "PAYLOAD_EXCEEDS_MTU"),
+            (StressError::PayloadTooLarge(70_000), "PAYLOAD_TOO_LARGE"),
+            (StressError::DeadMansSwitchTriggered, "DEAD_MANS_SWITCH"),
+            (StressError::CircuitBreakerTriggered, "CIRCUIT_BREAKER"),
+            (StressError::SustainedUnhealthy("latency".to_string()), "SUSTAINED_UNHEALTHY"),
+            (StressError::UserCancelled, "USER_CANCELLED"),
+            (StressError::NoPreviousTest, "NO_PREVIOUS_TEST"),
+            (StressError::TestTypeNotAllowed("SynFlood".to_string()), "TEST_TYPE_NOT_ALLOWED"),
+            (StressError::ConfirmationRequired, "CONFIRMATION_REQUIRED"),
+            (StressError::TestNotFound("test_1".to_string()), "TEST_NOT_FOUND"),
+            (StressError::InsufficientPrivileges("run with sudo".to_string()), "INSUFFICIENT_PRIVILEGES"),
+            (StressError::InvalidDscp(64), "INVALID_DSCP"),
+            (StressError::InvalidSourceIp("10.9.9.9".to_string()), "INVALID_SOURCE_IP"),
+            (StressError::HostnameUnresolved { host: "nas.local".to_string(), reason: "timeout".to_string() }, "HOSTNAME_UNRESOLVED"),
+            (StressError::InternalError("oops".to_string()), "INTERNAL_ERROR"),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.code(), code);
+        }
+
+        let response = ErrorResponse::from(StressError::CooldownActive(3));
+        assert_eq!(response.code, "COOLDOWN_ACTIVE");
+        assert_eq!(response.details.unwrap()["remaining_seconds"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_cap_limits_concurrent_pings() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let current = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (current_probe, peak_probe) = (Arc::clone(&current), Arc::clone(&peak));
+        let slow: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let (current, peak) = (Arc::clone(&current_probe), Arc::clone(&peak_probe));
+            Box::pin(async move {
+                let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                current.fetch_sub(1, Ordering::SeqCst);
+                Ok(200.0.into())
+            })
+        });
+        let limits = SafetyLimits { max_in_flight_pings: 2, ..SafetyLimits::default() };
+        let mut engine = StressTestEngine::new().with_safety_limits(limits).with_probe(slow);
+
+        let config = StressTestConfig { intensity: Intensity::High, ..test_config("192.168.1.50") };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert!(engine.get_current_metrics().await.in_flight <= 2);
+        engine.stop_current_test().await.unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_test_keeps_partial_metrics() {
+        let lossy: ProbeFn = Arc::new(|_: Ipv4Addr| -> ProbeFuture {
+            Box::pin(async { Err(StressError::NetworkError("Ping timeout".to_string())) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(lossy);
+        let config = StressTestConfig {
+            intensity: Intensity::High,
+            duration_seconds: 10,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+
+        let mut result = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let test = engine.get_current_test().await.unwrap();
+            if test.status == TestStatus::Failed {
+                result = Some(test);
+                break;
+            }
+        }
+
+        let result = result.expect("circuit breaker should have failed the test");
+        assert_eq!(result.error_message, Some(StressError::CircuitBreakerTriggered.to_string()));
+        assert_eq!(result.termination_reason, Some(TerminationReason::CircuitBreaker));
+        let metrics = result.final_metrics.expect("partial metrics should be kept");
+        assert!(metrics.packets_sent > 100);
+        assert_eq!(metrics.packets_received, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sustained_high_latency_fails_the_test() {
+        let slow: ProbeFn = Arc::new(|_: Ipv4Addr| -> ProbeFuture { Box::pin(async { Ok(250.0.into()) }) });
+        let mut engine = StressTestEngine::new().with_probe(slow);
+        let abort = SustainedAbort { latency_ms: Some(100.0), sustained_seconds: Some(1), ..SustainedAbort::default() };
+        let config = StressTestConfig {
+            duration_seconds: 10,
+            sustained_abort: Some(abort),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+
+        let mut result = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let test = engine.get_current_test().await.unwrap();
+            if test.status == TestStatus::Failed {
+                result = Some(test);
+                break;
+            }
+        }
+
+        let result = result.expect("sustained latency should have failed the test");
+        assert_eq!(result.termination_reason, Some(TerminationReason::SustainedUnhealthy));
+        let message = result.error_message.unwrap();
+        assert!(message.contains("latency 250.0 ms above 100 ms"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn test_loss_burst_then_recovery_does_not_abort() {
+        // Every ping in the first second is lost, then the target answers again. The
+        // cumulative loss stays over 30% for about three seconds; the recent loss doesn't.
+        let started = Instant::now();
+        let recovering: ProbeFn = Arc::new(move |_: Ipv4Addr| -> ProbeFuture {
+            let lossy = started.elapsed() < Duration::from_secs(1);
+            Box::pin(async move {
+                if lossy {
+                    Err(StressError::NetworkError("Ping timeout".to_string()))
+                } else {
+                    Ok(5.0.into())
+                }
+            })
+        });
+        let mut engine = StressTestEngine::new().with_probe(recovering);
+        let abort = SustainedAbort { packet_loss_percentage: Some(30.0), sustained_seconds: Some(2), ..SustainedAbort::default() };
+        let config = StressTestConfig {
+            duration_seconds: 5,
+            skip_reachability_check: true,
+            sustained_abort: Some(abort),
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+
+        let mut status = TestStatus::Running;
+        for _ in 0..80 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            status = engine.get_current_test().await.unwrap().status;
+            if matches!(status, TestStatus::Completed | TestStatus::Failed) {
+                break;
+            }
+        }
+        let test = engine.get_current_test().await.unwrap();
+        assert_eq!(status, TestStatus::Completed, "{:?}", test.error_message);
+    }
+
+    #[test]
+    fn test_recent_loss_forgets_old_packets() {
+        let mut marks = VecDeque::from([(0, 0)]);
+        assert_eq!(recent_loss(&mut marks, 5, 0), 100.0);
+        assert_eq!(recent_loss(&mut marks, 10, 5), 50.0);
+        // The lost packets are now out of the window
+        assert_eq!(recent_loss(&mut marks, 15, 10), 0.0);
+        assert_eq!(recent_loss(&mut marks, 20, 12), 30.0);
+    }
+
+    #[test]
+    fn test_update_throttle_coalesces() {
+        let mut throttle = UpdateThrottle::new(Duration::from_millis(100));
+        let start = Instant::now();
+        let emitted = (0..1000)
+            .filter(|ms| throttle.should_emit(start + Duration::from_millis(*ms)))
+            .count();
+        assert_eq!(emitted, 10);
+    }
+
+    #[tokio::test]
+    async fn test_updates_coalesced_during_busy_run() {
+        let updates = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink_updates = Arc::clone(&updates);
+        let sink: UpdateSink = Arc::new(move |update: StressTestUpdate| sink_updates.lock().unwrap().push(update));
+        let mut engine = StressTestEngine::new()
+            .with_update_sink(sink)
+            .with_update_interval(Duration::from_millis(300));
+
+        let config = StressTestConfig {
+            intensity: Intensity::High,
+            skip_reachability_check: true,
+            ..test_config("192.168.1.50")
+        };
+        engine.start_stress_test(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+
+        // ~10 internal samples over the second, but only one emit per 300ms plus the final one
+        let updates = updates.lock().unwrap();
+        assert!(updates.len() >= 2 && updates.len() <= 5, "emitted {} updates", updates.len());
+        let last = updates.last().unwrap();
+        assert_eq!(last.status, TestStatus::Completed);
+        assert!(last.metrics.packets_sent > 0);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_target_fails_precheck() {
+        let unreachable: ProbeFn = Arc::new(|_: Ipv4Addr| -> ProbeFuture {
+            Box::pin(async { Err(StressError::NetworkError("Ping timeout".to_string())) })
+        });
+        let mut engine = StressTestEngine::new().with_probe(unreachable);
+
+        let started = Instant::now();
+        let result = engine.start_stress_test(test_config("192.168.1.50")).await;
+
+        assert!(matches!(result, Err(StressError::TargetUnreachable(_))));
+        assert!(started.elapsed() < REACHABILITY_TIMEOUT);
+        assert_eq!(engine.get_current_status().await, TestStatus::Idle);
+
+        // Users can still opt into measuring an unreachable host
+        let config = StressTestConfig {
+            skip_reachability_check: true,
+            ..test_config("192.168.1.50")
+        };
+        assert!(engine.start_stress_test(config).await.is_ok());
+        engine.stop_current_test().await.unwrap();
     }
 }
\ No newline at end of file
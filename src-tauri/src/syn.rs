@@ -0,0 +1,145 @@
+// src-tauri/src/syn.rs
+
+use pnet::packet::ip::IpNextHeaderProtocols;
+use pnet::packet::tcp::{self, MutableTcpPacket, TcpFlags, TcpPacket};
+use pnet::packet::Packet;
+use pnet::transport::{tcp_packet_iter, transport_channel, TransportChannelType, TransportProtocol};
+use rand::Rng;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::qos;
+use crate::source_ip;
+use crate::stresser::{ProbeFn, ProbeFuture, ProbeReply, StressError};
+
+const TCP_HEADER_LEN: usize = 20;
+const SYN_WINDOW: u16 = 64_240;
+const SYN_REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+/// IANA ephemeral range, so SYNs look like ordinary client connection attempts.
+const EPHEMERAL_PORTS: std::ops::RangeInclusive<u16> = 49_152..=65_535;
+
+pub fn random_source_port() -> u16 {
+	rand::thread_rng().gen_range(EPHEMERAL_PORTS)
+}
+
+/// A bare TCP SYN (no options, no payload) with a valid checksum for the given addresses.
+pub fn build_syn_packet(source: Ipv4Addr, source_port: u16, target: Ipv4Addr, target_port: u16, sequence: u32) -> Vec<u8> {
+	let mut buffer = vec![0u8; TCP_HEADER_LEN];
+	{
+		let mut packet = MutableTcpPacket::new(&mut buffer).expect("buffer fits a TCP header");
+		packet.set_source(source_port);
+		packet.set_destination(target_port);
+		packet.set_sequence(sequence);
+		packet.set_acknowledgement(0);
+		packet.set_data_offset((TCP_HEADER_LEN / 4) as u8);
+		packet.set_flags(TcpFlags::SYN);
+		packet.set_window(SYN_WINDOW);
+		let checksum = tcp::ipv4_checksum(&packet.to_immutable(), &source, &target);
+		packet.set_checksum(checksum);
+	}
+	buffer
+}
+
+/// True when `packet` is the SYN-ACK answering a SYN we sent with `sequence` from `source_port`.
+fn is_syn_ack_for(packet: &TcpPacket, target_port: u16, source_port: u16, sequence: u32) -> bool {
+	let syn_ack = TcpFlags::SYN | TcpFlags::ACK;
+	packet.get_flags() & syn_ack == syn_ack
+		&& packet.get_source() == target_port
+		&& packet.get_destination() == source_port
+		&& packet.get_acknowledgement() == sequence.wrapping_add(1)
+}
+
+/// Probe for `TestType::SynFlood`: each call sends one SYN from a random source port and
+/// resolves with the SYN-ACK round trip. Needs raw socket privileges. With `bind_source`,
+/// the socket is bound to `source` rather than leaving the OS to pick the outgoing address.
+pub fn syn_probe(source: Ipv4Addr, bind_source: bool, target_port: u16, dscp: Option<u8>, ecn_capable: bool) -> ProbeFn {
+	Arc::new(move |target: Ipv4Addr| -> ProbeFuture {
+		Box::pin(async move {
+			tokio::task::spawn_blocking(move || send_syn(source, bind_source, target, target_port, dscp, ecn_capable))
+				.await
+				.map_err(|e| StressError::InternalError(e.to_string()))?
+		})
+	})
+}
+
+fn send_syn(
+	source: Ipv4Addr,
+	bind_source: bool,
+	target: Ipv4Addr,
+	target_port: u16,
+	dscp: Option<u8>,
+	ecn_capable: bool,
+) -> Result<ProbeReply, StressError> {
+	let network_error = |e: std::io::Error| StressError::NetworkError(e.to_string());
+	let protocol = TransportChannelType::Layer4(TransportProtocol::Ipv4(IpNextHeaderProtocols::Tcp));
+	let (mut tx, mut rx) = transport_channel(4096, protocol).map_err(network_error)?;
+	if bind_source {
+		source_ip::bind_raw(tx.socket.fd as _, source).map_err(network_error)?;
+	}
+	if dscp.is_some() || ecn_capable {
+		qos::set_marking_raw(tx.socket.fd as _, dscp, ecn_capable).map_err(network_error)?;
+	}
+
+	let source_port = random_source_port();
+	let sequence: u32 = rand::thread_rng().gen();
+	let mut syn = build_syn_packet(source, source_port, target, target_port, sequence);
+	let syn = MutableTcpPacket::new(&mut syn)
+		.ok_or_else(|| StressError::InternalError("Failed to build SYN packet".to_string()))?;
+
+	let start = Instant::now();
+	tx.send_to(syn, IpAddr::V4(target)).map_err(network_error)?;
+
+	let deadline = start + SYN_REPLY_TIMEOUT;
+	let mut replies = tcp_packet_iter(&mut rx);
+	while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+		match replies.next_with_timeout(remaining).map_err(network_error)? {
+			Some((packet, addr)) if addr == IpAddr::V4(target) && is_syn_ack_for(&packet, target_port, source_port, sequence) => {
+				return Ok((start.elapsed().as_secs_f64() * 1000.0).into());
+			}
+			Some(_) => continue,
+			None => break,
+		}
+	}
+
+	Err(StressError::NetworkError("SYN-ACK timeout".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_syn_packet_structure() {
+		let source: Ipv4Addr = "192.168.1.10".parse().unwrap();
+		let target: Ipv4Addr = "192.168.1.50".parse().unwrap();
+		let buffer = build_syn_packet(source, 50_000, target, 443, 1_000);
+		let packet = TcpPacket::new(&buffer).unwrap();
+
+		assert_eq!(buffer.len(), TCP_HEADER_LEN);
+		assert_eq!(packet.get_source(), 50_000);
+		assert_eq!(packet.get_destination(), 443);
+		assert_eq!(packet.get_sequence(), 1_000);
+		assert_eq!(packet.get_acknowledgement(), 0);
+		assert_eq!(packet.get_flags(), TcpFlags::SYN);
+		assert_eq!(packet.get_data_offset(), 5);
+		assert!(packet.payload().is_empty());
+		assert_eq!(packet.get_checksum(), tcp::ipv4_checksum(&packet, &source, &target));
+
+		// A matching SYN-ACK is recognised; a RST is not
+		let mut reply = build_syn_packet(target, 443, source, 50_000, 9);
+		let mut syn_ack = MutableTcpPacket::new(&mut reply).unwrap();
+		syn_ack.set_flags(TcpFlags::SYN | TcpFlags::ACK);
+		syn_ack.set_acknowledgement(1_001);
+		assert!(is_syn_ack_for(&syn_ack.to_immutable(), 443, 50_000, 1_000));
+		syn_ack.set_flags(TcpFlags::RST | TcpFlags::ACK);
+		assert!(!is_syn_ack_for(&syn_ack.to_immutable(), 443, 50_000, 1_000));
+	}
+
+	#[test]
+	fn test_source_port_randomized() {
+		let ports: std::collections::HashSet<u16> = (0..50).map(|_| random_source_port()).collect();
+		assert!(ports.iter().all(|port| EPHEMERAL_PORTS.contains(port)));
+		assert!(ports.len() > 1);
+	}
+}
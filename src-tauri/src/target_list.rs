@@ -0,0 +1,249 @@
+// src-tauri/src/target_list.rs
+
+use ipnetwork::Ipv4Network;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::stresser::{is_private_ip, ErrorResponse, StressError, StressTestConfig, StressTestEngine, TestResult, TestStatus};
+
+/// Most targets one list may expand to, so a stray `/16` can't queue thousands of tests.
+pub const MAX_LIST_TARGETS: usize = 256;
+const SUITE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Extra time past a test's duration before the suite stops waiting for it.
+const SUITE_TEST_GRACE: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ListedTarget {
+	pub ip: String,
+	pub label: Option<String>,
+}
+
+/// A line that was left out of the list, and why.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RejectedLine {
+	pub line: usize,
+	pub content: String,
+	pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct TargetList {
+	pub targets: Vec<ListedTarget>,
+	pub rejected: Vec<RejectedLine>,
+}
+
+/// Reads a target list file. See `parse_target_list` for the format.
+pub fn load_target_list(path: &Path) -> io::Result<TargetList> {
+	Ok(parse_target_list(&std::fs::read_to_string(path)?))
+}
+
+/// One target per line: an IPv4 address or CIDR block, optionally followed by a label
+/// (`192.168.1.20 printer`, `10.0.5.0/29, lab rack`). Blank lines and `#` comments are
+/// skipped. Blocks expand to their host addresses. Anything outside the private ranges,
+/// duplicated, or past `MAX_LIST_TARGETS` is rejected rather than failing the whole file.
+pub fn parse_target_list(contents: &str) -> TargetList {
+	let mut list = TargetList::default();
+	let mut seen = HashSet::new();
+	for (index, raw) in contents.lines().enumerate() {
+		let content = raw.trim();
+		if content.is_empty() || content.starts_with('#') {
+			continue;
+		}
+		let reject = |reason: &str| RejectedLine { line: index + 1, content: content.to_string(), reason: reason.to_string() };
+
+		let (target, label) = match content.split_once(|c: char| c == ',' || c.is_whitespace()) {
+			Some((target, label)) => (target, label.trim_start_matches(',').trim()),
+			None => (content, ""),
+		};
+		let label = (!label.is_empty()).then(|| label.to_string());
+		let ips = match expand_target(target) {
+			Ok(ips) => ips,
+			Err(reason) => {
+				list.rejected.push(reject(reason));
+				continue;
+			}
+		};
+		if list.targets.len() + ips.len() > MAX_LIST_TARGETS {
+			list.rejected.push(reject("list would exceed the target limit"));
+			continue;
+		}
+
+		let before = list.targets.len();
+		for ip in ips.into_iter().filter(|ip| seen.insert(*ip)) {
+			list.targets.push(ListedTarget { ip: ip.to_string(), label: label.clone() });
+		}
+		if list.targets.len() == before {
+			list.rejected.push(reject("duplicate target"));
+		}
+	}
+	list
+}
+
+fn expand_target(target: &str) -> Result<Vec<Ipv4Addr>, &'static str> {
+	if target.contains('/') {
+		let network: Ipv4Network = target.parse().map_err(|_| "not an IPv4 address or CIDR block")?;
+		if !is_private_ip(&network.network()) || !is_private_ip(&network.broadcast()) {
+			return Err("not in a private network range");
+		}
+		if network.size() > MAX_LIST_TARGETS as u32 + 2 {
+			return Err("block is larger than the target limit");
+		}
+		let hosts = network
+			.iter()
+			.filter(|ip| network.prefix() >= 31 || (*ip != network.network() && *ip != network.broadcast()))
+			.collect();
+		return Ok(hosts);
+	}
+
+	match target.parse::<IpAddr>() {
+		Ok(IpAddr::V4(ip)) if is_private_ip(&ip) => Ok(vec![ip]),
+		Ok(IpAddr::V4(_)) => Err("not in a private network range"),
+		Ok(IpAddr::V6(_)) => Err("only IPv4 targets are supported"),
+		Err(_) => Err("not an IPv4 address or CIDR block"),
+	}
+}
+
+/// How one target of a suite went: the finished test, or why it couldn't run.
+#[derive(Serialize, Clone, Debug)]
+pub struct SuiteEntry {
+	pub target: ListedTarget,
+	pub result: Option<TestResult>,
+	pub error: Option<ErrorResponse>,
+}
+
+/// Runs `config` against each target in turn, waiting for one test to finish before
+/// starting the next. A target still cooling down is waited out once; every other start
+/// failure is recorded and the suite moves on. A test the user stops ends the suite.
+///
+/// Like `execute_batch`, the engine is only locked briefly at a time so the UI can keep
+/// confirming the dead man's switch or stop the running test.
+pub async fn run_test_suite(config: StressTestConfig, targets: Vec<ListedTarget>, engine: &Mutex<StressTestEngine>) -> Vec<SuiteEntry> {
+	let mut entries = Vec::with_capacity(targets.len());
+	for target in targets {
+		let config = StressTestConfig {
+			target_ip: target.ip.clone(),
+			targets: Vec::new(),
+			label: target.label.clone().or_else(|| config.label.clone()),
+			..config.clone()
+		};
+
+		let started = match start(engine, config.clone()).await {
+			Err(StressError::CooldownActive(remaining)) => {
+				tokio::time::sleep(Duration::from_secs(remaining + 1)).await;
+				start(engine, config.clone()).await
+			}
+			started => started,
+		};
+		let entry = match started {
+			Ok(test_id) => match wait_for_result(engine, &test_id, &config).await {
+				Some(result) => SuiteEntry { target, result: Some(result), error: None },
+				None => SuiteEntry { target, result: None, error: Some(ErrorResponse::from(StressError::TestNotFound(test_id))) },
+			},
+			Err(e) => SuiteEntry { target, result: None, error: Some(ErrorResponse::from(e)) },
+		};
+
+		let cancelled = entry.result.as_ref().is_some_and(|result| result.status == TestStatus::Cancelled);
+		entries.push(entry);
+		if cancelled {
+			break;
+		}
+	}
+	entries
+}
+
+async fn start(engine: &Mutex<StressTestEngine>, config: StressTestConfig) -> Result<String, StressError> {
+	engine.lock().await.start_stress_test(config).await
+}
+
+/// The result of `test_id` once it's no longer running. A test that overruns its duration
+/// by more than `SUITE_TEST_GRACE` is stopped.
+async fn wait_for_result(engine: &Mutex<StressTestEngine>, test_id: &str, config: &StressTestConfig) -> Option<TestResult> {
+	let deadline = tokio::time::Instant::now() + Duration::from_secs(u64::from(config.duration_seconds)) + SUITE_TEST_GRACE;
+	loop {
+		tokio::time::sleep(SUITE_POLL_INTERVAL).await;
+		let engine = engine.lock().await;
+		let current = engine.get_current_test().await.filter(|test| test.test_id == test_id);
+		match current {
+			Some(test) if test.status == TestStatus::Running => {
+				if tokio::time::Instant::now() >= deadline {
+					let _ = engine.stop_current_test().await;
+				}
+			}
+			other => return other,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_list_filters_bad_entries() {
+		let list = parse_target_list(
+			"# lab devices\n\
+			192.168.1.20 printer\n\
+			192.168.1.21,camera, front door\n\
+			\n\
+			8.8.8.8 dns\n\
+			not-an-ip\n\
+			fe80::1\n\
+			192.168.1.20 printer again\n\
+			10.0.5.0/30 lab rack\n\
+			192.168.255.0/15\n\
+			10.0.0.0/16\n",
+		);
+
+		let targets: Vec<(&str, Option<&str>)> =
+			list.targets.iter().map(|target| (target.ip.as_str(), target.label.as_deref())).collect();
+		assert_eq!(
+			targets,
+			vec![
+				("192.168.1.20", Some("printer")),
+				("192.168.1.21", Some("camera, front door")),
+				("10.0.5.1", Some("lab rack")),
+				("10.0.5.2", Some("lab rack")),
+			]
+		);
+
+		let rejected: Vec<(usize, &str)> = list.rejected.iter().map(|line| (line.line, line.reason.as_str())).collect();
+		assert_eq!(
+			rejected,
+			vec![
+				(5, "not in a private network range"),
+				(6, "not an IPv4 address or CIDR block"),
+				(7, "only IPv4 targets are supported"),
+				(8, "duplicate target"),
+				(10, "not in a private network range"),
+				(11, "block is larger than the target limit"),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_suite_runs_each_target() {
+		let engine = Mutex::new(StressTestEngine::new());
+		let config = StressTestConfig {
+			duration_seconds: 1,
+			skip_reachability_check: true,
+			label: Some("nightly".to_string()),
+			warmup_packets: Some(0),
+			..Default::default()
+		};
+		let targets = parse_target_list("192.168.1.20 printer\n192.168.1.21\n").targets;
+
+		let entries = run_test_suite(config, targets, &engine).await;
+		assert_eq!(entries.len(), 2);
+		let results: Vec<&TestResult> = entries.iter().map(|entry| entry.result.as_ref().unwrap()).collect();
+		assert_eq!(results[0].target_ip, "192.168.1.20");
+		assert_eq!(results[0].label.as_deref(), Some("printer"));
+		assert_eq!(results[1].target_ip, "192.168.1.21");
+		assert_eq!(results[1].label.as_deref(), Some("nightly"));
+		assert!(results.iter().all(|result| result.status == TestStatus::Completed));
+	}
+}
@@ -4,17 +4,42 @@ import {
   TestStatus,
   TestMetrics,
   TestResult,
+  SafetyLimits,
+  EngineStateSnapshot,
+  TestComparison,
+  HistoryFilter,
+  TargetValidation,
+  SafetyWarning,
   StressError,
+  StressErrorResponse,
+  STRESS_ERROR_CODES,
 } from "@/types/stresser";
 
 // Custom error class for stress test operations
 export class StressTestError extends Error {
-  constructor(public errorType: StressError, message: string) {
+  constructor(
+    public errorType: StressError,
+    message: string,
+    public details?: Record<string, unknown> | null
+  ) {
     super(message);
     this.name = "StressTestError";
   }
 }
 
+function isStressErrorResponse(error: unknown): error is StressErrorResponse {
+  return typeof error === "object" && error !== null && "code" in error && "message" in error;
+}
+
+// Map a structured backend error onto a StressTestError
+function toStressTestError(error: unknown, fallbackMessage: string): StressTestError {
+  if (isStressErrorResponse(error)) {
+    const errorType = STRESS_ERROR_CODES[error.code] ?? "InternalError";
+    return new StressTestError(errorType, error.message, error.details);
+  }
+  return new StressTestError("InternalError", `${fallbackMessage}: ${error}`);
+}
+
 // Retry configuration
 const RETRY_CONFIG = {
   maxRetries: 3,
@@ -49,7 +74,34 @@ export async function validateStressTarget(ip: string): Promise<boolean> {
     return isValid;
   } catch (error) {
     console.error("Failed to validate stress target:", error);
-    throw new StressTestError("InvalidTargetIp", `Invalid target IP: ${error}`);
+    throw toStressTestError(error, "Invalid target IP");
+  }
+}
+
+export async function validateTargets(ips: string[]): Promise<TargetValidation[]> {
+  try {
+    return await invoke<TargetValidation[]>("validate_targets", { ips });
+  } catch (error) {
+    console.error("Failed to validate targets:", error);
+    throw new StressTestError("InternalError", `Failed to validate targets: ${error}`);
+  }
+}
+
+export async function getTargetCooldown(ip: string): Promise<number> {
+  try {
+    return await invoke<number>("get_target_cooldown", { ip });
+  } catch (error) {
+    console.error("Failed to get target cooldown:", error);
+    throw new StressTestError("InternalError", `Failed to get target cooldown: ${error}`);
+  }
+}
+
+export async function validateStressConfig(config: StressTestConfig): Promise<SafetyWarning[]> {
+  try {
+    return await invoke<SafetyWarning[]>("validate_stress_config", { config });
+  } catch (error) {
+    console.error("Failed to validate stress config:", error);
+    throw toStressTestError(error, "Invalid stress test config");
   }
 }
 
@@ -63,30 +115,18 @@ export async function startStressTest(config: StressTestConfig): Promise<string>
     return testId;
   } catch (error) {
     console.error("Failed to start stress test:", error);
+    throw toStressTestError(error, "Failed to start test");
+  }
+}
 
-    // Parse the error message to determine the error type
-    const errorMessage = error as string;
-    let errorType: StressError = "InternalError";
-
-    if (errorMessage.includes("Target IP is not in a private network")) {
-      errorType = "InvalidTargetIp";
-    } else if (errorMessage.includes("Test already running")) {
-      errorType = "TestAlreadyRunning";
-    } else if (errorMessage.includes("Rate limit exceeded")) {
-      errorType = "RateLimitExceeded";
-    } else if (errorMessage.includes("Test duration too long")) {
-      errorType = "DurationTooLong";
-    } else if (errorMessage.includes("Cooldown period active")) {
-      errorType = "CooldownActive";
-    } else if (errorMessage.includes("Resource limit exceeded")) {
-      errorType = "ResourceLimitExceeded";
-    } else if (errorMessage.includes("Dead man's switch")) {
-      errorType = "DeadMansSwitchTriggered";
-    } else if (errorMessage.includes("Network error")) {
-      errorType = "NetworkError";
-    }
-
-    throw new StressTestError(errorType, errorMessage);
+export async function rerunLastTest(): Promise<string> {
+  try {
+    const testId = await invoke<string>("rerun_last_test");
+    console.log(`Re-ran last stress test: ${testId}`);
+    return testId;
+  } catch (error) {
+    console.error("Failed to re-run last stress test:", error);
+    throw toStressTestError(error, "Failed to re-run last test");
   }
 }
 
@@ -98,7 +138,17 @@ export async function stopStressTest(): Promise<void> {
     console.log("Stress test stopped");
   } catch (error) {
     console.error("Failed to stop stress test:", error);
-    throw new StressTestError("InternalError", `Failed to stop test: ${error}`);
+    throw toStressTestError(error, "Failed to stop test");
+  }
+}
+
+// Clears cooldowns, metrics and the current test. Refused while a test is running.
+export async function resetEngineState(): Promise<void> {
+  try {
+    await invoke<void>("reset_engine_state");
+  } catch (error) {
+    console.error("Failed to reset engine state:", error);
+    throw toStressTestError(error, "Failed to reset engine state");
   }
 }
 
@@ -130,6 +180,60 @@ export async function getCurrentStressTest(): Promise<TestResult | null> {
   }
 }
 
+export async function getSafetyLimits(): Promise<SafetyLimits> {
+  try {
+    return await invoke<SafetyLimits>("get_safety_limits");
+  } catch (error) {
+    console.error("Failed to get safety limits:", error);
+    throw new StressTestError("InternalError", `Failed to get safety limits: ${error}`);
+  }
+}
+
+export async function getEngineSnapshot(): Promise<EngineStateSnapshot> {
+  try {
+    return await invoke<EngineStateSnapshot>("get_engine_snapshot");
+  } catch (error) {
+    console.error("Failed to get engine snapshot:", error);
+    throw new StressTestError("InternalError", `Failed to get engine snapshot: ${error}`);
+  }
+}
+
+export async function getTestHistory(): Promise<TestResult[]> {
+  try {
+    return await invoke<TestResult[]>("get_test_history");
+  } catch (error) {
+    console.error("Failed to get test history:", error);
+    throw new StressTestError("InternalError", `Failed to get test history: ${error}`);
+  }
+}
+
+export async function queryTestHistory(filter: HistoryFilter): Promise<TestResult[]> {
+  try {
+    return await invoke<TestResult[]>("query_test_history", { filter });
+  } catch (error) {
+    console.error("Failed to query test history:", error);
+    throw new StressTestError("InternalError", `Failed to query test history: ${error}`);
+  }
+}
+
+export async function compareTests(idA: string, idB: string): Promise<TestComparison> {
+  try {
+    return await invoke<TestComparison>("compare_tests", { idA, idB });
+  } catch (error) {
+    console.error("Failed to compare tests:", error);
+    throw toStressTestError(error, "Failed to compare tests");
+  }
+}
+
+export async function setBaseline(testId: string): Promise<void> {
+  try {
+    await invoke<void>("set_baseline", { testId });
+  } catch (error) {
+    console.error("Failed to set baseline:", error);
+    throw toStressTestError(error, "Failed to set baseline");
+  }
+}
+
 // Dead man's switch API
 export async function confirmStressAlive(): Promise<void> {
   try {
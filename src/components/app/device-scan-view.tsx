@@ -1,6 +1,7 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { Device } from "@/types";
+import { listen } from "@tauri-apps/api/event";
+import type { Device, ScanSummary } from "@/types";
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
@@ -23,22 +24,31 @@ export function DeviceScanView() {
         const manufacturer = ex.manufacturer === "Unknown" && d.manufacturer !== "Unknown" ? d.manufacturer : ex.manufacturer;
         const ip_address = ex.ip_address === d.ip_address ? ex.ip_address : d.ip_address;
         const hostname = ex.hostname === "Unknown" && d.hostname !== "Unknown" ? d.hostname : ex.hostname;
-        map.set(d.mac_address, { ...ex, ip_address, manufacturer, hostname });
+        map.set(d.mac_address, { ...ex, ip_address, manufacturer, hostname, friendly_name: d.friendly_name });
       }
     }
     return Array.from(map.values()).sort((a, b) => a.ip_address.localeCompare(b.ip_address));
   };
 
+  // Devices are streamed in via "device-found" events while the scan runs
+  const listenForDevices = () =>
+    listen<Device>("device-found", (event) => {
+      setDevices((prev) => mergeDevices(prev, [event.payload]));
+    });
+
   const handleScan = async () => {
     setIsLoading(true);
     setError(null);
+    setDevices([]);
+    const unlisten = await listenForDevices();
     try {
-      const result = await invoke<Device[]>("scan_network");
-      setDevices(result);
+      const result = await invoke<ScanSummary>("scan_network");
+      setDevices(result.devices);
     } catch (err: unknown) {
       setError(err instanceof Error ? err.message : String(err));
       setDevices([]);
     } finally {
+      unlisten();
       setIsLoading(false);
     }
   };
@@ -46,18 +56,21 @@ export function DeviceScanView() {
   const handleRepeatScan = async () => {
     setIsLoading(true);
     setError(null);
+    const unlisten = await listenForDevices();
     try {
-      const result = await invoke<Device[]>("scan_network");
-      setDevices((prev) => mergeDevices(prev, result));
+      // An explicit re-scan bypasses the backend's scan cache
+      const result = await invoke<ScanSummary>("scan_network", { force: true });
+      setDevices((prev) => mergeDevices(prev, result.devices));
     } catch (err: unknown) {
       setError(err instanceof Error ? err.message : String(err));
     } finally {
+      unlisten();
       setIsLoading(false);
     }
   };
 
   const renderContent = () => {
-    if (isLoading) {
+    if (isLoading && devices.length === 0) {
       return (
         <div className="space-y-2">
           {[...Array(5)].map((_, i) => (
@@ -110,7 +123,7 @@ export function DeviceScanView() {
               <TableCell className="font-mono">{device.ip_address}</TableCell>
               <TableCell className="font-mono">{device.mac_address}</TableCell>
               <TableCell>{device.manufacturer}</TableCell>
-              <TableCell className="font-mono">{device.hostname}</TableCell>
+              <TableCell className="font-mono">{device.friendly_name ?? device.hostname}</TableCell>
             </TableRow>
           ))}
         </TableBody>
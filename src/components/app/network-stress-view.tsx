@@ -134,6 +134,7 @@ function NetworkStressContent() {
                       <p className={`text-xs font-medium ${
                         test.status === TestStatus.Completed ? 'text-green-600' :
                         test.status === TestStatus.Failed ? 'text-red-600' :
+                        test.status === TestStatus.Cancelled ? 'text-yellow-600' :
                         'text-gray-600'
                       }`}>
                         {test.status}
@@ -7,6 +7,7 @@ import {
   Square,
   Zap,
   Shield,
+  Gauge,
   Target,
   Timer,
   TrendingUp,
@@ -40,6 +41,8 @@ const iconMap = {
   Activity,
   Clock,
   AlertTriangle,
+  Shield,
+  Gauge,
 } as const;
 
 export function StressTestInterface({ onEmergencyStop }: StressTestInterfaceProps) {
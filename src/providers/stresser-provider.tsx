@@ -60,6 +60,25 @@ const initialState: StresserState = {
     packets_sent: 0,
     packets_received: 0,
     timestamp: 0,
+    latency_p95_ms: 0,
+    bytes_sent: 0,
+    current_rate_pps: 0,
+    in_flight: 0,
+    per_target: [],
+    required_jitter_buffer_ms: 0,
+    average_rate_pps: 0,
+    ttl_varied: false,
+    distinct_ttls: [],
+    late_replies: 0,
+    warmup_packets: 0,
+    ecn_marked_count: 0,
+    quality_grade: null,
+    duplicate_replies: 0,
+    owd_estimate_ms: null,
+    playout_underruns: 0,
+    playout_discards: 0,
+    bufferbloat_ms: null,
+    bufferbloat_grade: null,
   },
   history: [],
   isLoading: false,
@@ -99,8 +118,10 @@ function stresserReducer(state: StresserState, action: StresserAction): Stresser
       return {
         ...state,
         currentTest: updatedTest,
-        // If test is completed or failed, add to history
-        ...(updatedTest.status === TestStatus.Completed || updatedTest.status === TestStatus.Failed
+        // If test is completed, cancelled or failed, add to history
+        ...(updatedTest.status === TestStatus.Completed ||
+        updatedTest.status === TestStatus.Cancelled ||
+        updatedTest.status === TestStatus.Failed
           ? { history: [updatedTest, ...state.history.slice(0, 99)] }
           : {}),
       };
@@ -294,13 +315,13 @@ export function StresserProvider({ children }: StresserProviderProps) {
 
     try {
       await stressTestApi.stopTest();
-      dispatch({ type: 'SET_STATUS', payload: TestStatus.Completed });
+      dispatch({ type: 'SET_STATUS', payload: TestStatus.Cancelled });
 
       if (state.currentTest) {
         dispatch({
           type: 'UPDATE_TEST',
           payload: {
-            status: TestStatus.Completed,
+            status: TestStatus.Cancelled,
             end_time: Date.now(),
           },
         });
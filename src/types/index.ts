@@ -1,12 +1,171 @@
 // src/types/index.ts
+import type { StressErrorResponse, StressTestConfig, TestResult, TestStatus } from './stresser';
+
 export interface Device {
   ip_address: string;
   mac_address: string;
   manufacturer: string;
   hostname: string;
+  interface_name: string;
+  mac_conflict: boolean;
+  source: "LiveReply" | "ArpCache" | "PassiveObservation";
+  friendly_name: string | null; // set with set_device_alias
+  answered_on: string[]; // filled by scan_all_interfaces
+  vlan_id: number | null; // 802.1Q tag on the reply, if any
+}
+
+// export_devices output: CSV for spreadsheets, JSON for other tools
+export type ExportFormat = "csv" | "json";
+
+export interface ScanOptions {
+  randomize_order?: boolean;
+  seed?: number | null;
+  online_oui_lookup?: boolean;
+  exclude_ips?: string[];
+  exclude_macs?: string[]; // full MACs or vendor prefixes
+  max_hosts?: number | null;
+  channel_retries?: number | null; // at most 10
+  channel_retry_delay_ms?: number | null; // at most 10000
+  read_buffer_size?: number | null; // bytes; defaults to 256 KiB, kept between 4 KiB and 16 MiB
+  source_ip?: string | null; // which of the interface's IPv4 addresses to scan from
+  promiscuous?: boolean; // also learn devices from replies meant for other hosts
+  interfaces?: string[]; // scan_all_interfaces only; empty means every usable interface
+  record_to?: string; // save the ARP replies received, for scan_from_recording
+  own_manufacturer_from_oui?: boolean; // look up this machine's vendor by MAC instead of showing its adapter name
+}
+
+export interface InterfaceScanError {
+  interface_name: string;
+  message: string;
+}
+
+// Several MACs answering ARP for one IP
+export interface IpConflict {
+  ip_address: string;
+  mac_addresses: string[];
+  vlan_id: number | null; // the VLAN the conflicting replies were tagged with
+}
+
+export interface ScanSummary {
+  devices: Device[];
+  ip_conflicts: IpConflict[];
+  hosts_probed: number;
+  subnet_hosts: number;
+  promiscuous: boolean; // false when requested but unavailable
+  stress_test_running: boolean; // the scan may have skewed a running test's measurements
+}
+
+export interface MultiInterfaceScan {
+  devices: Device[];
+  ip_conflicts: IpConflict[];
+  errors: InterfaceScanError[];
+  stress_test_running: boolean;
+}
+
+// Projected scan cost, from estimate_scan_time
+export interface ScanEstimate {
+  network: string;
+  host_count: number;
+  estimated_seconds: number;
 }
 
 // Re-export stresser types
 export * from './stresser';
 
 
+
+export interface SystemStatus {
+  raw_sockets_available: boolean;
+  sufficient_privileges: boolean;
+  default_interface: string | null;
+  engine_status: TestStatus;
+  oui_entries: number;
+  problems: string[];
+}
+
+// Loopback-only smoke test, one entry per check
+export interface SelfTestCheck {
+  name: "loopback_ping" | "oui_database" | "raw_sockets" | "stress_engine";
+  passed: boolean;
+  detail: string;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  checks: SelfTestCheck[];
+}
+
+export type TimestampProbe =
+  | { result: 'Measured'; round_trip_ms: number; clock_offset_ms: number }
+  | { result: 'NotSupported'; reason: string };
+
+// Per-interface gateway health, from diagnose_gateways
+export interface GatewayDiagnosis {
+  interface_name: string;
+  gateway_ip: string | null;
+  gateway_mac: string | null;
+  is_default: boolean;
+  reachable: boolean;
+  latency_ms: number | null;
+  multiple_default_routes: boolean;
+  problems: string[];
+}
+
+// Path MTU to a host, from discover_path_mtu. Sizes include IP headers.
+export type PathMtu =
+  | { result: 'Discovered'; mtu: number }
+  | { result: 'Indeterminate'; at_least: number | null; reason: string };
+
+// Result of measure_latency; latencies_ms has null for unanswered pings
+export interface LatencyMeasurement {
+  sent: number;
+  received: number;
+  latencies_ms: (number | null)[];
+  min_ms: number | null;
+  avg_ms: number | null;
+  max_ms: number | null;
+}
+
+// One step of a traceroute; ip and latency_ms are null for a hop that didn't answer
+export interface Hop {
+  ttl: number;
+  ip: string | null;
+  latency_ms: number | null;
+}
+
+// Scripted workflows, run in one call by execute_batch
+export type BatchOperation =
+  | { op: 'scan'; options?: ScanOptions }
+  | { op: 'validate'; ip: string }
+  | { op: 'start_test'; config: StressTestConfig }
+  | { op: 'wait'; ms: number }
+  | { op: 'stop' };
+
+export type BatchStep = BatchOperation & { required?: boolean };
+
+export interface BatchStepResult {
+  op: string;
+  ok: boolean;
+  result: unknown;
+  error: unknown;
+}
+
+// Target list files for run_test_suite: one IP or CIDR block per line, optional label
+export interface ListedTarget {
+  ip: string;
+  label: string | null;
+}
+
+export interface TargetList {
+  targets: ListedTarget[];
+  rejected: { line: number; content: string; reason: string }[];
+}
+
+export interface SuiteEntry {
+  target: ListedTarget;
+  result: TestResult | null;
+  error: StressErrorResponse | null;
+}
+
+// Interface used when none is named, for both scans and stress tests
+export type InterfacePolicy = 'system_default' | 'prefer_wired';
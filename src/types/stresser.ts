@@ -5,6 +5,8 @@ export enum TestType {
   BandwidthTest = "BandwidthTest",
   LatencyTest = "LatencyTest",
   PacketLoss = "PacketLoss",
+  SynFlood = "SynFlood",
+  Bufferbloat = "Bufferbloat",
 }
 
 export enum Intensity {
@@ -18,9 +20,20 @@ export enum TestStatus {
   Running = "Running",
   Paused = "Paused",
   Completed = "Completed",
+  Cancelled = "Cancelled", // stopped by the user
   Failed = "Failed",
 }
 
+export type TerminationReason =
+  | "DurationReached"
+  | "ByteCapReached"
+  | "UserCancelled"
+  | "DeadMansSwitch"
+  | "CircuitBreaker"
+  | "SustainedUnhealthy"
+  | "ResourceLimit"
+  | "Error";
+
 export interface TestMetrics {
   latency_ms: number;
   packet_loss_percentage: number;
@@ -29,11 +42,54 @@ export interface TestMetrics {
   packets_sent: number;
   packets_received: number;
   timestamp: number;
+  latency_p95_ms: number;
+  bytes_sent: number;
+  current_rate_pps: number;
+  in_flight: number;
+  per_target: TargetMetrics[];
+  required_jitter_buffer_ms: number;
+  average_rate_pps: number;
+  ttl_varied: boolean;
+  distinct_ttls: number[];
+  late_replies: number;
+  warmup_packets: number;
+  ecn_marked_count: number;
+  quality_grade: QualityGrade | null;
+  duplicate_replies: number;
+  owd_estimate_ms: number | null; // only when probing a timestamping reflector
+  playout_underruns: number;
+  playout_discards: number;
+  bufferbloat_ms: number | null; // Bufferbloat tests: latency added at full load
+  bufferbloat_grade: QualityGrade | null;
+}
+
+export type QualityGrade = "Excellent" | "Good" | "Fair" | "Poor";
+
+// Inclusive upper bound of each grade; anything above `fair` is Poor
+export interface GradeCutoffs {
+  excellent: number;
+  good: number;
+  fair: number;
+}
+
+export interface QualityThresholds {
+  latency_ms: GradeCutoffs;
+  jitter_ms: GradeCutoffs;
+  packet_loss_percentage: GradeCutoffs;
+}
+
+export interface TargetMetrics {
+  target_ip: string;
+  packets_sent: number;
+  packets_received: number;
+  packet_loss_percentage: number;
+  latency_ms: number;
 }
 
 export interface TestResult {
   test_id: string;
   target_ip: string;
+  target_hostname?: string | null; // set when target_ip was resolved from a name
   test_type: TestType;
   intensity: Intensity;
   duration_seconds: number;
@@ -42,13 +98,126 @@ export interface TestResult {
   final_metrics?: TestMetrics;
   status: TestStatus;
   error_message?: string;
+  source_interface?: string | null;
+  source_ip?: string | null;
+  label?: string | null;
+  notes?: string | null;
+  stop_reason?: string | null;
+  termination_reason?: TerminationReason | null;
+  dscp?: number | null;
+  ecn_capable?: boolean;
+  config?: StressTestConfig | null; // missing on older history entries
+  replay_of?: string | null;
+  metric_series?: MetricSample[];
+  concurrent_scan_during_test?: boolean; // a scan's ARP traffic may have skewed the results
+  vs_baseline?: TestComparison | null; // against the first successful or pinned test of the same target and test type
 }
 
+// One point per second of a test, downsampled by get_metric_sparkline
+export interface MetricSample {
+  elapsed_ms: number;
+  latency_ms: number;
+  jitter_ms: number;
+  packet_loss_percentage: number;
+  throughput_mbps: number;
+}
+
+export type SparklineMetric = "latency" | "jitter" | "packet_loss" | "throughput";
+
 export interface StressTestConfig {
-  target_ip: string;
+  target_ip: string; // IPv4 address or a hostname resolving to a private one
   test_type: TestType;
   intensity: Intensity;
   duration_seconds: number;
+  adaptive?: boolean;
+  skip_reachability_check?: boolean;
+  payload_size?: number | null;
+  dont_fragment?: boolean;
+  targets?: WeightedTarget[];
+  target_port?: number | null;
+  confirmed_sensitive_target?: boolean;
+  label?: string | null;
+  notes?: string | null;
+  dscp?: number | null; // 0-63
+  burst_on_ms?: number | null;
+  burst_off_ms?: number | null;
+  reply_window_ms?: number | null;
+  warmup_packets?: number | null;
+  ecn?: boolean;
+  quality_thresholds?: QualityThresholds | null;
+  alert_thresholds?: AlertThresholds | null;
+  sustained_abort?: SustainedAbort | null;
+  source_ip?: string | null; // must be an address of this host
+  owd_reflector_port?: number | null; // UDP port of a one-way delay reflector on the target
+  playout_buffer_ms?: number | null; // simulated playout buffer depth, latency/ping tests
+  dead_mans_switch_seconds?: number | null; // per-test confirmation interval, at least 5 s and at most the global one
+  replay_of?: string | null; // set by replay_test
+}
+
+// Live alerting while a test runs; unset metrics aren't watched
+export interface AlertThresholds {
+  latency_ms?: number | null;
+  packet_loss_percentage?: number | null;
+  jitter_ms?: number | null;
+  hysteresis_percent?: number | null; // defaults to 10
+}
+
+// Fails the test once a limit stays exceeded for the whole window; unset metrics aren't watched
+export interface SustainedAbort {
+  packet_loss_percentage?: number | null; // over the last 10 packets sent
+  latency_ms?: number | null;
+  sustained_seconds?: number | null; // defaults to 10
+}
+
+export type AlertMetric = "Latency" | "PacketLoss" | "Jitter";
+
+// Payload of the stress_test_alert and stress_test_alert_clear events
+export interface AlertEvent {
+  metric: AlertMetric;
+  kind: "Alert" | "Clear";
+  value: number;
+  threshold: number;
+}
+
+export interface WeightedTarget {
+  ip: string;
+  weight: number;
+}
+
+export interface MtuInfo {
+  interface: string | null;
+  mtu: number;
+  max_payload: number;
+}
+
+// Engine internals for diagnostics, from get_engine_snapshot
+export interface TargetCooldown {
+  target_ip: string;
+  remaining_seconds: number;
+}
+
+export interface EngineStateSnapshot {
+  current_test: TestResult | null;
+  metrics: TestMetrics;
+  seconds_since_update: number;
+  seconds_since_confirmation: number;
+  dead_mans_switch_remaining_seconds: number | null; // null when no test is running
+  cooldowns: TargetCooldown[];
+}
+
+export interface SafetyLimits {
+  max_packets_per_second: number;
+  max_duration_seconds: number;
+  min_cooldown_seconds: number;
+  max_cpu_percent: number;
+  max_memory_percent: number;
+  dead_mans_switch_interval_seconds: number;
+  max_in_flight_pings: number;
+  allowed_test_types: TestType[];
+  max_total_bytes: number | null;
+  max_total_packets: number | null;
+  reject_automated_confirmations: boolean;
+  soft_limit_ratio: number;
 }
 
 // UI-specific types
@@ -70,16 +239,109 @@ export interface SafetyConfirmation {
 // Error types
 export type StressError =
   | "InvalidTargetIp"
+  | "Ipv6NotSupported"
+  | "InvalidTargetWeight"
   | "TestAlreadyRunning"
   | "RateLimitExceeded"
   | "DurationTooLong"
   | "CooldownActive"
   | "ResourceLimitExceeded"
   | "NetworkError"
+  | "TargetUnreachable"
+  | "PayloadExceedsMtu"
+  | "PayloadTooLarge"
+  | "PacketBudgetExceeded"
   | "DeadMansSwitchTriggered"
+  | "CircuitBreakerTriggered"
+  | "SustainedUnhealthy"
   | "UserCancelled"
+  | "NoPreviousTest"
+  | "TestTypeNotAllowed"
+  | "ConfirmationRequired"
+  | "TestNotFound"
+  | "InsufficientPrivileges"
+  | "InvalidDscp"
+  | "InvalidSourceIp"
+  | "HostnameUnresolved"
   | "InternalError";
 
+// Structured error returned by the stress test commands
+export interface StressErrorResponse {
+  code: string;
+  message: string;
+  details?: Record<string, unknown> | null;
+}
+
+export const STRESS_ERROR_CODES: Record<string, StressError> = {
+  INVALID_TARGET_IP: "InvalidTargetIp",
+  IPV6_NOT_SUPPORTED: "Ipv6NotSupported",
+  INVALID_TARGET_WEIGHT: "InvalidTargetWeight",
+  TEST_ALREADY_RUNNING: "TestAlreadyRunning",
+  RATE_LIMIT: "RateLimitExceeded",
+  DURATION_TOO_LONG: "DurationTooLong",
+  COOLDOWN_ACTIVE: "CooldownActive",
+  RESOURCE_LIMIT: "ResourceLimitExceeded",
+  NETWORK_ERROR: "NetworkError",
+  TARGET_UNREACHABLE: "TargetUnreachable",
+  PAYLOAD_EXCEEDS_MTU: "PayloadExceedsMtu",
+  PAYLOAD_TOO_LARGE: "PayloadTooLarge",
+  PACKET_BUDGET_EXCEEDED: "PacketBudgetExceeded",
+  DEAD_MANS_SWITCH: "DeadMansSwitchTriggered",
+  CIRCUIT_BREAKER: "CircuitBreakerTriggered",
+  SUSTAINED_UNHEALTHY: "SustainedUnhealthy",
+  USER_CANCELLED: "UserCancelled",
+  NO_PREVIOUS_TEST: "NoPreviousTest",
+  TEST_TYPE_NOT_ALLOWED: "TestTypeNotAllowed",
+  CONFIRMATION_REQUIRED: "ConfirmationRequired",
+  TEST_NOT_FOUND: "TestNotFound",
+  INSUFFICIENT_PRIVILEGES: "InsufficientPrivileges",
+  INVALID_DSCP: "InvalidDscp",
+  INVALID_SOURCE_IP: "InvalidSourceIp",
+  HOSTNAME_UNRESOLVED: "HostnameUnresolved",
+  INTERNAL_ERROR: "InternalError",
+};
+
+// Metric-by-metric diff of two tests from the history
+export type Verdict = "Better" | "Worse" | "Unchanged";
+
+export interface MetricDelta {
+  metric: string;
+  a: number;
+  b: number;
+  absolute_delta: number;
+  percent_delta: number | null;
+  verdict: Verdict;
+}
+
+// A config that passed validate_stress_config but comes close to a hard limit
+export type SafetyWarning =
+  | { kind: "near_rate_limit"; packets_per_second: number; max: number }
+  | { kind: "near_duration_limit"; seconds: number; max: number }
+  | { kind: "near_packet_budget"; projected: number; max: number }
+  | { kind: "concurrent_scan" };
+
+// One entry of validate_targets, in the order the addresses were given
+export interface TargetValidation {
+  ip: string;
+  valid: boolean;
+  error: StressErrorResponse | null;
+}
+
+// Narrows get_test_history; unset fields match everything
+export interface HistoryFilter {
+  target_ip?: string | null;
+  label?: string | null;
+  limit?: number | null;
+}
+
+export interface TestComparison {
+  test_a: string;
+  test_b: string;
+  label_a: string | null;
+  label_b: string | null;
+  metrics: MetricDelta[];
+}
+
 // Event types for real-time updates
 export interface StressTestUpdateEvent {
   metrics: TestMetrics;
@@ -143,6 +405,18 @@ export const TEST_TYPE_INFO: Record<TestType, TestTypeInfo> = {
     primaryMetric: "packet_loss_percentage",
     icon: "AlertTriangle",
   },
+  [TestType.SynFlood]: {
+    name: "SYN Flood",
+    description: "Raw TCP SYNs to test firewall SYN-flood resilience (opt-in only)",
+    primaryMetric: "packet_loss_percentage",
+    icon: "Shield",
+  },
+  [TestType.Bufferbloat]: {
+    name: "Bufferbloat",
+    description: "How much latency grows when the link is busy",
+    primaryMetric: "bufferbloat_ms",
+    icon: "Gauge",
+  },
 };
 
 // Safety limits constants